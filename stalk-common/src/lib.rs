@@ -98,17 +98,1505 @@ pub struct RawXdpEvent {
 #[repr(C)]
 pub struct RawExitEvent {
     pub pid: u32,
+    pub comm: [u8; 16],
+    /// Wait status as stored in `task->exit_code`.
     pub exit_code: u64,
 }
 
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProcessEventKind {
+    Fork,
+    Exec,
+    Exit,
+}
+
+#[repr(C)]
+pub struct RawProcessEvent {
+    pub kind: ProcessEventKind,
+    /// Thread id of the subject: the child for fork, the current task otherwise.
+    pub pid: u32,
+    /// Thread group id of the subject; the child's own pid for fork, as new threads are not
+    /// reported.
+    pub tgid: u32,
+    /// Thread group id of the forking parent, only set for fork.
+    pub ppid: u32,
+    pub comm: [u8; 16],
+    pub filename: [u8; 64],
+}
+
+/// `task:task_newtask`, which runs in the parent before `sched_process_fork`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct TaskNewtaskInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub pid: i32,
+    pub comm: [u8; 16],
+    pub clone_flags: u64,
+    pub oom_score_adj: i16,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SchedProcessForkInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub parent_comm: [u8; 16],
+    pub parent_pid: i32,
+    pub child_comm: [u8; 16],
+    pub child_pid: i32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SchedProcessExecInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    /// `__data_loc char[]`: offset in the low 16 bits, length in the high 16 bits.
+    pub filename_loc: u32,
+    pub pid: i32,
+    pub old_pid: i32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SchedProcessExitInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub comm: [u8; 16],
+    pub pid: i32,
+    pub prio: i32,
+}
+
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SignalEventKind {
+    Generate,
+    Deliver,
+}
+
+#[repr(C)]
+pub struct RawSignalEvent {
+    pub kind: SignalEventKind,
+    /// Thread group id of the current task: the sender for generate, the receiver for deliver.
+    pub tgid: u32,
+    pub comm: [u8; 16],
+    /// Target thread and its process, only set for generate.
+    pub target_pid: u32,
+    pub target_tgid: u32,
+    /// Name of the target process.
+    pub target_comm: [u8; 16],
+    pub sig: i32,
+    pub errno: i32,
+    pub code: i32,
+    /// `TRACE_SIGNAL_*` result, only set for generate.
+    pub result: i32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SignalDeliverInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub sig: i32,
+    pub errno: i32,
+    pub code: i32,
+    pub padding: u32,
+    pub sa_handler: u64,
+    pub sa_flags: u64,
+}
+
+/// Layout shared by every `syscalls:sys_exit_*` tracepoint.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysExitInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub ret: i64,
+}
+
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FileOpKind {
+    Unlink,
+    Rmdir,
+    Rename,
+    Link,
+    Symlink,
+    Mkdir,
+    Truncate,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawFileOpEvent {
+    pub kind: FileOpKind,
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: [u8; 16],
+    /// Directory `path` is relative to, or the file itself for ftruncate.
+    pub dfd: i32,
+    /// Directory `path2` is relative to.
+    pub dfd2: i32,
+    pub path: [u8; 64],
+    /// Destination of rename/link/symlink.
+    pub path2: [u8; 64],
+    /// Mode for mkdir, length for truncate, flags otherwise.
+    pub arg: u64,
+    pub ret: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterUnlinkatInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub dfd: i64,
+    pub pathname: *const core::ffi::c_char,
+    pub flag: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterRenameat2Info {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub olddfd: i64,
+    pub oldname: *const core::ffi::c_char,
+    pub newdfd: i64,
+    pub newname: *const core::ffi::c_char,
+    pub flags: u64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterLinkatInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub olddfd: i64,
+    pub oldname: *const core::ffi::c_char,
+    pub newdfd: i64,
+    pub newname: *const core::ffi::c_char,
+    pub flags: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterSymlinkatInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub oldname: *const core::ffi::c_char,
+    pub newdfd: i64,
+    pub newname: *const core::ffi::c_char,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterMkdiratInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub dfd: i64,
+    pub pathname: *const core::ffi::c_char,
+    pub mode: u64,
+}
+
+/// Layout of `rename`, `link` and `symlink`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterRenameInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub oldname: *const core::ffi::c_char,
+    pub newname: *const core::ffi::c_char,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterRenameatInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub olddfd: i64,
+    pub oldname: *const core::ffi::c_char,
+    pub newdfd: i64,
+    pub newname: *const core::ffi::c_char,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterMkdirInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub pathname: *const core::ffi::c_char,
+    pub mode: u64,
+}
+
+/// Layout of `rmdir` and `unlink`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterRmdirInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub pathname: *const core::ffi::c_char,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterUnlinkInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub pathname: *const core::ffi::c_char,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterLinkInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub oldname: *const core::ffi::c_char,
+    pub newname: *const core::ffi::c_char,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterSymlinkInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub oldname: *const core::ffi::c_char,
+    pub newname: *const core::ffi::c_char,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterTruncateInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub path: *const core::ffi::c_char,
+    pub length: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterFtruncateInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub fd: u64,
+    pub length: i64,
+}
+
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PermOpKind {
+    Chmod,
+    Chown,
+    SetXattr,
+    RemoveXattr,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawPermEvent {
+    pub kind: PermOpKind,
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: [u8; 16],
+    /// Directory fd `path` is relative to, or the target fd when `path` is empty.
+    pub dfd: i32,
+    pub mode: u32,
+    /// Mode of the inode before a chmod, `u32::MAX` if the syscall failed before reaching it.
+    pub old_mode: u32,
+    pub owner: u32,
+    pub group: u32,
+    pub path: [u8; 64],
+    /// Xattr name.
+    pub name: [u8; 32],
+    pub ret: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterFchmodatInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub dfd: i64,
+    pub filename: *const core::ffi::c_char,
+    pub mode: u64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterFchownatInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub dfd: i64,
+    pub filename: *const core::ffi::c_char,
+    pub user: u64,
+    pub group: u64,
+    pub flag: i64,
+}
+
+/// Layout of `sys_enter_chmod`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterChmodInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub filename: u64,
+    pub mode: u64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterFchmodInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub fd: u64,
+    pub mode: u64,
+}
+
+/// Layout of `sys_enter_chown` and `sys_enter_lchown`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterChownInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub filename: u64,
+    pub user: u64,
+    pub group: u64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterFchownInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub fd: u64,
+    pub user: u64,
+    pub group: u64,
+}
+
+/// Layout of `sys_enter_setxattr` and `sys_enter_lsetxattr`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterSetxattrInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub pathname: u64,
+    pub name: *const core::ffi::c_char,
+    pub value: *const core::ffi::c_void,
+    pub size: u64,
+    pub flags: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterFsetxattrInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub fd: i64,
+    pub name: *const core::ffi::c_char,
+    pub value: *const core::ffi::c_void,
+    pub size: u64,
+    pub flags: i64,
+}
+
+/// Layout of `sys_enter_removexattr` and `sys_enter_lremovexattr`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterRemovexattrInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub pathname: *const core::ffi::c_char,
+    pub name: *const core::ffi::c_char,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterFremovexattrInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub fd: i64,
+    pub name: *const core::ffi::c_char,
+}
+
+/// Ids and capability sets copied out of a `struct cred`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Creds {
+    pub uid: u32,
+    pub gid: u32,
+    pub suid: u32,
+    pub sgid: u32,
+    pub euid: u32,
+    pub egid: u32,
+    pub fsuid: u32,
+    pub fsgid: u32,
+    pub cap_inheritable: u64,
+    pub cap_permitted: u64,
+    pub cap_effective: u64,
+    pub cap_bset: u64,
+    pub cap_ambient: u64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawCredsEvent {
+    pub pid: u32,
+    pub tgid: u32,
+    pub comm: [u8; 16],
+    pub old: Creds,
+    pub new: Creds,
+}
+
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ModuleEventKind {
+    Init,
+    Finit,
+    Delete,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawModuleEvent {
+    pub kind: ModuleEventKind,
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: [u8; 16],
+    /// Image fd for `finit_module`.
+    pub fd: i32,
+    /// Taint flags reported by `module:module_load`.
+    pub taints: u32,
+    /// Taken from `module:module_load` for loads, from the argument for `delete_module`.
+    pub name: [u8; 56],
+    pub ret: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterFinitModuleInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub fd: i64,
+    pub uargs: *const core::ffi::c_char,
+    pub flags: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterDeleteModuleInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub name_user: *const core::ffi::c_char,
+    pub flags: u64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct ModuleLoadInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub taints: u32,
+    /// `__data_loc char[] name`
+    pub name_loc: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawBpfEvent {
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: [u8; 16],
+    pub cmd: u32,
+    /// `prog_type` for `BPF_PROG_LOAD`, `map_type` for `BPF_MAP_CREATE`.
+    pub attr_type: u32,
+    /// `prog_name` or `map_name`.
+    pub name: [u8; 16],
+    pub ret: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterBpfInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub cmd: i64,
+    pub uattr: *const u8,
+    pub size: u64,
+}
+
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NamespaceOpKind {
+    Mount,
+    Umount,
+    Setns,
+    Unshare,
+    PivotRoot,
+    Chroot,
+    Clone,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawNamespaceEvent {
+    pub kind: NamespaceOpKind,
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: [u8; 16],
+    /// Mount, umount, unshare or clone flags, or the nstype for setns.
+    pub flags: u64,
+    /// Namespace fd for setns.
+    pub fd: i32,
+    /// Filesystem type for mount.
+    pub fstype: [u8; 16],
+    /// Mount source, umount target, pivot_root new root or chroot path.
+    pub path: [u8; 64],
+    /// Mount target or pivot_root old root.
+    pub path2: [u8; 64],
+    pub ret: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterMountInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub dev_name: *const core::ffi::c_char,
+    pub dir_name: *const core::ffi::c_char,
+    pub fs_type: *const core::ffi::c_char,
+    pub flags: u64,
+    pub data: *const core::ffi::c_void,
+}
+
+/// Layout of `sys_enter_umount`, the tracepoint of `umount2`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterUmountInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub name: *const core::ffi::c_char,
+    pub flags: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterSetnsInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub fd: i64,
+    pub flags: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterUnshareInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub unshare_flags: u64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterPivotRootInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub new_root: *const core::ffi::c_char,
+    pub put_old: *const core::ffi::c_char,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterChrootInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub filename: *const core::ffi::c_char,
+}
+
+/// Only the leading `clone_flags` argument is used; its position is the same on every
+/// architecture.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterCloneInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub clone_flags: u64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterClone3Info {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    /// `struct clone_args`, which starts with `u64 flags`.
+    pub uargs: *const u64,
+    pub size: u64,
+}
+
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PtraceEventKind {
+    Ptrace,
+    VmReadv,
+    VmWritev,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawPtraceEvent {
+    pub kind: PtraceEventKind,
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: [u8; 16],
+    /// Tracee pid, 0 for `PTRACE_TRACEME`, where the caller is traced by its parent.
+    pub target: u32,
+    /// Ptrace request.
+    pub request: u64,
+    /// Bytes asked for in the remote iovecs of `process_vm_readv`/`process_vm_writev`.
+    pub requested: u64,
+    /// Syscall return value, the bytes transferred for `process_vm_*`.
+    pub ret: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterPtraceInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub request: i64,
+    pub pid: i64,
+    pub addr: u64,
+    pub data: u64,
+}
+
+/// Layout shared by `sys_enter_process_vm_readv` and `sys_enter_process_vm_writev`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterProcessVmInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub pid: i64,
+    pub lvec: *const u8,
+    pub liovcnt: u64,
+    pub rvec: *const u8,
+    pub riovcnt: u64,
+    pub flags: u64,
+}
+
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExecMemKind {
+    Mmap,
+    Mprotect,
+    MemfdCreate,
+    Execveat,
+}
+
+/// What an mprotect'ed region maps, read from its VMA.
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExecMemBacking {
+    /// Not an mprotect, or one that failed before reaching a VMA.
+    Unknown,
+    Anonymous,
+    File,
+    Memfd,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawExecMemEvent {
+    pub kind: ExecMemKind,
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: [u8; 16],
+    pub addr: u64,
+    pub len: u64,
+    pub prot: u32,
+    /// Mapping flags for mmap, `MFD_*` for memfd_create, `AT_*` for execveat.
+    pub flags: u32,
+    /// Descriptor passed to mmap or execveat.
+    pub fd: i32,
+    /// For mprotect, the first anonymous or memfd region when it spans several.
+    pub backing: ExecMemBacking,
+    /// Memfd name, the memfd execveat ran, or the file mprotect changed.
+    pub name: [u8; 64],
+    pub ret: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterMmapInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub addr: u64,
+    pub len: u64,
+    pub prot: u64,
+    pub flags: u64,
+    pub fd: u64,
+    pub off: u64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterMprotectInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub start: u64,
+    pub len: u64,
+    pub prot: u64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterMemfdCreateInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub uname: *const core::ffi::c_char,
+    pub flags: u64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterExecveatInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub fd: i64,
+    pub filename: *const core::ffi::c_char,
+    pub argv: *const *const core::ffi::c_char,
+    pub envp: *const *const core::ffi::c_char,
+    pub flags: i64,
+}
+
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SocketEventKind {
+    Bind,
+    Listen,
+    /// `sock:inet_sock_set_state` into or out of `TCP_LISTEN`.
+    StateChange,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawSocketEvent {
+    pub kind: SocketEventKind,
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: [u8; 16],
+    pub fd: i32,
+    pub backlog: i32,
+    pub oldstate: i32,
+    pub newstate: i32,
+    pub family: u16,
+    pub protocol: u16,
+    /// Host byte order.
+    pub port: u16,
+    pub padding: u16,
+    /// IPv4 addresses use the first 4 bytes.
+    pub addr: [u8; 16],
+    /// Socket inode of a state change, 0 when unknown.
+    pub inode: u64,
+    pub ret: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterBindInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub fd: i64,
+    pub umyaddr: *const u8,
+    pub addrlen: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterListenInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub fd: i64,
+    pub backlog: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct InetSockSetStateInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub skaddr: u64,
+    pub oldstate: i32,
+    pub newstate: i32,
+    pub sport: u16,
+    pub dport: u16,
+    pub family: u16,
+    pub protocol: u16,
+    pub saddr: [u8; 4],
+    pub daddr: [u8; 4],
+    pub saddr_v6: [u8; 16],
+    pub daddr_v6: [u8; 16],
+}
+
+/// Bytes of a DNS message kept, the classic UDP limit.
+pub const DNS_MAX_LEN: usize = 512;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawDnsEvent {
+    pub pid: u32,
+    pub tgid: u32,
+    pub comm: [u8; 16],
+    /// Message length as received, which may exceed `data`.
+    pub len: u32,
+    pub data: [u8; DNS_MAX_LEN],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterRecvfromInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub fd: i64,
+    pub ubuf: *const u8,
+    pub size: u64,
+    pub flags: u64,
+    pub addr: *const u8,
+    pub addr_len: *const i32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SysEnterRecvmsgInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub syscall_nr: i32,
+    pub padding: u32,
+    pub fd: i64,
+    /// `struct user_msghdr`
+    pub msg: *const u8,
+    pub flags: u64,
+}
+
 #[repr(C)]
 #[derive(Copy, Clone)]
-pub struct SysEnterExitGroupInfo {
+pub struct SysEnterRecvmmsgInfo {
     pub common_type: u16,
     pub common_flags: u8,
     pub common_preempt_count: u8,
     pub common_pid: i32,
     pub syscall_nr: i32,
     pub padding: u32,
-    pub error_code: u64,
+    pub fd: i64,
+    /// `struct mmsghdr *`
+    pub mmsg: *const u8,
+    pub vlen: u64,
+    pub flags: u64,
+    pub timeout: *const u8,
+}
+
+/// Upper bound of the plaintext captured per `SSL_read`/`SSL_write`; the configured capture
+/// size is clamped to it.
+pub const TLS_MAX_CAPTURE: usize = 4096;
+
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TlsDirection {
+    Read,
+    Write,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawTlsDataEvent {
+    pub direction: TlsDirection,
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: [u8; 16],
+    /// Bytes read or written by the call, which may exceed what was captured.
+    pub len: u32,
+    pub captured: u32,
+    pub data: [u8; TLS_MAX_CAPTURE],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawShellCommandEvent {
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: [u8; 16],
+    /// Non-zero when the line was longer than `line` holds.
+    pub truncated: u32,
+    pub line: [u8; 256],
+}
+
+/// Argument registers captured by the generic uprobe.
+pub const UPROBE_ARGS: usize = 6;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawUprobeEvent {
+    pub pid: u32,
+    pub tgid: u32,
+    pub comm: [u8; 16],
+    pub args: [u64; UPROBE_ARGS],
+    /// Entry time, from `bpf_ktime_get_ns`.
+    pub ts: u64,
+    /// Set, along with `ret` and `duration_ns`, when the event comes from the return probe.
+    pub returned: u32,
+    pub ret: u64,
+    pub duration_ns: u64,
+}
+
+/// Where a USDT argument is, as decoded from its spec in the probe's note.
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UsdtArgKind {
+    /// `$value`
+    Const,
+    /// `%reg`
+    Reg,
+    /// `value(%reg)`
+    RegDeref,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UsdtArgSpec {
+    pub kind: UsdtArgKind,
+    /// Offset of the register in `struct pt_regs`.
+    pub reg_offset: u32,
+    /// The constant, or the displacement from the register.
+    pub value: i64,
+    /// Size of the argument in bytes: 1, 2, 4 or 8.
+    pub size: u32,
+    /// Non-zero when the argument is sign-extended.
+    pub signed: u32,
+}
+
+impl UsdtArgSpec {
+    pub const UNUSED: UsdtArgSpec = UsdtArgSpec {
+        kind: UsdtArgKind::Const,
+        reg_offset: 0,
+        value: 0,
+        size: 8,
+        signed: 0,
+    };
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for UsdtArgSpec {}
+
+/// Bytes of a raw tracepoint record copied by the generic tracepoint program.
+pub const TRACEPOINT_MAX_RECORD: usize = 1024;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawTracepointEvent {
+    pub pid: u32,
+    pub tgid: u32,
+    pub comm: [u8; 16],
+    pub len: u32,
+    pub data: [u8; TRACEPOINT_MAX_RECORD],
+}
+
+/// `raw_syscalls:sys_enter`
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawSysEnterInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub id: i64,
+    pub args: [u64; 6],
+}
+
+/// `raw_syscalls:sys_exit`
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawSysExitInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub id: i64,
+    pub ret: i64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SyscallKey {
+    pub tgid: u32,
+    pub nr: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SyscallErrorKey {
+    pub tgid: u32,
+    pub nr: u32,
+    pub errno: u32,
+    pub padding: u32,
+}
+
+/// Totals of one syscall in one process, updated in the kernel.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SyscallStats {
+    pub count: u64,
+    pub errors: u64,
+    pub total_ns: u64,
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for SyscallKey {}
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for SyscallErrorKey {}
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for SyscallStats {}
+
+/// Samples of the profiler are counted per process and pair of stacks.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ProfileKey {
+    pub tgid: u32,
+    /// Ids in the stack trace map, negative when the stack couldn't be collected.
+    pub user_stack: i32,
+    pub kernel_stack: i32,
+    pub comm: [u8; 16],
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for ProfileKey {}
+
+/// `sched:sched_switch`
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SchedSwitchInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub prev_comm: [u8; 16],
+    pub prev_pid: i32,
+    pub prev_prio: i32,
+    pub prev_state: i64,
+    pub next_comm: [u8; 16],
+    pub next_pid: i32,
+    pub next_prio: i32,
+}
+
+/// `sched:sched_wakeup`
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SchedWakeupInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub comm: [u8; 16],
+    pub pid: i32,
+    pub prio: i32,
+    pub target_cpu: i32,
+}
+
+/// Off-CPU time is summed per process, pair of stacks and reason.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OffCpuKey {
+    pub tgid: u32,
+    /// Ids in the stack trace map, negative when the stack couldn't be collected.
+    pub user_stack: i32,
+    pub kernel_stack: i32,
+    /// 1 for time spent runnable, waiting for a CPU, 0 for time spent blocked.
+    pub runqueue: u32,
+    pub comm: [u8; 16],
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for OffCpuKey {}
+
+/// A thread switched out, waiting to run again.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct OffCpuStart {
+    /// Switched out.
+    pub start: u64,
+    /// Woken up, or `start` if it was still runnable.
+    pub wakeup: u64,
+    pub key: OffCpuKey,
+}
+
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for OffCpuStart {}
+
+/// `block:block_bio_queue`, up to the fields stable across kernel versions.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct BlockBioQueueInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub dev: u32,
+    pub sector: u64,
+}
+
+/// `block:block_rq_issue`, up to the fields stable across kernel versions; `rwbs` moved
+/// when `ioprio` was added.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct BlockRqIssueInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub dev: u32,
+    pub sector: u64,
+    pub nr_sector: u32,
+    pub bytes: u32,
+}
+
+/// `block:block_rq_complete`, up to the fields stable across kernel versions.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct BlockRqCompleteInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub dev: u32,
+    pub sector: u64,
+    pub nr_sector: u32,
+    pub error: i32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawBioEvent {
+    /// Process that submitted the I/O, or the one that issued it to the device when the
+    /// submission wasn't seen.
+    pub tgid: u32,
+    pub comm: [u8; 16],
+    /// Kernel `dev_t`: major in the upper 12 bits, minor in the lower 20.
+    pub dev: u32,
+    pub sector: u64,
+    pub bytes: u32,
+    pub error: i32,
+    /// Operation flags, e.g. `R`, `WS` or `FWS`.
+    pub rwbs: [u8; 8],
+    /// From issue to the device to completion.
+    pub latency_ns: u64,
+}
+
+/// `tcp:tcp_retransmit_skb` and `tcp:tcp_send_reset`
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct TcpEventSkSkbInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub skbaddr: u64,
+    /// Null for resets sent in reply to a segment without a socket.
+    pub skaddr: u64,
+    pub state: i32,
+    pub sport: u16,
+    pub dport: u16,
+    pub family: u16,
+    pub saddr: [u8; 4],
+    pub daddr: [u8; 4],
+    pub saddr_v6: [u8; 16],
+    pub daddr_v6: [u8; 16],
+}
+
+/// `tcp:tcp_receive_reset`
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct TcpEventSkInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    pub skaddr: u64,
+    pub sport: u16,
+    pub dport: u16,
+    pub family: u16,
+    pub saddr: [u8; 4],
+    pub daddr: [u8; 4],
+    pub saddr_v6: [u8; 16],
+    pub daddr_v6: [u8; 16],
+}
+
+/// `tcp:tcp_probe`, up to the fields stable across kernel versions. `srtt` and `skaddr` are
+/// found through the format file.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct TcpProbeInfo {
+    pub common_type: u16,
+    pub common_flags: u8,
+    pub common_preempt_count: u8,
+    pub common_pid: i32,
+    /// `sockaddr_in` or `sockaddr_in6`.
+    pub saddr: [u8; 28],
+    pub daddr: [u8; 28],
+    pub sport: u16,
+    pub dport: u16,
+}
+
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TcpEventKind {
+    Retransmit,
+    SendReset,
+    ReceiveReset,
+    /// A smoothed RTT sample, at most one per connection per second.
+    Rtt,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawTcpEvent {
+    pub kind: TcpEventKind,
+    /// Owner of the socket, last seen connecting, accepting or sending on it, or 0 if unknown.
+    pub tgid: u32,
+    pub comm: [u8; 16],
+    pub family: u16,
+    /// Host byte order.
+    pub sport: u16,
+    pub dport: u16,
+    pub padding: u16,
+    /// IPv4 addresses use the first 4 bytes.
+    pub saddr: [u8; 16],
+    pub daddr: [u8; 16],
+    pub srtt_us: u32,
+    /// TCP state of the socket, for retransmits.
+    pub state: i32,
+}
+
+/// Bytes of the `oom:mark_victim` record copied; its fields vary across kernel versions and
+/// are decoded in userspace.
+pub const OOM_MAX_RECORD: usize = 256;
+pub const OOM_MAX_MESSAGE: usize = 64;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawOomEvent {
+    /// Task that ran out of memory and chose the victim.
+    pub trigger_pid: u32,
+    pub trigger_tgid: u32,
+    pub trigger_comm: [u8; 16],
+    /// cgroup v2 id of the memory cgroup that hit its limit, the inode of its directory, or 0
+    /// for system-wide OOMs or when unknown.
+    pub memcg_id: u64,
+    /// Message passed to `oom_kill_process`, empty when the victim was picked without it.
+    pub message: [u8; OOM_MAX_MESSAGE],
+    pub len: u32,
+    pub data: [u8; OOM_MAX_RECORD],
 }
@@ -0,0 +1,6 @@
+mod creds;
+pub mod execmem;
+mod exit;
+pub mod oom;
+pub mod perms;
+pub mod tcp;
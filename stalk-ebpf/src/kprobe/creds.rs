@@ -0,0 +1,77 @@
+use aya_ebpf::{
+    helpers::{bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_probe_read_kernel, r#gen},
+    macros::{kprobe, map},
+    maps::RingBuf,
+    programs::ProbeContext,
+};
+use stalk_common::{Creds, RawCredsEvent};
+
+use crate::task;
+
+/// Offsets of `uid` and `cap_inheritable` in `struct cred`, set by userspace from BTF.
+#[unsafe(no_mangle)]
+static CRED_IDS_OFFSET: u32 = 8;
+#[unsafe(no_mangle)]
+static CRED_CAPS_OFFSET: u32 = 48;
+
+#[map]
+static mut CREDS_EVENTS: RingBuf = RingBuf::with_byte_size(256 * 1024, 0);
+
+/// `commit_creds(struct cred *new)` installs the new credentials on the current task, whose
+/// `real_cred` still points to the ones being replaced.
+#[kprobe]
+pub fn stalk_commit_creds(ctx: ProbeContext) -> u32 {
+    match try_stalk_commit_creds(ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+fn try_stalk_commit_creds(ctx: ProbeContext) -> Result<u32, i64> {
+    let cred: *const u8 = ctx.arg(0).ok_or(1i64)?;
+    let new = read_creds(cred)?;
+    let old = unsafe { read_creds(task::real_cred(r#gen::bpf_get_current_task() as *const u8)?)? };
+    if old == new {
+        return Ok(0);
+    }
+    let tgid_pid = bpf_get_current_pid_tgid();
+    let event = RawCredsEvent {
+        pid: (tgid_pid & 0xFFFFFFFF) as u32,
+        tgid: (tgid_pid >> 32) as u32,
+        comm: bpf_get_current_comm()?,
+        old,
+        new,
+    };
+    unsafe {
+        let event_map = &raw mut CREDS_EVENTS;
+        if let Some(mut buf) = (*event_map).reserve::<RawCredsEvent>(0) {
+            buf.write(event);
+            buf.submit(0);
+        }
+    }
+    Ok(0)
+}
+
+fn read_creds(cred: *const u8) -> Result<Creds, i64> {
+    unsafe {
+        let ids_offset = core::ptr::read_volatile(&raw const CRED_IDS_OFFSET) as usize;
+        let caps_offset = core::ptr::read_volatile(&raw const CRED_CAPS_OFFSET) as usize;
+        let ids: [u32; 8] = bpf_probe_read_kernel(cred.add(ids_offset) as *const [u32; 8])?;
+        let caps: [u64; 5] = bpf_probe_read_kernel(cred.add(caps_offset) as *const [u64; 5])?;
+        Ok(Creds {
+            uid: ids[0],
+            gid: ids[1],
+            suid: ids[2],
+            sgid: ids[3],
+            euid: ids[4],
+            egid: ids[5],
+            fsuid: ids[6],
+            fsgid: ids[7],
+            cap_inheritable: caps[0],
+            cap_permitted: caps[1],
+            cap_effective: caps[2],
+            cap_bset: caps[3],
+            cap_ambient: caps[4],
+        })
+    }
+}
@@ -0,0 +1,81 @@
+use aya_ebpf::{
+    helpers::{bpf_get_current_pid_tgid, bpf_probe_read_kernel, bpf_probe_read_kernel_str_bytes},
+    macros::{kprobe, map},
+    maps::LruHashMap,
+    programs::ProbeContext,
+};
+use stalk_common::{ExecMemBacking, ExecMemKind, RawExecMemEvent};
+
+/// Position of the `vma` parameter of `mprotect_fixup`, which the VMA iterator pushed back,
+/// and the offsets leading from a VMA to the name of the file it maps, set by userspace from
+/// BTF.
+#[unsafe(no_mangle)]
+static MPROTECT_FIXUP_VMA_ARG: u32 = 1;
+#[unsafe(no_mangle)]
+static VMA_FILE_OFFSET: u32 = 160;
+#[unsafe(no_mangle)]
+static FILE_DENTRY_OFFSET: u32 = 24;
+#[unsafe(no_mangle)]
+static DENTRY_NAME_OFFSET: u32 = 40;
+
+/// Name of the dentry of a memfd, see `memfd_alloc_file`.
+pub const MEMFD_PREFIX: [u8; 6] = *b"memfd:";
+
+/// Thread -> call waiting for its syscall to return.
+#[map]
+pub static mut EXECMEM_PENDING: LruHashMap<u64, RawExecMemEvent> =
+    LruHashMap::with_max_entries(10240, 0);
+
+/// `mprotect_fixup()` applies an mprotect to each VMA of the range in turn.
+#[kprobe]
+pub fn stalk_mprotect_fixup(ctx: ProbeContext) -> u32 {
+    match try_stalk_mprotect_fixup(ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+fn try_stalk_mprotect_fixup(ctx: ProbeContext) -> Result<u32, i64> {
+    unsafe {
+        let pending = &raw mut EXECMEM_PENDING;
+        let Some(event) = (*pending).get_ptr_mut(&bpf_get_current_pid_tgid()) else {
+            return Ok(0);
+        };
+        if (*event).kind != ExecMemKind::Mprotect
+            || matches!(
+                (*event).backing,
+                ExecMemBacking::Anonymous | ExecMemBacking::Memfd
+            )
+        {
+            return Ok(0);
+        }
+        let arg = core::ptr::read_volatile(&raw const MPROTECT_FIXUP_VMA_ARG) as usize;
+        let file_offset = core::ptr::read_volatile(&raw const VMA_FILE_OFFSET) as usize;
+        let vma: *const u8 = ctx.arg(arg).ok_or(1i64)?;
+        let file: *const u8 = bpf_probe_read_kernel(vma.add(file_offset) as *const *const u8)?;
+        if file.is_null() {
+            (*event).backing = ExecMemBacking::Anonymous;
+            return Ok(0);
+        }
+        let name = &mut (*event).name;
+        file_name(file, name)?;
+        (*event).backing = if name[..MEMFD_PREFIX.len()] == MEMFD_PREFIX {
+            ExecMemBacking::Memfd
+        } else {
+            ExecMemBacking::File
+        };
+    }
+    Ok(0)
+}
+
+/// Copies the name of the dentry `file` was opened through into `name`.
+pub unsafe fn file_name(file: *const u8, name: &mut [u8]) -> Result<(), i64> {
+    unsafe {
+        let dentry_offset = core::ptr::read_volatile(&raw const FILE_DENTRY_OFFSET) as usize;
+        let name_offset = core::ptr::read_volatile(&raw const DENTRY_NAME_OFFSET) as usize;
+        let dentry: *const u8 = bpf_probe_read_kernel(file.add(dentry_offset) as *const *const u8)?;
+        let src: *const u8 = bpf_probe_read_kernel(dentry.add(name_offset) as *const *const u8)?;
+        bpf_probe_read_kernel_str_bytes(src, name)?;
+    }
+    Ok(())
+}
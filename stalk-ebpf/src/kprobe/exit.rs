@@ -0,0 +1,45 @@
+use aya_ebpf::{
+    helpers::{bpf_get_current_comm, bpf_get_current_pid_tgid},
+    macros::{kprobe, map},
+    maps::RingBuf,
+    programs::ProbeContext,
+};
+use stalk_common::RawExitEvent;
+
+#[map]
+static mut EXIT_EVENTS: RingBuf = RingBuf::with_byte_size(256 * 1024, 0);
+
+/// `do_exit(long code)` runs for every exiting task, whether it called `exit_group` or was
+/// killed by a signal, and `code` becomes `task->exit_code`.
+#[kprobe]
+pub fn stalk_exit(ctx: ProbeContext) -> u32 {
+    match try_stalk_exit(ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+fn try_stalk_exit(ctx: ProbeContext) -> Result<u32, i64> {
+    let tgid_pid = bpf_get_current_pid_tgid();
+    let pid = (tgid_pid & 0xFFFFFFFF) as u32;
+    let tgid = (tgid_pid >> 32) as u32;
+    // Every thread passes through do_exit with the group's status, report it once.
+    if pid != tgid {
+        return Ok(0);
+    }
+    let exit_code: i64 = ctx.arg(0).ok_or(1i64)?;
+    let comm = bpf_get_current_comm()?;
+    let event = RawExitEvent {
+        pid,
+        comm,
+        exit_code: exit_code as u64,
+    };
+    unsafe {
+        let event_map = &raw mut EXIT_EVENTS;
+        if let Some(mut buf) = (*event_map).reserve::<RawExitEvent>(0) {
+            buf.write(event);
+            buf.submit(0);
+        }
+    }
+    Ok(0)
+}
@@ -0,0 +1,70 @@
+use aya_ebpf::{
+    helpers::{bpf_get_current_pid_tgid, bpf_probe_read_kernel, bpf_probe_read_kernel_str_bytes},
+    macros::{kprobe, map},
+    maps::LruHashMap,
+    programs::ProbeContext,
+};
+use stalk_common::OOM_MAX_MESSAGE;
+
+/// Offsets leading from `oom_control` to the id of the memory cgroup that hit its limit, set by
+/// userspace from BTF. `u32::MAX` leaves the cgroup unknown.
+#[unsafe(no_mangle)]
+static OOM_CONTROL_MEMCG_OFFSET: u32 = u32::MAX;
+#[unsafe(no_mangle)]
+static MEMCG_CGROUP_OFFSET: u32 = 0;
+#[unsafe(no_mangle)]
+static CGROUP_KN_OFFSET: u32 = 0;
+#[unsafe(no_mangle)]
+static KERNFS_NODE_ID_OFFSET: u32 = 0;
+
+#[derive(Copy, Clone)]
+pub struct OomKill {
+    pub message: [u8; OOM_MAX_MESSAGE],
+    /// cgroup v2 id of `oc->memcg`, 0 when the whole system ran out.
+    pub memcg_id: u64,
+}
+
+/// pid_tgid -> `oom_kill_process` call in progress, picked up by `oom:mark_victim`, which fires
+/// further down in the same task.
+#[map]
+pub static mut OOM_KILLS: LruHashMap<u64, OomKill> = LruHashMap::with_max_entries(1024, 0);
+
+/// `oom_kill_process(struct oom_control *oc, const char *message)`
+#[kprobe]
+pub fn stalk_oom_kill_process(ctx: ProbeContext) -> u32 {
+    let (Some(oc), Some(message)) = (ctx.arg::<*const u8>(0), ctx.arg::<*const u8>(1)) else {
+        return 0;
+    };
+    let mut kill = OomKill {
+        message: [0; OOM_MAX_MESSAGE],
+        memcg_id: unsafe { memcg_id(oc) }.unwrap_or(0),
+    };
+    unsafe {
+        let _ = bpf_probe_read_kernel_str_bytes(message, &mut kill.message);
+        let kills = &raw mut OOM_KILLS;
+        let _ = (*kills).insert(&bpf_get_current_pid_tgid(), &kill, 0);
+    }
+    0
+}
+
+/// `oc->memcg->css.cgroup->kn->id`, the inode of the cgroup's directory.
+unsafe fn memcg_id(oc: *const u8) -> Result<u64, i64> {
+    unsafe {
+        let memcg_offset = core::ptr::read_volatile(&raw const OOM_CONTROL_MEMCG_OFFSET);
+        if memcg_offset == u32::MAX {
+            return Ok(0);
+        }
+        let cgroup_offset = core::ptr::read_volatile(&raw const MEMCG_CGROUP_OFFSET) as usize;
+        let kn_offset = core::ptr::read_volatile(&raw const CGROUP_KN_OFFSET) as usize;
+        let id_offset = core::ptr::read_volatile(&raw const KERNFS_NODE_ID_OFFSET) as usize;
+        let memcg: *const u8 =
+            bpf_probe_read_kernel(oc.add(memcg_offset as usize) as *const *const u8)?;
+        if memcg.is_null() {
+            return Ok(0);
+        }
+        let cgroup: *const u8 =
+            bpf_probe_read_kernel(memcg.add(cgroup_offset) as *const *const u8)?;
+        let kn: *const u8 = bpf_probe_read_kernel(cgroup.add(kn_offset) as *const *const u8)?;
+        bpf_probe_read_kernel(kn.add(id_offset) as *const u64)
+    }
+}
@@ -0,0 +1,52 @@
+use aya_ebpf::{
+    helpers::{bpf_get_current_pid_tgid, bpf_probe_read_kernel},
+    macros::{kprobe, map},
+    maps::LruHashMap,
+    programs::ProbeContext,
+};
+use stalk_common::{PermOpKind, RawPermEvent};
+
+/// Position of the `dentry` parameter of `notify_change`, which idmapped mounts pushed back,
+/// and the offsets leading from it to the inode's mode, set by userspace from BTF.
+#[unsafe(no_mangle)]
+static NOTIFY_CHANGE_DENTRY_ARG: u32 = 1;
+#[unsafe(no_mangle)]
+static DENTRY_INODE_OFFSET: u32 = 48;
+#[unsafe(no_mangle)]
+static INODE_MODE_OFFSET: u32 = 0;
+
+/// Thread -> operation waiting for its syscall to return.
+#[map]
+pub static mut PERMS_PENDING: LruHashMap<u64, RawPermEvent> =
+    LruHashMap::with_max_entries(10240, 0);
+
+/// `notify_change()` applies the new attributes to the inode, so on entry it still holds the
+/// mode a chmod replaces.
+#[kprobe]
+pub fn stalk_perms_notify_change(ctx: ProbeContext) -> u32 {
+    match try_stalk_perms_notify_change(ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+fn try_stalk_perms_notify_change(ctx: ProbeContext) -> Result<u32, i64> {
+    let tgid_pid = bpf_get_current_pid_tgid();
+    unsafe {
+        let pending = &raw mut PERMS_PENDING;
+        let Some(event) = (*pending).get_ptr_mut(&tgid_pid) else {
+            return Ok(0);
+        };
+        if (*event).kind != PermOpKind::Chmod {
+            return Ok(0);
+        }
+        let arg = core::ptr::read_volatile(&raw const NOTIFY_CHANGE_DENTRY_ARG) as usize;
+        let inode_offset = core::ptr::read_volatile(&raw const DENTRY_INODE_OFFSET) as usize;
+        let mode_offset = core::ptr::read_volatile(&raw const INODE_MODE_OFFSET) as usize;
+        let dentry: *const u8 = ctx.arg(arg).ok_or(1i64)?;
+        let inode: *const u8 = bpf_probe_read_kernel(dentry.add(inode_offset) as *const *const u8)?;
+        let mode: u16 = bpf_probe_read_kernel(inode.add(mode_offset) as *const u16)?;
+        (*event).old_mode = mode as u32;
+    }
+    Ok(0)
+}
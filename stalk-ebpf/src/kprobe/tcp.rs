@@ -0,0 +1,59 @@
+use aya_ebpf::{
+    helpers::{bpf_get_current_comm, bpf_get_current_pid_tgid},
+    macros::{kprobe, kretprobe, map},
+    maps::LruHashMap,
+    programs::{ProbeContext, RetProbeContext},
+};
+
+#[derive(Copy, Clone)]
+pub struct SockOwner {
+    pub tgid: u32,
+    pub comm: [u8; 16],
+}
+
+/// `struct sock *` -> process last seen connecting, accepting or sending on it. The TCP
+/// tracepoints mostly run in softirq context, where the current task is unrelated to the socket.
+#[map]
+pub static mut SOCK_OWNERS: LruHashMap<u64, SockOwner> = LruHashMap::with_max_entries(65536, 0);
+
+/// `tcp_sendmsg(struct sock *sk, struct msghdr *msg, size_t size)`
+#[kprobe]
+pub fn stalk_tcp_sendmsg(ctx: ProbeContext) -> u32 {
+    if let Some(sk) = ctx.arg::<u64>(0) {
+        record_owner(sk);
+    }
+    0
+}
+
+/// `tcp_connect(struct sock *sk)`, in the task calling `connect()`, so that a connection
+/// reset before it sent anything is still attributed.
+#[kprobe]
+pub fn stalk_tcp_connect(ctx: ProbeContext) -> u32 {
+    if let Some(sk) = ctx.arg::<u64>(0) {
+        record_owner(sk);
+    }
+    0
+}
+
+/// `inet_csk_accept()` returns the new connection's `struct sock *`, in the task calling
+/// `accept()`.
+#[kretprobe]
+pub fn stalk_inet_csk_accept(ctx: RetProbeContext) -> u32 {
+    if let Some(sk) = ctx.ret::<u64>()
+        && sk != 0
+    {
+        record_owner(sk);
+    }
+    0
+}
+
+fn record_owner(sk: u64) {
+    let owner = SockOwner {
+        tgid: (bpf_get_current_pid_tgid() >> 32) as u32,
+        comm: bpf_get_current_comm().unwrap_or([0; 16]),
+    };
+    unsafe {
+        let owners = &raw mut SOCK_OWNERS;
+        let _ = (*owners).insert(&sk, &owner, 0);
+    }
+}
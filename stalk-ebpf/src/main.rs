@@ -1,7 +1,12 @@
 #![no_std]
 #![no_main]
 
+mod kprobe;
+mod perf_event;
+mod syscall;
+mod task;
 mod tracepoint;
+mod uprobe;
 mod xdp;
 
 #[cfg(not(test))]
@@ -0,0 +1,55 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use aya_ebpf::{
+    bindings::BPF_F_USER_STACK,
+    helpers::{bpf_get_current_comm, bpf_get_current_pid_tgid},
+    macros::{map, perf_event},
+    maps::{HashMap, StackTrace},
+    programs::PerfEventContext,
+};
+use stalk_common::ProfileKey;
+
+/// Another CPU may have created the entry in the meantime; keep its count.
+const BPF_NOEXIST: u64 = 1;
+
+#[map]
+static mut PROFILE_STACKS: StackTrace = StackTrace::with_max_entries(16384, 0);
+
+/// (tgid, user stack, kernel stack) -> samples since userspace last drained the map.
+#[map]
+static mut PROFILE_COUNTS: HashMap<ProfileKey, u64> = HashMap::with_max_entries(40960, 0);
+
+/// Runs on every tick of each CPU's clock, sampling whatever is on the CPU.
+#[perf_event]
+pub fn stalk_profile(ctx: PerfEventContext) -> u32 {
+    match try_stalk_profile(ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+fn try_stalk_profile(ctx: PerfEventContext) -> Result<u32, i64> {
+    let tgid = (bpf_get_current_pid_tgid() >> 32) as u32;
+    // The idle task.
+    if tgid == 0 {
+        return Ok(0);
+    }
+    unsafe {
+        let stacks = &raw mut PROFILE_STACKS;
+        let key = ProfileKey {
+            tgid,
+            user_stack: (*stacks)
+                .get_stackid(&ctx, BPF_F_USER_STACK as u64)
+                .unwrap_or(-1) as i32,
+            kernel_stack: (*stacks).get_stackid(&ctx, 0).unwrap_or(-1) as i32,
+            comm: bpf_get_current_comm()?,
+        };
+        let counts = &raw mut PROFILE_COUNTS;
+        if (*counts).get_ptr_mut(&key).is_none() {
+            let _ = (*counts).insert(&key, &0, BPF_NOEXIST);
+        }
+        let count = (*counts).get_ptr_mut(&key).ok_or(1i64)?;
+        AtomicU64::from_ptr(count).fetch_add(1, Ordering::Relaxed);
+    }
+    Ok(0)
+}
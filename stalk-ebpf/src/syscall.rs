@@ -0,0 +1,112 @@
+//! Syscalls traced in two halves: the event is built when the syscall enters, kept per thread,
+//! and submitted with the return value when the syscall exits.
+
+use aya_ebpf::{
+    EbpfContext,
+    helpers::{bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_get_current_uid_gid},
+    maps::{LruHashMap, RingBuf},
+    programs::TracePointContext,
+};
+use stalk_common::{
+    RawBpfEvent, RawExecMemEvent, RawFileOpEvent, RawModuleEvent, RawNamespaceEvent, RawPermEvent,
+    RawPtraceEvent, RawSocketEvent, SysExitInfo,
+};
+
+/// An event completed by the return value of its syscall.
+pub trait SyscallEvent: Copy + 'static {
+    fn set_ret(&mut self, ret: i64);
+}
+
+macro_rules! syscall_events {
+    ($($ty:ty),*) => {
+        $(impl SyscallEvent for $ty {
+            fn set_ret(&mut self, ret: i64) {
+                self.ret = ret;
+            }
+        })*
+    };
+}
+
+syscall_events!(
+    RawBpfEvent,
+    RawExecMemEvent,
+    RawFileOpEvent,
+    RawModuleEvent,
+    RawNamespaceEvent,
+    RawPermEvent,
+    RawPtraceEvent,
+    RawSocketEvent
+);
+
+/// The task making the syscall, which every event starts with.
+pub struct Caller {
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: [u8; 16],
+}
+
+/// Reads the current task into a [`Caller`].
+pub fn caller() -> Result<Caller, u32> {
+    let tgid_pid = bpf_get_current_pid_tgid();
+    Ok(Caller {
+        pid: (tgid_pid & 0xFFFFFFFF) as u32,
+        tgid: (tgid_pid >> 32) as u32,
+        uid: (bpf_get_current_uid_gid() & 0xFFFFFFFF) as u32,
+        comm: bpf_get_current_comm().map_err(|e| e as u32)?,
+    })
+}
+
+/// Keeps `event` until the current thread's syscall returns.
+pub unsafe fn enter<T>(pending: *mut LruHashMap<u64, T>, event: &T) -> Result<u32, u32> {
+    unsafe {
+        (*pending)
+            .insert(&bpf_get_current_pid_tgid(), event, 0)
+            .map_err(|e| e as u32)?;
+    }
+    Ok(0)
+}
+
+/// Removes the current thread's event from `pending`.
+pub unsafe fn take<T: Copy>(pending: *mut LruHashMap<u64, T>) -> Option<T> {
+    let tgid_pid = bpf_get_current_pid_tgid();
+    unsafe {
+        let event = (*pending).get(&tgid_pid).copied()?;
+        let _ = (*pending).remove(&tgid_pid);
+        Some(event)
+    }
+}
+
+/// Submits the current thread's event, if any, with the return value of the `sys_exit_*`
+/// record at `ctx`.
+pub unsafe fn exit<T: SyscallEvent>(
+    ctx: &TracePointContext,
+    pending: *mut LruHashMap<u64, T>,
+    events: *mut RingBuf,
+) -> Result<u32, u32> {
+    unsafe {
+        if let Some(event) = take(pending) {
+            submit(ctx, events, event);
+        }
+    }
+    Ok(0)
+}
+
+/// Writes `event` to `events` with the return value of the `sys_exit_*` record at `ctx`.
+pub unsafe fn submit<T: SyscallEvent>(ctx: &TracePointContext, events: *mut RingBuf, mut event: T) {
+    unsafe {
+        let exit_info = ctx.as_ptr() as *const SysExitInfo;
+        event.set_ret((*exit_info).ret);
+        output(events, event);
+    }
+}
+
+/// Writes `event` to `events`, dropping it if the ring buffer is full.
+pub unsafe fn output<T: 'static>(events: *mut RingBuf, event: T) {
+    unsafe {
+        if let Some(mut buf) = (*events).reserve::<T>(0) {
+            buf.write(event);
+            buf.submit(0);
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use aya_ebpf::helpers::bpf_probe_read_kernel;
+
+/// Offsets of `struct task_struct` fields, set by userspace from the kernel's BTF.
+#[unsafe(no_mangle)]
+static TASK_PID_OFFSET: u32 = 0;
+#[unsafe(no_mangle)]
+static TASK_TGID_OFFSET: u32 = 0;
+#[unsafe(no_mangle)]
+static TASK_GROUP_LEADER_OFFSET: u32 = 0;
+#[unsafe(no_mangle)]
+static TASK_COMM_OFFSET: u32 = 0;
+#[unsafe(no_mangle)]
+static TASK_REAL_CRED_OFFSET: u32 = 0;
+#[unsafe(no_mangle)]
+static TASK_FILES_OFFSET: u32 = 0;
+#[unsafe(no_mangle)]
+static FILES_FDT_OFFSET: u32 = 0;
+#[unsafe(no_mangle)]
+static FDTABLE_MAX_FDS_OFFSET: u32 = 0;
+#[unsafe(no_mangle)]
+static FDTABLE_FD_OFFSET: u32 = 0;
+
+unsafe fn read_field<T>(task: *const u8, offset: *const u32) -> Result<T, i64> {
+    unsafe {
+        let offset = core::ptr::read_volatile(offset) as usize;
+        bpf_probe_read_kernel(task.add(offset) as *const T)
+    }
+}
+
+/// Thread id of `task`.
+pub unsafe fn pid(task: *const u8) -> Result<u32, i64> {
+    unsafe { read_field::<i32>(task, &raw const TASK_PID_OFFSET).map(|pid| pid as u32) }
+}
+
+/// Process id of `task`.
+pub unsafe fn tgid(task: *const u8) -> Result<u32, i64> {
+    unsafe { read_field::<i32>(task, &raw const TASK_TGID_OFFSET).map(|tgid| tgid as u32) }
+}
+
+/// Name of the process `task` belongs to, which its threads may have renamed themselves from.
+pub unsafe fn process_comm(task: *const u8) -> Result<[u8; 16], i64> {
+    unsafe {
+        let leader: *const u8 = read_field(task, &raw const TASK_GROUP_LEADER_OFFSET)?;
+        read_field(leader, &raw const TASK_COMM_OFFSET)
+    }
+}
+
+/// Objective credentials of `task`, the ones others see it acting with.
+pub unsafe fn real_cred(task: *const u8) -> Result<*const u8, i64> {
+    unsafe { read_field(task, &raw const TASK_REAL_CRED_OFFSET) }
+}
+
+/// Open file behind descriptor `fd` of `task`.
+pub unsafe fn file(task: *const u8, fd: i32) -> Result<*const u8, i64> {
+    unsafe {
+        let files: *const u8 = read_field(task, &raw const TASK_FILES_OFFSET)?;
+        let fdt: *const u8 = read_field(files, &raw const FILES_FDT_OFFSET)?;
+        let max_fds: u32 = read_field(fdt, &raw const FDTABLE_MAX_FDS_OFFSET)?;
+        if fd < 0 || fd as u32 >= max_fds {
+            return Err(1);
+        }
+        let fds: *const *const u8 = read_field(fdt, &raw const FDTABLE_FD_OFFSET)?;
+        bpf_probe_read_kernel(fds.add(fd as usize))
+    }
+}
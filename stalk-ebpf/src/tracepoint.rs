@@ -1,5 +1,21 @@
+mod bio;
+mod bpf;
+mod dns;
+mod execmem;
 mod execve;
-mod exit;
+mod fileops;
+mod generic;
+mod modules;
+mod namespaces;
+mod offcpu;
+mod oom;
 mod openat;
+mod perms;
+mod process;
+mod ptrace;
 mod read;
 mod read_exit;
+mod signal;
+mod sockets;
+mod syscalls;
+mod tcp;
@@ -0,0 +1,137 @@
+use aya_ebpf::{
+    EbpfContext,
+    helpers::{bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_ktime_get_ns},
+    macros::{map, tracepoint},
+    maps::{LruHashMap, RingBuf},
+    programs::TracePointContext,
+};
+use stalk_common::{BlockBioQueueInfo, BlockRqCompleteInfo, BlockRqIssueInfo, RawBioEvent};
+
+/// Offset of `rwbs` in `block_rq_issue`, set by userspace from the tracepoint's format file.
+#[unsafe(no_mangle)]
+static BIO_RWBS_OFFSET: u32 = 32;
+
+/// A request is identified by its device and first sector while in flight.
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct BioKey {
+    dev: u32,
+    padding: u32,
+    sector: u64,
+}
+
+#[derive(Copy, Clone)]
+struct BioOwner {
+    tgid: u32,
+    comm: [u8; 16],
+}
+
+#[derive(Copy, Clone)]
+struct BioStart {
+    ts: u64,
+    owner: BioOwner,
+    bytes: u32,
+    rwbs: [u8; 8],
+}
+
+/// Bios submitted but not issued yet. Merged bios are never issued under their own sector,
+/// hence the LRU.
+#[map]
+static mut BIO_QUEUED: LruHashMap<BioKey, BioOwner> = LruHashMap::with_max_entries(10240, 0);
+
+/// Requests issued but not completed yet. Completions are missed for requests that end
+/// without one, e.g. when a device is removed, hence the LRU.
+#[map]
+static mut BIO_START: LruHashMap<BioKey, BioStart> = LruHashMap::with_max_entries(10240, 0);
+
+#[map]
+static mut BIO_EVENTS: RingBuf = RingBuf::with_byte_size(256 * 1024, 0);
+
+fn owner() -> BioOwner {
+    BioOwner {
+        tgid: (bpf_get_current_pid_tgid() >> 32) as u32,
+        comm: bpf_get_current_comm().unwrap_or([0; 16]),
+    }
+}
+
+/// Runs in the context of the process submitting the bio, which may not be the one issuing
+/// the request to the device (e.g. when a plug is flushed from a kworker).
+#[tracepoint]
+pub fn stalk_bio_queue(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const BlockBioQueueInfo) };
+    let key = BioKey {
+        dev: info.dev,
+        padding: 0,
+        sector: info.sector,
+    };
+    unsafe {
+        let queued = &raw mut BIO_QUEUED;
+        let _ = (*queued).insert(&key, &owner(), 0);
+    }
+    0
+}
+
+#[tracepoint]
+pub fn stalk_rq_issue(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const BlockRqIssueInfo) };
+    let key = BioKey {
+        dev: info.dev,
+        padding: 0,
+        sector: info.sector,
+    };
+    let rwbs_offset = unsafe { core::ptr::read_volatile(&raw const BIO_RWBS_OFFSET) };
+    unsafe {
+        let queued = &raw mut BIO_QUEUED;
+        let owner = match (*queued).get(&key).copied() {
+            Some(owner) => {
+                let _ = (*queued).remove(&key);
+                owner
+            }
+            None => owner(),
+        };
+        let start = BioStart {
+            ts: bpf_ktime_get_ns(),
+            owner,
+            bytes: info.bytes,
+            rwbs: ctx
+                .read_at::<[u8; 8]>(rwbs_offset as usize)
+                .unwrap_or([0; 8]),
+        };
+        let starts = &raw mut BIO_START;
+        let _ = (*starts).insert(&key, &start, 0);
+    }
+    0
+}
+
+#[tracepoint]
+pub fn stalk_rq_complete(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const BlockRqCompleteInfo) };
+    let key = BioKey {
+        dev: info.dev,
+        padding: 0,
+        sector: info.sector,
+    };
+    unsafe {
+        let starts = &raw mut BIO_START;
+        let Some(start) = (*starts).get(&key).copied() else {
+            return 0;
+        };
+        let _ = (*starts).remove(&key);
+        let event = RawBioEvent {
+            tgid: start.owner.tgid,
+            comm: start.owner.comm,
+            dev: info.dev,
+            sector: info.sector,
+            bytes: start.bytes,
+            error: info.error,
+            rwbs: start.rwbs,
+            latency_ns: bpf_ktime_get_ns() - start.ts,
+        };
+        let event_map = &raw mut BIO_EVENTS;
+        if let Some(mut buf) = (*event_map).reserve::<RawBioEvent>(0) {
+            buf.write(event);
+            buf.submit(0);
+        }
+    }
+    0
+}
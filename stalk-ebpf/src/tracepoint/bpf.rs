@@ -0,0 +1,84 @@
+use aya_ebpf::{
+    EbpfContext,
+    helpers::bpf_probe_read_user,
+    macros::{map, tracepoint},
+    maps::{LruHashMap, RingBuf},
+    programs::TracePointContext,
+};
+use stalk_common::{RawBpfEvent, SysEnterBpfInfo};
+
+use crate::syscall;
+
+const BPF_MAP_CREATE: u32 = 0;
+const BPF_PROG_LOAD: u32 = 5;
+const BPF_OBJ_PIN: u32 = 6;
+const BPF_PROG_ATTACH: u32 = 8;
+const BPF_RAW_TRACEPOINT_OPEN: u32 = 17;
+const BPF_BTF_LOAD: u32 = 18;
+const BPF_LINK_CREATE: u32 = 28;
+
+/// `prog_name` in the `BPF_PROG_LOAD` member of `union bpf_attr`.
+const PROG_NAME_OFFSET: usize = 48;
+/// `map_name` in the `BPF_MAP_CREATE` member of `union bpf_attr`.
+const MAP_NAME_OFFSET: usize = 28;
+
+#[map]
+static mut BPF_EVENTS: RingBuf = RingBuf::with_byte_size(64 * 1024, 0);
+
+/// Thread -> bpf command waiting to return.
+#[map]
+static mut BPF_PENDING: LruHashMap<u64, RawBpfEvent> = LruHashMap::with_max_entries(1024, 0);
+
+/// Records commands that add something to the kernel. Lookups and updates are too frequent
+/// to be worth auditing.
+#[tracepoint]
+pub fn stalk_bpf(ctx: TracePointContext) -> u32 {
+    try_stalk_bpf(ctx).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_bpf_exit(ctx: TracePointContext) -> u32 {
+    unsafe { syscall::exit(&ctx, &raw mut BPF_PENDING, &raw mut BPF_EVENTS) }
+        .unwrap_or_else(|ret| ret)
+}
+
+fn try_stalk_bpf(ctx: TracePointContext) -> Result<u32, u32> {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterBpfInfo) };
+    let cmd = info.cmd as u32;
+    if !matches!(
+        cmd,
+        BPF_MAP_CREATE
+            | BPF_PROG_LOAD
+            | BPF_OBJ_PIN
+            | BPF_PROG_ATTACH
+            | BPF_RAW_TRACEPOINT_OPEN
+            | BPF_BTF_LOAD
+            | BPF_LINK_CREATE
+    ) {
+        return Ok(0);
+    }
+    let caller = syscall::caller()?;
+    let mut event = RawBpfEvent {
+        pid: caller.pid,
+        tgid: caller.tgid,
+        uid: caller.uid,
+        comm: caller.comm,
+        cmd,
+        attr_type: 0,
+        name: [0; 16],
+        ret: 0,
+    };
+    let name_offset = match cmd {
+        BPF_PROG_LOAD => Some(PROG_NAME_OFFSET),
+        BPF_MAP_CREATE => Some(MAP_NAME_OFFSET),
+        _ => None,
+    };
+    if let Some(name_offset) = name_offset {
+        unsafe {
+            event.attr_type = bpf_probe_read_user(info.uattr as *const u32).unwrap_or(0);
+            event.name = bpf_probe_read_user(info.uattr.add(name_offset) as *const [u8; 16])
+                .unwrap_or([0; 16]);
+        }
+    }
+    unsafe { syscall::enter(&raw mut BPF_PENDING, &event) }
+}
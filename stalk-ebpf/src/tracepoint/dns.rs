@@ -0,0 +1,262 @@
+use aya_ebpf::{
+    EbpfContext,
+    helpers::{
+        bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_probe_read_kernel, bpf_probe_read_user,
+        r#gen,
+    },
+    macros::{map, tracepoint},
+    maps::{HashMap, LruHashMap, RingBuf},
+    programs::TracePointContext,
+};
+use stalk_common::{
+    DNS_MAX_LEN, RawDnsEvent, SysEnterBindInfo, SysEnterReadInfo, SysEnterRecvfromInfo,
+    SysEnterRecvmmsgInfo, SysEnterRecvmsgInfo, SysExitInfo,
+};
+
+use crate::task;
+
+const AF_INET: u16 = 2;
+const AF_INET6: u16 = 10;
+const SOCK_DGRAM: u16 = 2;
+const DNS_PORT: u16 = 53;
+/// Size of the DNS header.
+const DNS_MIN_LEN: i64 = 12;
+/// `msg_len` follows the `struct user_msghdr` in a `struct mmsghdr`.
+const MMSGHDR_LEN_OFFSET: usize = 56;
+
+/// Offsets leading from an open file to the type of its socket, set by userspace from BTF.
+#[unsafe(no_mangle)]
+static FILE_PRIVATE_DATA_OFFSET: u32 = 200;
+#[unsafe(no_mangle)]
+static SOCKET_TYPE_OFFSET: u32 = 4;
+
+/// Where a receive call will put the payload and the peer address. `addr` is null on a
+/// socket connected to port 53, and `msg_len` is where recvmmsg stores the first length.
+#[derive(Copy, Clone)]
+struct PendingRecv {
+    buf: *const u8,
+    addr: *const u8,
+    msg_len: *const u32,
+}
+
+#[map]
+static mut DNS_EVENTS: RingBuf = RingBuf::with_byte_size(1024 * 1024, 0);
+
+#[map]
+static mut DNS_PENDING: HashMap<u64, PendingRecv> = HashMap::with_max_entries(10240, 0);
+
+/// Thread -> descriptor being connected to port 53.
+#[map]
+static mut DNS_CONNECTING: LruHashMap<u64, i32> = LruHashMap::with_max_entries(1024, 0);
+
+/// `tgid << 32 | fd` of UDP sockets connected to port 53 -> their file, which tells a
+/// descriptor closed and reused since.
+#[map]
+static mut DNS_SOCKETS: LruHashMap<u64, usize> = LruHashMap::with_max_entries(10240, 0);
+
+#[tracepoint]
+pub fn stalk_dns_connect(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterBindInfo) };
+    let tgid_pid = bpf_get_current_pid_tgid();
+    let fd = info.fd as i32;
+    unsafe {
+        if is_dns_addr(info.umyaddr) {
+            let connecting = &raw mut DNS_CONNECTING;
+            let _ = (*connecting).insert(&tgid_pid, &fd, 0);
+        } else {
+            // Connected elsewhere, or disconnected with AF_UNSPEC.
+            let sockets = &raw mut DNS_SOCKETS;
+            let _ = (*sockets).remove(&socket_key(tgid_pid, fd));
+        }
+    }
+    0
+}
+
+/// Remembers sockets connected to port 53 once the connect succeeded.
+#[tracepoint]
+pub fn stalk_dns_connect_exit(ctx: TracePointContext) -> u32 {
+    let tgid_pid = bpf_get_current_pid_tgid();
+    unsafe {
+        let connecting = &raw mut DNS_CONNECTING;
+        let Some(fd) = (*connecting).get(&tgid_pid).copied() else {
+            return 0;
+        };
+        let _ = (*connecting).remove(&tgid_pid);
+        if (*(ctx.as_ptr() as *const SysExitInfo)).ret != 0 {
+            return 0;
+        }
+        let Ok(file) = task::file(r#gen::bpf_get_current_task() as *const u8, fd) else {
+            return 0;
+        };
+        let data_offset = core::ptr::read_volatile(&raw const FILE_PRIVATE_DATA_OFFSET) as usize;
+        let type_offset = core::ptr::read_volatile(&raw const SOCKET_TYPE_OFFSET) as usize;
+        let Ok(socket) = bpf_probe_read_kernel(file.add(data_offset) as *const *const u8) else {
+            return 0;
+        };
+        // DNS over TCP frames messages with their length.
+        if bpf_probe_read_kernel(socket.add(type_offset) as *const u16) != Ok(SOCK_DGRAM) {
+            return 0;
+        }
+        let sockets = &raw mut DNS_SOCKETS;
+        let _ = (*sockets).insert(&socket_key(tgid_pid, fd), &(file as usize), 0);
+    }
+    0
+}
+
+#[tracepoint]
+pub fn stalk_dns_read(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterReadInfo) };
+    if !is_dns_socket(info.fd as i32) {
+        return 0;
+    }
+    insert(PendingRecv {
+        buf: info.buf as *const u8,
+        addr: core::ptr::null(),
+        msg_len: core::ptr::null(),
+    })
+}
+
+#[tracepoint]
+pub fn stalk_dns_recvfrom(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterRecvfromInfo) };
+    let addr = if is_dns_socket(info.fd as i32) {
+        core::ptr::null()
+    } else if info.addr.is_null() {
+        // Without an address there is no telling the peer's port.
+        return 0;
+    } else {
+        info.addr
+    };
+    insert(PendingRecv {
+        buf: info.ubuf,
+        addr,
+        msg_len: core::ptr::null(),
+    })
+}
+
+#[tracepoint]
+pub fn stalk_dns_recvmsg(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterRecvmsgInfo) };
+    recv_msg(info.fd as i32, info.msg, core::ptr::null())
+}
+
+/// Only the first message is captured.
+#[tracepoint]
+pub fn stalk_dns_recvmmsg(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterRecvmmsgInfo) };
+    let msg_len = unsafe { info.mmsg.add(MMSGHDR_LEN_OFFSET) } as *const u32;
+    recv_msg(info.fd as i32, info.mmsg, msg_len)
+}
+
+/// Attached to the `sys_exit_*` tracepoint of every receive call above.
+#[tracepoint]
+pub fn stalk_dns_exit(ctx: TracePointContext) -> u32 {
+    try_stalk_dns_exit(ctx).unwrap_or_else(|ret| ret)
+}
+
+fn recv_msg(fd: i32, msg: *const u8, msg_len: *const u32) -> u32 {
+    // struct user_msghdr { void *msg_name; int msg_namelen; struct iovec *msg_iov; ... }
+    let (addr, iov) = unsafe {
+        (
+            bpf_probe_read_user(msg as *const *const u8).unwrap_or(core::ptr::null()),
+            bpf_probe_read_user(msg.add(16) as *const *const u8).unwrap_or(core::ptr::null()),
+        )
+    };
+    let addr = if is_dns_socket(fd) {
+        core::ptr::null()
+    } else if addr.is_null() {
+        return 0;
+    } else {
+        addr
+    };
+    if iov.is_null() {
+        return 0;
+    }
+    // Only the first iovec is captured.
+    let buf = unsafe { bpf_probe_read_user(iov as *const *const u8).unwrap_or(core::ptr::null()) };
+    insert(PendingRecv { buf, addr, msg_len })
+}
+
+fn socket_key(tgid_pid: u64, fd: i32) -> u64 {
+    (tgid_pid & !0xFFFFFFFF) | fd as u32 as u64
+}
+
+/// Whether `sockaddr` is an IPv4 or IPv6 address on port 53.
+unsafe fn is_dns_addr(sockaddr: *const u8) -> bool {
+    unsafe {
+        // sockaddr_in and sockaddr_in6 both start with family and port.
+        let family = bpf_probe_read_user(sockaddr as *const u16).unwrap_or(0);
+        let port = bpf_probe_read_user(sockaddr.add(2) as *const u16).unwrap_or(0);
+        (family == AF_INET || family == AF_INET6) && u16::from_be(port) == DNS_PORT
+    }
+}
+
+/// Whether `fd` of the current process is a UDP socket connected to port 53.
+fn is_dns_socket(fd: i32) -> bool {
+    unsafe {
+        let sockets = &raw mut DNS_SOCKETS;
+        let key = socket_key(bpf_get_current_pid_tgid(), fd);
+        let Some(file) = (*sockets).get(&key).copied() else {
+            return false;
+        };
+        task::file(r#gen::bpf_get_current_task() as *const u8, fd) == Ok(file as *const u8)
+    }
+}
+
+fn insert(recv: PendingRecv) -> u32 {
+    let tgid_pid = bpf_get_current_pid_tgid();
+    unsafe {
+        let pending = &raw mut DNS_PENDING;
+        let _ = (*pending).insert(&tgid_pid, &recv, 0);
+    }
+    0
+}
+
+fn try_stalk_dns_exit(ctx: TracePointContext) -> Result<u32, u32> {
+    let tgid_pid = bpf_get_current_pid_tgid();
+    let recv = unsafe {
+        let pending = &raw mut DNS_PENDING;
+        let Some(recv) = (*pending).get(&tgid_pid).copied() else {
+            return Ok(0);
+        };
+        let _ = (*pending).remove(&tgid_pid);
+        recv
+    };
+    let mut ret = unsafe { (*(ctx.as_ptr() as *const SysExitInfo)).ret };
+    if ret > 0 && !recv.msg_len.is_null() {
+        // recvmmsg returns the number of messages.
+        ret = unsafe { bpf_probe_read_user(recv.msg_len) }.map_err(|_| 0u32)? as i64;
+    }
+    if ret < DNS_MIN_LEN || recv.buf.is_null() {
+        return Ok(0);
+    }
+    if !recv.addr.is_null() && !unsafe { is_dns_addr(recv.addr) } {
+        return Ok(0);
+    }
+    let mut len = ret as u32;
+    if len > DNS_MAX_LEN as u32 {
+        len = DNS_MAX_LEN as u32;
+    }
+    unsafe {
+        let event_map = &raw mut DNS_EVENTS;
+        let Some(mut buf) = (*event_map).reserve::<RawDnsEvent>(0) else {
+            return Ok(0);
+        };
+        let event = buf.as_mut_ptr();
+        (*event).pid = (tgid_pid & 0xFFFFFFFF) as u32;
+        (*event).tgid = (tgid_pid >> 32) as u32;
+        (*event).comm = bpf_get_current_comm().unwrap_or([0; 16]);
+        (*event).len = ret as u32;
+        let read = r#gen::bpf_probe_read_user(
+            (*event).data.as_mut_ptr() as *mut _,
+            len,
+            recv.buf as *const _,
+        );
+        if read != 0 {
+            buf.discard(0);
+            return Ok(0);
+        }
+        buf.submit(0);
+    }
+    Ok(0)
+}
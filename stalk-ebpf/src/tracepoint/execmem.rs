@@ -0,0 +1,158 @@
+use aya_ebpf::{
+    EbpfContext,
+    helpers::{bpf_get_current_pid_tgid, bpf_probe_read_user_str_bytes, r#gen},
+    macros::{map, tracepoint},
+    maps::{LruHashMap, RingBuf},
+    programs::TracePointContext,
+};
+use stalk_common::{
+    ExecMemBacking, ExecMemKind, RawExecMemEvent, SysEnterExecveatInfo, SysEnterMemfdCreateInfo,
+    SysEnterMmapInfo, SysEnterMprotectInfo,
+};
+
+use crate::{
+    kprobe::execmem::{EXECMEM_PENDING, MEMFD_PREFIX, file_name},
+    syscall, task,
+};
+
+const PROT_WRITE: u64 = 0x2;
+const PROT_EXEC: u64 = 0x4;
+const MAP_ANONYMOUS: u64 = 0x20;
+
+#[map]
+static mut EXECMEM_EVENTS: RingBuf = RingBuf::with_byte_size(256 * 1024, 0);
+
+/// Process -> execveat waiting to return. A thread other than the leader that execs returns
+/// with the leader's pid, so these can't be keyed by thread.
+#[map]
+static mut EXECVEAT_PENDING: LruHashMap<u32, RawExecMemEvent> =
+    LruHashMap::with_max_entries(1024, 0);
+
+/// Executable mappings that are anonymous or writable; plain library loads are skipped.
+#[tracepoint]
+pub fn stalk_mmap(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterMmapInfo) };
+    let anonymous = info.flags & MAP_ANONYMOUS != 0;
+    let writable = info.prot & PROT_WRITE != 0;
+    if info.prot & PROT_EXEC == 0 || !(anonymous || writable) {
+        return 0;
+    }
+    let Ok(mut event) = new_event(ExecMemKind::Mmap) else {
+        return 0;
+    };
+    event.addr = info.addr;
+    event.len = info.len;
+    event.prot = info.prot as u32;
+    event.flags = info.flags as u32;
+    event.fd = info.fd as i32;
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+/// Every mprotect adding `PROT_EXEC`; `stalk_mprotect_fixup` then records what the region maps.
+#[tracepoint]
+pub fn stalk_mprotect(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterMprotectInfo) };
+    if info.prot & PROT_EXEC == 0 {
+        return 0;
+    }
+    let Ok(mut event) = new_event(ExecMemKind::Mprotect) else {
+        return 0;
+    };
+    event.addr = info.start;
+    event.len = info.len;
+    event.prot = info.prot as u32;
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_memfd_create(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterMemfdCreateInfo) };
+    let Ok(mut event) = new_event(ExecMemKind::MemfdCreate) else {
+        return 0;
+    };
+    event.flags = info.flags as u32;
+    unsafe {
+        let _ = bpf_probe_read_user_str_bytes(info.uname as *const u8, &mut event.name);
+    }
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+/// Execveat of a memfd, which is the file behind `fd` whatever the flags.
+#[tracepoint]
+pub fn stalk_execveat(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterExecveatInfo) };
+    let Ok(mut event) = new_event(ExecMemKind::Execveat) else {
+        return 0;
+    };
+    event.fd = info.fd as i32;
+    event.flags = info.flags as u32;
+    unsafe {
+        let Ok(file) = task::file(r#gen::bpf_get_current_task() as *const u8, event.fd) else {
+            return 0;
+        };
+        if file_name(file, &mut event.name).is_err()
+            || event.name[..MEMFD_PREFIX.len()] != MEMFD_PREFIX
+        {
+            return 0;
+        }
+        let pending = &raw mut EXECVEAT_PENDING;
+        let _ = (*pending).insert(&event.tgid, &event, 0);
+    }
+    0
+}
+
+/// Attached to the `sys_exit_*` tracepoint of every syscall above but execveat. Read-only
+/// file mappings made executable are relocations by the loader, and dropped.
+#[tracepoint]
+pub fn stalk_execmem_exit(ctx: TracePointContext) -> u32 {
+    unsafe {
+        let Some(event) = syscall::take(&raw mut EXECMEM_PENDING) else {
+            return 0;
+        };
+        if event.kind == ExecMemKind::Mprotect
+            && event.backing == ExecMemBacking::File
+            && event.prot as u64 & PROT_WRITE == 0
+        {
+            return 0;
+        }
+        syscall::submit(&ctx, &raw mut EXECMEM_EVENTS, event);
+    }
+    0
+}
+
+#[tracepoint]
+pub fn stalk_execveat_exit(ctx: TracePointContext) -> u32 {
+    let tgid = (bpf_get_current_pid_tgid() >> 32) as u32;
+    unsafe {
+        let pending = &raw mut EXECVEAT_PENDING;
+        let Some(event) = (*pending).get(&tgid).copied() else {
+            return 0;
+        };
+        let _ = (*pending).remove(&tgid);
+        syscall::submit(&ctx, &raw mut EXECMEM_EVENTS, event);
+    }
+    0
+}
+
+fn new_event(kind: ExecMemKind) -> Result<RawExecMemEvent, u32> {
+    let caller = syscall::caller()?;
+    Ok(RawExecMemEvent {
+        kind,
+        pid: caller.pid,
+        tgid: caller.tgid,
+        uid: caller.uid,
+        comm: caller.comm,
+        addr: 0,
+        len: 0,
+        prot: 0,
+        flags: 0,
+        fd: -1,
+        backing: ExecMemBacking::Unknown,
+        name: [0; 64],
+        ret: 0,
+    })
+}
+
+fn insert(event: &RawExecMemEvent) -> Result<u32, u32> {
+    unsafe { syscall::enter(&raw mut EXECMEM_PENDING, event) }
+}
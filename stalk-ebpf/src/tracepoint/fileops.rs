@@ -0,0 +1,227 @@
+use aya_ebpf::{
+    EbpfContext,
+    helpers::bpf_probe_read_user_str_bytes,
+    macros::{map, tracepoint},
+    maps::{LruHashMap, RingBuf},
+    programs::TracePointContext,
+};
+use stalk_common::{
+    FileOpKind, RawFileOpEvent, SysEnterFtruncateInfo, SysEnterLinkInfo, SysEnterLinkatInfo,
+    SysEnterMkdirInfo, SysEnterMkdiratInfo, SysEnterRenameInfo, SysEnterRenameat2Info,
+    SysEnterRenameatInfo, SysEnterRmdirInfo, SysEnterSymlinkInfo, SysEnterSymlinkatInfo,
+    SysEnterTruncateInfo, SysEnterUnlinkInfo, SysEnterUnlinkatInfo,
+};
+
+use crate::syscall;
+
+const AT_FDCWD: i32 = -100;
+const AT_REMOVEDIR: i64 = 0x200;
+
+/// Path arguments of a syscall: a path and the directory it is relative to.
+type PathArg = (i32, *const core::ffi::c_char);
+
+const NO_PATH: PathArg = (AT_FDCWD, core::ptr::null());
+
+#[map]
+static mut FILEOPS_EVENTS: RingBuf = RingBuf::with_byte_size(256 * 1024, 0);
+
+/// Thread -> operation waiting for its syscall to return.
+#[map]
+static mut FILEOPS_PENDING: LruHashMap<u64, RawFileOpEvent> =
+    LruHashMap::with_max_entries(10240, 0);
+
+#[tracepoint]
+pub fn stalk_unlinkat(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterUnlinkatInfo) };
+    let kind = if info.flag & AT_REMOVEDIR != 0 {
+        FileOpKind::Rmdir
+    } else {
+        FileOpKind::Unlink
+    };
+    enter(
+        kind,
+        (info.dfd as i32, info.pathname),
+        NO_PATH,
+        info.flag as u64,
+    )
+    .unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_unlink(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterUnlinkInfo) };
+    enter(FileOpKind::Unlink, (AT_FDCWD, info.pathname), NO_PATH, 0).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_renameat2(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterRenameat2Info) };
+    enter(
+        FileOpKind::Rename,
+        (info.olddfd as i32, info.oldname),
+        (info.newdfd as i32, info.newname),
+        info.flags,
+    )
+    .unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_renameat(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterRenameatInfo) };
+    enter(
+        FileOpKind::Rename,
+        (info.olddfd as i32, info.oldname),
+        (info.newdfd as i32, info.newname),
+        0,
+    )
+    .unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_rename(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterRenameInfo) };
+    enter(
+        FileOpKind::Rename,
+        (AT_FDCWD, info.oldname),
+        (AT_FDCWD, info.newname),
+        0,
+    )
+    .unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_linkat(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterLinkatInfo) };
+    enter(
+        FileOpKind::Link,
+        (info.olddfd as i32, info.oldname),
+        (info.newdfd as i32, info.newname),
+        info.flags as u64,
+    )
+    .unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_link(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterLinkInfo) };
+    enter(
+        FileOpKind::Link,
+        (AT_FDCWD, info.oldname),
+        (AT_FDCWD, info.newname),
+        0,
+    )
+    .unwrap_or_else(|ret| ret)
+}
+
+/// The first path is the content of the link, so it isn't resolved against any directory.
+#[tracepoint]
+pub fn stalk_symlinkat(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterSymlinkatInfo) };
+    enter(
+        FileOpKind::Symlink,
+        (AT_FDCWD, info.oldname),
+        (info.newdfd as i32, info.newname),
+        0,
+    )
+    .unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_symlink(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterSymlinkInfo) };
+    enter(
+        FileOpKind::Symlink,
+        (AT_FDCWD, info.oldname),
+        (AT_FDCWD, info.newname),
+        0,
+    )
+    .unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_mkdirat(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterMkdiratInfo) };
+    enter(
+        FileOpKind::Mkdir,
+        (info.dfd as i32, info.pathname),
+        NO_PATH,
+        info.mode,
+    )
+    .unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_mkdir(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterMkdirInfo) };
+    enter(
+        FileOpKind::Mkdir,
+        (AT_FDCWD, info.pathname),
+        NO_PATH,
+        info.mode,
+    )
+    .unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_rmdir(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterRmdirInfo) };
+    enter(FileOpKind::Rmdir, (AT_FDCWD, info.pathname), NO_PATH, 0).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_truncate(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterTruncateInfo) };
+    enter(
+        FileOpKind::Truncate,
+        (AT_FDCWD, info.path),
+        NO_PATH,
+        info.length as u64,
+    )
+    .unwrap_or_else(|ret| ret)
+}
+
+/// The file is only known by its descriptor, left for userspace to resolve.
+#[tracepoint]
+pub fn stalk_ftruncate(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterFtruncateInfo) };
+    enter(
+        FileOpKind::Truncate,
+        (info.fd as i32, core::ptr::null()),
+        NO_PATH,
+        info.length as u64,
+    )
+    .unwrap_or_else(|ret| ret)
+}
+
+/// Attached to the `sys_exit_*` tracepoint of every syscall above.
+#[tracepoint]
+pub fn stalk_fileops_exit(ctx: TracePointContext) -> u32 {
+    unsafe { syscall::exit(&ctx, &raw mut FILEOPS_PENDING, &raw mut FILEOPS_EVENTS) }
+        .unwrap_or_else(|ret| ret)
+}
+
+fn enter(kind: FileOpKind, path: PathArg, path2: PathArg, arg: u64) -> Result<u32, u32> {
+    let caller = syscall::caller()?;
+    let mut event = RawFileOpEvent {
+        kind,
+        pid: caller.pid,
+        tgid: caller.tgid,
+        uid: caller.uid,
+        comm: caller.comm,
+        dfd: path.0,
+        dfd2: path2.0,
+        path: [0; 64],
+        path2: [0; 64],
+        arg,
+        ret: 0,
+    };
+    unsafe {
+        if !path.1.is_null() {
+            let _ = bpf_probe_read_user_str_bytes(path.1 as *const u8, &mut event.path);
+        }
+        if !path2.1.is_null() {
+            let _ = bpf_probe_read_user_str_bytes(path2.1 as *const u8, &mut event.path2);
+        }
+        syscall::enter(&raw mut FILEOPS_PENDING, &event)
+    }
+}
@@ -0,0 +1,49 @@
+use aya_ebpf::{
+    EbpfContext,
+    helpers::{bpf_get_current_comm, bpf_get_current_pid_tgid, r#gen},
+    macros::{map, tracepoint},
+    maps::RingBuf,
+    programs::TracePointContext,
+};
+use stalk_common::{RawTracepointEvent, TRACEPOINT_MAX_RECORD};
+
+/// Bytes of the record to copy, set by userspace from the tracepoint's format file.
+#[unsafe(no_mangle)]
+static TRACEPOINT_RECORD_SIZE: u32 = 64;
+
+#[map]
+static mut TRACEPOINT_EVENTS: RingBuf = RingBuf::with_byte_size(1024 * 1024, 0);
+
+/// Copies the raw record of whichever tracepoint it is attached to; fields are decoded in
+/// userspace.
+#[tracepoint]
+pub fn stalk_tracepoint(ctx: TracePointContext) -> u32 {
+    let tgid_pid = bpf_get_current_pid_tgid();
+    let mut len = unsafe { core::ptr::read_volatile(&raw const TRACEPOINT_RECORD_SIZE) };
+    if len > TRACEPOINT_MAX_RECORD as u32 {
+        len = TRACEPOINT_MAX_RECORD as u32;
+    }
+    unsafe {
+        let event_map = &raw mut TRACEPOINT_EVENTS;
+        let Some(mut buf) = (*event_map).reserve::<RawTracepointEvent>(0) else {
+            return 0;
+        };
+        let event = buf.as_mut_ptr();
+        (*event).pid = (tgid_pid & 0xFFFFFFFF) as u32;
+        (*event).tgid = (tgid_pid >> 32) as u32;
+        (*event).comm = bpf_get_current_comm().unwrap_or([0; 16]);
+        (*event).len = len;
+        if len == 0
+            || r#gen::bpf_probe_read_kernel(
+                (*event).data.as_mut_ptr() as *mut _,
+                len,
+                ctx.as_ptr() as *const _,
+            ) != 0
+        {
+            buf.discard(0);
+            return 0;
+        }
+        buf.submit(0);
+    }
+    0
+}
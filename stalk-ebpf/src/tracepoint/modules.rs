@@ -0,0 +1,103 @@
+use aya_ebpf::{
+    EbpfContext,
+    helpers::{
+        bpf_get_current_pid_tgid, bpf_probe_read_kernel_str_bytes, bpf_probe_read_user_str_bytes,
+    },
+    macros::{map, tracepoint},
+    maps::{LruHashMap, RingBuf},
+    programs::TracePointContext,
+};
+use stalk_common::{
+    ModuleEventKind, ModuleLoadInfo, RawModuleEvent, SysEnterDeleteModuleInfo,
+    SysEnterFinitModuleInfo,
+};
+
+use crate::syscall;
+
+#[map]
+static mut MODULES_EVENTS: RingBuf = RingBuf::with_byte_size(64 * 1024, 0);
+
+/// Thread -> module syscall waiting to return.
+#[map]
+static mut MODULES_PENDING: LruHashMap<u64, RawModuleEvent> = LruHashMap::with_max_entries(1024, 0);
+
+#[tracepoint]
+pub fn stalk_init_module(_ctx: TracePointContext) -> u32 {
+    match new_event(ModuleEventKind::Init) {
+        Ok(event) => insert(&event).unwrap_or_else(|ret| ret),
+        Err(ret) => ret,
+    }
+}
+
+#[tracepoint]
+pub fn stalk_finit_module(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterFinitModuleInfo) };
+    match new_event(ModuleEventKind::Finit) {
+        Ok(mut event) => {
+            event.fd = info.fd as i32;
+            insert(&event).unwrap_or_else(|ret| ret)
+        }
+        Err(ret) => ret,
+    }
+}
+
+#[tracepoint]
+pub fn stalk_delete_module(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterDeleteModuleInfo) };
+    match new_event(ModuleEventKind::Delete) {
+        Ok(mut event) => {
+            unsafe {
+                let _ = bpf_probe_read_user_str_bytes(info.name_user as *const u8, &mut event.name);
+            }
+            insert(&event).unwrap_or_else(|ret| ret)
+        }
+        Err(ret) => ret,
+    }
+}
+
+/// `module:module_load` fires inside `init_module`/`finit_module` once the image is parsed,
+/// and fills in the module name for the pending syscall.
+#[tracepoint]
+pub fn stalk_module_load(ctx: TracePointContext) -> u32 {
+    let tgid_pid = bpf_get_current_pid_tgid();
+    unsafe {
+        let pending = &raw mut MODULES_PENDING;
+        let Some(event) = (*pending).get_ptr_mut(&tgid_pid) else {
+            return 0;
+        };
+        let load_info: *const ModuleLoadInfo = ctx.as_ptr() as *const ModuleLoadInfo;
+        (*event).taints = (*load_info).taints;
+        let offset = ((*load_info).name_loc & 0xFFFF) as usize;
+        let _ = bpf_probe_read_kernel_str_bytes(
+            (ctx.as_ptr() as *const u8).add(offset),
+            &mut (*event).name,
+        );
+    }
+    0
+}
+
+/// Attached to the `sys_exit_*` tracepoint of every syscall above.
+#[tracepoint]
+pub fn stalk_modules_exit(ctx: TracePointContext) -> u32 {
+    unsafe { syscall::exit(&ctx, &raw mut MODULES_PENDING, &raw mut MODULES_EVENTS) }
+        .unwrap_or_else(|ret| ret)
+}
+
+fn new_event(kind: ModuleEventKind) -> Result<RawModuleEvent, u32> {
+    let caller = syscall::caller()?;
+    Ok(RawModuleEvent {
+        kind,
+        pid: caller.pid,
+        tgid: caller.tgid,
+        uid: caller.uid,
+        comm: caller.comm,
+        fd: -1,
+        taints: 0,
+        name: [0; 56],
+        ret: 0,
+    })
+}
+
+fn insert(event: &RawModuleEvent) -> Result<u32, u32> {
+    unsafe { syscall::enter(&raw mut MODULES_PENDING, event) }
+}
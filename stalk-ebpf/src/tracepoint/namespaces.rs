@@ -0,0 +1,156 @@
+use aya_ebpf::{
+    EbpfContext,
+    helpers::{bpf_probe_read_user, bpf_probe_read_user_str_bytes},
+    macros::{map, tracepoint},
+    maps::{LruHashMap, RingBuf},
+    programs::TracePointContext,
+};
+use stalk_common::{
+    NamespaceOpKind, RawNamespaceEvent, SysEnterChrootInfo, SysEnterClone3Info, SysEnterCloneInfo,
+    SysEnterMountInfo, SysEnterPivotRootInfo, SysEnterSetnsInfo, SysEnterUmountInfo,
+    SysEnterUnshareInfo,
+};
+
+use crate::syscall;
+
+/// `CLONE_NEWNS | CLONE_NEWCGROUP | CLONE_NEWUTS | CLONE_NEWIPC | CLONE_NEWUSER | CLONE_NEWPID
+/// | CLONE_NEWNET | CLONE_NEWTIME`
+const CLONE_NEW_MASK: u64 = 0x7E02_0080;
+
+#[map]
+static mut NAMESPACES_EVENTS: RingBuf = RingBuf::with_byte_size(256 * 1024, 0);
+
+/// Thread -> operation waiting for its syscall to return.
+#[map]
+static mut NAMESPACES_PENDING: LruHashMap<u64, RawNamespaceEvent> =
+    LruHashMap::with_max_entries(10240, 0);
+
+#[tracepoint]
+pub fn stalk_mount(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterMountInfo) };
+    let Ok(mut event) = new_event(NamespaceOpKind::Mount, info.flags) else {
+        return 0;
+    };
+    unsafe {
+        let _ = bpf_probe_read_user_str_bytes(info.dev_name as *const u8, &mut event.path);
+        let _ = bpf_probe_read_user_str_bytes(info.dir_name as *const u8, &mut event.path2);
+        let _ = bpf_probe_read_user_str_bytes(info.fs_type as *const u8, &mut event.fstype);
+    }
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_umount(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterUmountInfo) };
+    let Ok(mut event) = new_event(NamespaceOpKind::Umount, info.flags as u64) else {
+        return 0;
+    };
+    unsafe {
+        let _ = bpf_probe_read_user_str_bytes(info.name as *const u8, &mut event.path);
+    }
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_setns(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterSetnsInfo) };
+    let Ok(mut event) = new_event(NamespaceOpKind::Setns, info.flags as u64) else {
+        return 0;
+    };
+    event.fd = info.fd as i32;
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_unshare(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterUnshareInfo) };
+    let Ok(event) = new_event(NamespaceOpKind::Unshare, info.unshare_flags) else {
+        return 0;
+    };
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_pivot_root(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterPivotRootInfo) };
+    let Ok(mut event) = new_event(NamespaceOpKind::PivotRoot, 0) else {
+        return 0;
+    };
+    unsafe {
+        let _ = bpf_probe_read_user_str_bytes(info.new_root as *const u8, &mut event.path);
+        let _ = bpf_probe_read_user_str_bytes(info.put_old as *const u8, &mut event.path2);
+    }
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_chroot(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterChrootInfo) };
+    let Ok(mut event) = new_event(NamespaceOpKind::Chroot, 0) else {
+        return 0;
+    };
+    unsafe {
+        let _ = bpf_probe_read_user_str_bytes(info.filename as *const u8, &mut event.path);
+    }
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+/// Only clones that create a namespace are recorded.
+#[tracepoint]
+pub fn stalk_clone(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterCloneInfo) };
+    if info.clone_flags & CLONE_NEW_MASK == 0 {
+        return 0;
+    }
+    let Ok(event) = new_event(NamespaceOpKind::Clone, info.clone_flags) else {
+        return 0;
+    };
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_clone3(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterClone3Info) };
+    let flags = unsafe { bpf_probe_read_user(info.uargs).unwrap_or(0) };
+    if flags & CLONE_NEW_MASK == 0 {
+        return 0;
+    }
+    let Ok(event) = new_event(NamespaceOpKind::Clone, flags) else {
+        return 0;
+    };
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+/// Attached to the `sys_exit_*` tracepoint of every syscall above.
+#[tracepoint]
+pub fn stalk_namespaces_exit(ctx: TracePointContext) -> u32 {
+    unsafe {
+        syscall::exit(
+            &ctx,
+            &raw mut NAMESPACES_PENDING,
+            &raw mut NAMESPACES_EVENTS,
+        )
+    }
+    .unwrap_or_else(|ret| ret)
+}
+
+fn new_event(kind: NamespaceOpKind, flags: u64) -> Result<RawNamespaceEvent, u32> {
+    let caller = syscall::caller()?;
+    Ok(RawNamespaceEvent {
+        kind,
+        pid: caller.pid,
+        tgid: caller.tgid,
+        uid: caller.uid,
+        comm: caller.comm,
+        flags,
+        fd: -1,
+        fstype: [0; 16],
+        path: [0; 64],
+        path2: [0; 64],
+        ret: 0,
+    })
+}
+
+fn insert(event: &RawNamespaceEvent) -> Result<u32, u32> {
+    unsafe { syscall::enter(&raw mut NAMESPACES_PENDING, event) }
+}
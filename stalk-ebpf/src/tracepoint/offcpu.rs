@@ -0,0 +1,110 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use aya_ebpf::{
+    EbpfContext,
+    bindings::BPF_F_USER_STACK,
+    helpers::{bpf_get_current_pid_tgid, bpf_ktime_get_ns},
+    macros::{map, tracepoint},
+    maps::{HashMap, StackTrace},
+    programs::TracePointContext,
+};
+use stalk_common::{OffCpuKey, OffCpuStart, SchedSwitchInfo, SchedWakeupInfo};
+
+/// Another CPU may have created the entry in the meantime; keep its total.
+const BPF_NOEXIST: u64 = 1;
+/// Bits of `prev_state` holding the task state; above them is the preemption flag.
+const TASK_REPORT: i64 = 0xFF;
+/// `EXIT_DEAD | EXIT_ZOMBIE`: the thread is switching out for the last time.
+const EXIT_STATES: i64 = 0x10 | 0x20;
+
+#[map]
+static mut OFFCPU_STACKS: StackTrace = StackTrace::with_max_entries(16384, 0);
+
+#[map]
+static mut OFFCPU_START: HashMap<u32, OffCpuStart> = HashMap::with_max_entries(40960, 0);
+
+/// (tgid, stacks, reason) -> nanoseconds since userspace last drained the map.
+#[map]
+static mut OFFCPU_TIME: HashMap<OffCpuKey, u64> = HashMap::with_max_entries(40960, 0);
+
+/// Runs on the CPU being switched, in the context of the thread going off-CPU, whose stacks
+/// show why it blocked.
+#[tracepoint]
+pub fn stalk_sched_switch(ctx: TracePointContext) -> u32 {
+    match try_stalk_sched_switch(ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+fn try_stalk_sched_switch(ctx: TracePointContext) -> Result<u32, i64> {
+    let info = unsafe { *(ctx.as_ptr() as *const SchedSwitchInfo) };
+    let now = unsafe { bpf_ktime_get_ns() };
+    unsafe {
+        let starts = &raw mut OFFCPU_START;
+        // The idle task never blocks, and exiting threads never come back to remove their entry.
+        if info.prev_pid != 0 && info.prev_state & EXIT_STATES == 0 {
+            let stacks = &raw mut OFFCPU_STACKS;
+            let runnable = info.prev_state & TASK_REPORT == 0;
+            let start = OffCpuStart {
+                start: now,
+                wakeup: if runnable { now } else { 0 },
+                key: OffCpuKey {
+                    tgid: (bpf_get_current_pid_tgid() >> 32) as u32,
+                    user_stack: (*stacks)
+                        .get_stackid(&ctx, BPF_F_USER_STACK as u64)
+                        .unwrap_or(-1) as i32,
+                    kernel_stack: (*stacks).get_stackid(&ctx, 0).unwrap_or(-1) as i32,
+                    runqueue: 0,
+                    comm: info.prev_comm,
+                },
+            };
+            let _ = (*starts).insert(&(info.prev_pid as u32), &start, 0);
+        }
+        let next_pid = info.next_pid as u32;
+        let Some(start) = (*starts).get(&next_pid).copied() else {
+            return Ok(0);
+        };
+        let _ = (*starts).remove(&next_pid);
+        // A wakeup from before tracing started was missed, count it all as blocked.
+        let wakeup = if start.wakeup == 0 { now } else { start.wakeup };
+        add(start.key, wakeup - start.start)?;
+        add(
+            OffCpuKey {
+                runqueue: 1,
+                ..start.key
+            },
+            now - wakeup,
+        )?;
+    }
+    Ok(0)
+}
+
+#[tracepoint]
+pub fn stalk_sched_wakeup(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SchedWakeupInfo) };
+    unsafe {
+        let starts = &raw mut OFFCPU_START;
+        if let Some(start) = (*starts).get_ptr_mut(&(info.pid as u32))
+            && (*start).wakeup == 0
+        {
+            (*start).wakeup = bpf_ktime_get_ns();
+        }
+    }
+    0
+}
+
+unsafe fn add(key: OffCpuKey, ns: u64) -> Result<(), i64> {
+    if ns == 0 {
+        return Ok(());
+    }
+    unsafe {
+        let times = &raw mut OFFCPU_TIME;
+        if (*times).get_ptr_mut(&key).is_none() {
+            let _ = (*times).insert(&key, &0, BPF_NOEXIST);
+        }
+        let total = (*times).get_ptr_mut(&key).ok_or(1i64)?;
+        AtomicU64::from_ptr(total).fetch_add(ns, Ordering::Relaxed);
+    }
+    Ok(())
+}
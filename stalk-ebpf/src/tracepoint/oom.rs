@@ -0,0 +1,57 @@
+use aya_ebpf::{
+    EbpfContext,
+    helpers::{bpf_get_current_comm, bpf_get_current_pid_tgid, r#gen},
+    macros::{map, tracepoint},
+    maps::RingBuf,
+    programs::TracePointContext,
+};
+use stalk_common::{OOM_MAX_MESSAGE, OOM_MAX_RECORD, RawOomEvent};
+
+use crate::kprobe::oom::OOM_KILLS;
+
+/// Bytes of the record to copy, set by userspace from the format file.
+#[unsafe(no_mangle)]
+static OOM_RECORD_SIZE: u32 = 8;
+
+#[map]
+static mut OOM_EVENTS: RingBuf = RingBuf::with_byte_size(64 * 1024, 0);
+
+/// `oom:mark_victim` runs in the task that hit the OOM condition, so the current task is the
+/// trigger and the record describes the victim.
+#[tracepoint]
+pub fn stalk_oom_mark_victim(ctx: TracePointContext) -> u32 {
+    let tgid_pid = bpf_get_current_pid_tgid();
+    let mut len = unsafe { core::ptr::read_volatile(&raw const OOM_RECORD_SIZE) };
+    if len > OOM_MAX_RECORD as u32 {
+        len = OOM_MAX_RECORD as u32;
+    }
+    unsafe {
+        let kills = &raw mut OOM_KILLS;
+        let kill = (*kills).get(&tgid_pid).copied();
+        let _ = (*kills).remove(&tgid_pid);
+
+        let event_map = &raw mut OOM_EVENTS;
+        let Some(mut buf) = (*event_map).reserve::<RawOomEvent>(0) else {
+            return 0;
+        };
+        let event = buf.as_mut_ptr();
+        (*event).trigger_pid = (tgid_pid & 0xFFFFFFFF) as u32;
+        (*event).trigger_tgid = (tgid_pid >> 32) as u32;
+        (*event).trigger_comm = bpf_get_current_comm().unwrap_or([0; 16]);
+        (*event).message = kill.map_or([0; OOM_MAX_MESSAGE], |kill| kill.message);
+        (*event).memcg_id = kill.map_or(0, |kill| kill.memcg_id);
+        (*event).len = len;
+        if len == 0
+            || r#gen::bpf_probe_read_kernel(
+                (*event).data.as_mut_ptr() as *mut _,
+                len,
+                ctx.as_ptr() as *const _,
+            ) != 0
+        {
+            buf.discard(0);
+            return 0;
+        }
+        buf.submit(0);
+    }
+    0
+}
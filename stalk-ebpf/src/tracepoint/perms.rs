@@ -0,0 +1,180 @@
+use aya_ebpf::{
+    EbpfContext,
+    helpers::bpf_probe_read_user_str_bytes,
+    macros::{map, tracepoint},
+    maps::RingBuf,
+    programs::TracePointContext,
+};
+use stalk_common::{
+    PermOpKind, RawPermEvent, SysEnterChmodInfo, SysEnterChownInfo, SysEnterFchmodInfo,
+    SysEnterFchmodatInfo, SysEnterFchownInfo, SysEnterFchownatInfo, SysEnterFremovexattrInfo,
+    SysEnterFsetxattrInfo, SysEnterRemovexattrInfo, SysEnterSetxattrInfo,
+};
+
+use crate::{kprobe::perms::PERMS_PENDING, syscall};
+
+const AT_FDCWD: i32 = -100;
+
+#[map]
+static mut PERMS_EVENTS: RingBuf = RingBuf::with_byte_size(256 * 1024, 0);
+
+#[tracepoint]
+pub fn stalk_fchmodat(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterFchmodatInfo) };
+    let Ok(mut event) = new_event(PermOpKind::Chmod, info.dfd as i32) else {
+        return 0;
+    };
+    event.mode = info.mode as u32;
+    unsafe {
+        let _ = bpf_probe_read_user_str_bytes(info.filename as *const u8, &mut event.path);
+    }
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_chmod(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterChmodInfo) };
+    let Ok(mut event) = new_event(PermOpKind::Chmod, AT_FDCWD) else {
+        return 0;
+    };
+    event.mode = info.mode as u32;
+    unsafe {
+        let _ = bpf_probe_read_user_str_bytes(info.filename as *const u8, &mut event.path);
+    }
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_fchmod(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterFchmodInfo) };
+    // The fd is resolved to a path in userspace.
+    let Ok(mut event) = new_event(PermOpKind::Chmod, info.fd as i32) else {
+        return 0;
+    };
+    event.mode = info.mode as u32;
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_fchownat(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterFchownatInfo) };
+    let Ok(mut event) = new_event(PermOpKind::Chown, info.dfd as i32) else {
+        return 0;
+    };
+    event.owner = info.user as u32;
+    event.group = info.group as u32;
+    unsafe {
+        let _ = bpf_probe_read_user_str_bytes(info.filename as *const u8, &mut event.path);
+    }
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+/// Attached to both `chown` and `lchown`.
+#[tracepoint]
+pub fn stalk_chown(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterChownInfo) };
+    let Ok(mut event) = new_event(PermOpKind::Chown, AT_FDCWD) else {
+        return 0;
+    };
+    event.owner = info.user as u32;
+    event.group = info.group as u32;
+    unsafe {
+        let _ = bpf_probe_read_user_str_bytes(info.filename as *const u8, &mut event.path);
+    }
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_fchown(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterFchownInfo) };
+    // The fd is resolved to a path in userspace.
+    let Ok(mut event) = new_event(PermOpKind::Chown, info.fd as i32) else {
+        return 0;
+    };
+    event.owner = info.user as u32;
+    event.group = info.group as u32;
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+/// Attached to both `setxattr` and `lsetxattr`.
+#[tracepoint]
+pub fn stalk_setxattr(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterSetxattrInfo) };
+    let Ok(mut event) = new_event(PermOpKind::SetXattr, AT_FDCWD) else {
+        return 0;
+    };
+    unsafe {
+        let _ = bpf_probe_read_user_str_bytes(info.pathname as *const u8, &mut event.path);
+        let _ = bpf_probe_read_user_str_bytes(info.name as *const u8, &mut event.name);
+    }
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_fsetxattr(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterFsetxattrInfo) };
+    // The fd is resolved to a path in userspace.
+    let Ok(mut event) = new_event(PermOpKind::SetXattr, info.fd as i32) else {
+        return 0;
+    };
+    unsafe {
+        let _ = bpf_probe_read_user_str_bytes(info.name as *const u8, &mut event.name);
+    }
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_removexattr(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterRemovexattrInfo) };
+    let Ok(mut event) = new_event(PermOpKind::RemoveXattr, AT_FDCWD) else {
+        return 0;
+    };
+    unsafe {
+        let _ = bpf_probe_read_user_str_bytes(info.pathname as *const u8, &mut event.path);
+        let _ = bpf_probe_read_user_str_bytes(info.name as *const u8, &mut event.name);
+    }
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_fremovexattr(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterFremovexattrInfo) };
+    // The fd is resolved to a path in userspace.
+    let Ok(mut event) = new_event(PermOpKind::RemoveXattr, info.fd as i32) else {
+        return 0;
+    };
+    unsafe {
+        let _ = bpf_probe_read_user_str_bytes(info.name as *const u8, &mut event.name);
+    }
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+/// Attached to the `sys_exit_*` tracepoint of every syscall above.
+#[tracepoint]
+pub fn stalk_perms_exit(ctx: TracePointContext) -> u32 {
+    unsafe { syscall::exit(&ctx, &raw mut PERMS_PENDING, &raw mut PERMS_EVENTS) }
+        .unwrap_or_else(|ret| ret)
+}
+
+fn new_event(kind: PermOpKind, dfd: i32) -> Result<RawPermEvent, u32> {
+    let caller = syscall::caller()?;
+    Ok(RawPermEvent {
+        kind,
+        pid: caller.pid,
+        tgid: caller.tgid,
+        uid: caller.uid,
+        comm: caller.comm,
+        dfd,
+        mode: 0,
+        old_mode: u32::MAX,
+        owner: u32::MAX,
+        group: u32::MAX,
+        path: [0; 64],
+        name: [0; 32],
+        ret: 0,
+    })
+}
+
+fn insert(event: &RawPermEvent) -> Result<u32, u32> {
+    unsafe { syscall::enter(&raw mut PERMS_PENDING, event) }
+}
@@ -0,0 +1,129 @@
+use aya_ebpf::{
+    EbpfContext,
+    helpers::{bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_probe_read_kernel_str_bytes},
+    macros::{map, tracepoint},
+    maps::{LruHashMap, RingBuf},
+    programs::TracePointContext,
+};
+use stalk_common::{
+    ProcessEventKind, RawProcessEvent, SchedProcessExecInfo, SchedProcessExitInfo,
+    SchedProcessForkInfo, TaskNewtaskInfo,
+};
+
+const CLONE_THREAD: u64 = 0x10000;
+
+#[map]
+static mut PROCESS_EVENTS: RingBuf = RingBuf::with_byte_size(256 * 1024, 0);
+
+/// Pids of threads created by `clone(CLONE_THREAD)` whose `sched_process_fork` hasn't fired
+/// yet; the two tracepoints run back to back in the parent.
+#[map]
+static mut NEW_THREADS: LruHashMap<u32, u8> = LruHashMap::with_max_entries(1024, 0);
+
+#[tracepoint]
+pub fn stalk_task_newtask(ctx: TracePointContext) -> u32 {
+    unsafe {
+        let info = ctx.as_ptr() as *const TaskNewtaskInfo;
+        if (*info).clone_flags & CLONE_THREAD != 0 {
+            let threads = &raw mut NEW_THREADS;
+            let _ = (*threads).insert(&((*info).pid as u32), &1, 0);
+        }
+    }
+    0
+}
+
+#[tracepoint]
+pub fn stalk_process_fork(ctx: TracePointContext) -> u32 {
+    try_stalk_process_fork(ctx).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_process_exec(ctx: TracePointContext) -> u32 {
+    try_stalk_process_exec(ctx).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_process_exit(ctx: TracePointContext) -> u32 {
+    try_stalk_process_exit(ctx).unwrap_or_else(|ret| ret)
+}
+
+fn try_stalk_process_fork(ctx: TracePointContext) -> Result<u32, u32> {
+    let tgid_pid = bpf_get_current_pid_tgid();
+    let ppid = (tgid_pid >> 32) as u32;
+    unsafe {
+        let fork_info: *const SchedProcessForkInfo = ctx.as_ptr() as *const SchedProcessForkInfo;
+        let child = (*fork_info).child_pid as u32;
+        let threads = &raw mut NEW_THREADS;
+        if (*threads).get(&child).is_some() {
+            let _ = (*threads).remove(&child);
+            return Ok(0);
+        }
+        let event = RawProcessEvent {
+            kind: ProcessEventKind::Fork,
+            pid: child,
+            tgid: child,
+            ppid,
+            comm: (*fork_info).child_comm,
+            filename: [0; 64],
+        };
+        submit(event);
+    }
+    Ok(0)
+}
+
+fn try_stalk_process_exec(ctx: TracePointContext) -> Result<u32, u32> {
+    let tgid_pid = bpf_get_current_pid_tgid();
+    let pid = (tgid_pid & 0xFFFFFFFF) as u32;
+    let tgid = (tgid_pid >> 32) as u32;
+    let comm = bpf_get_current_comm().map_err(|e| e as u32)?;
+    let mut filename = [0u8; 64];
+    unsafe {
+        let exec_info: *const SchedProcessExecInfo = ctx.as_ptr() as *const SchedProcessExecInfo;
+        let offset = ((*exec_info).filename_loc & 0xFFFF) as usize;
+        let _ =
+            bpf_probe_read_kernel_str_bytes((ctx.as_ptr() as *const u8).add(offset), &mut filename);
+        let event = RawProcessEvent {
+            kind: ProcessEventKind::Exec,
+            pid,
+            tgid,
+            ppid: 0,
+            comm,
+            filename,
+        };
+        submit(event);
+    }
+    Ok(0)
+}
+
+fn try_stalk_process_exit(ctx: TracePointContext) -> Result<u32, u32> {
+    let tgid_pid = bpf_get_current_pid_tgid();
+    let pid = (tgid_pid & 0xFFFFFFFF) as u32;
+    let tgid = (tgid_pid >> 32) as u32;
+    // Only the group leader's exit ends the process.
+    if pid != tgid {
+        return Ok(0);
+    }
+    unsafe {
+        let exit_info: *const SchedProcessExitInfo = ctx.as_ptr() as *const SchedProcessExitInfo;
+        let event = RawProcessEvent {
+            kind: ProcessEventKind::Exit,
+            pid,
+            tgid,
+            ppid: 0,
+            comm: (*exit_info).comm,
+            filename: [0; 64],
+        };
+        submit(event);
+    }
+    Ok(0)
+}
+
+unsafe fn submit(event: RawProcessEvent) {
+    unsafe {
+        let event_map = &raw mut PROCESS_EVENTS;
+        if let Some(mut buf) = (*event_map).reserve::<RawProcessEvent>(0) {
+            buf.write(event);
+            buf.submit(0);
+        }
+    }
+}
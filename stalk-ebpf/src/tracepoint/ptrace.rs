@@ -0,0 +1,116 @@
+use aya_ebpf::{
+    EbpfContext,
+    helpers::bpf_probe_read_user,
+    macros::{map, tracepoint},
+    maps::{LruHashMap, RingBuf},
+    programs::TracePointContext,
+};
+use stalk_common::{PtraceEventKind, RawPtraceEvent, SysEnterProcessVmInfo, SysEnterPtraceInfo};
+
+use crate::syscall;
+
+/// Remote iovecs summed into `requested`.
+const MAX_IOVECS: usize = 8;
+
+/// Requests that start tracing or write to the tracee, from `include/uapi/linux/ptrace.h` and
+/// x86's `asm/ptrace-abi.h`. Reads, continues and the like are only noise between these.
+const PTRACE_TRACEME: i64 = 0;
+const PTRACE_POKETEXT: i64 = 4;
+const PTRACE_POKEDATA: i64 = 5;
+const PTRACE_POKEUSR: i64 = 6;
+const PTRACE_SETREGS: i64 = 13;
+const PTRACE_SETFPREGS: i64 = 15;
+const PTRACE_ATTACH: i64 = 16;
+const PTRACE_SETFPXREGS: i64 = 19;
+const PTRACE_SETREGSET: i64 = 0x4205;
+const PTRACE_SEIZE: i64 = 0x4206;
+
+#[map]
+static mut PTRACE_EVENTS: RingBuf = RingBuf::with_byte_size(256 * 1024, 0);
+
+/// Thread -> call waiting for its syscall to return.
+#[map]
+static mut PTRACE_PENDING: LruHashMap<u64, RawPtraceEvent> = LruHashMap::with_max_entries(10240, 0);
+
+#[tracepoint]
+pub fn stalk_ptrace(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterPtraceInfo) };
+    if !matches!(
+        info.request,
+        PTRACE_TRACEME
+            | PTRACE_POKETEXT
+            | PTRACE_POKEDATA
+            | PTRACE_POKEUSR
+            | PTRACE_SETREGS
+            | PTRACE_SETFPREGS
+            | PTRACE_ATTACH
+            | PTRACE_SETFPXREGS
+            | PTRACE_SETREGSET
+            | PTRACE_SEIZE
+    ) {
+        return 0;
+    }
+    // The kernel ignores pid for PTRACE_TRACEME.
+    let target = if info.request == PTRACE_TRACEME {
+        0
+    } else {
+        info.pid as u32
+    };
+    let Ok(mut event) = new_event(PtraceEventKind::Ptrace, target) else {
+        return 0;
+    };
+    event.request = info.request as u64;
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_process_vm_readv(ctx: TracePointContext) -> u32 {
+    process_vm(ctx, PtraceEventKind::VmReadv)
+}
+
+#[tracepoint]
+pub fn stalk_process_vm_writev(ctx: TracePointContext) -> u32 {
+    process_vm(ctx, PtraceEventKind::VmWritev)
+}
+
+/// Attached to the `sys_exit_*` tracepoint of every syscall above.
+#[tracepoint]
+pub fn stalk_ptrace_exit(ctx: TracePointContext) -> u32 {
+    unsafe { syscall::exit(&ctx, &raw mut PTRACE_PENDING, &raw mut PTRACE_EVENTS) }
+        .unwrap_or_else(|ret| ret)
+}
+
+fn process_vm(ctx: TracePointContext, kind: PtraceEventKind) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterProcessVmInfo) };
+    let Ok(mut event) = new_event(kind, info.pid as u32) else {
+        return 0;
+    };
+    for i in 0..MAX_IOVECS {
+        if i as u64 >= info.riovcnt {
+            break;
+        }
+        // struct iovec { void *iov_base; size_t iov_len; }
+        let len = unsafe { bpf_probe_read_user(info.rvec.add(i * 16 + 8) as *const u64) };
+        event.requested += len.unwrap_or(0);
+    }
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+fn new_event(kind: PtraceEventKind, target: u32) -> Result<RawPtraceEvent, u32> {
+    let caller = syscall::caller()?;
+    Ok(RawPtraceEvent {
+        kind,
+        pid: caller.pid,
+        tgid: caller.tgid,
+        uid: caller.uid,
+        comm: caller.comm,
+        target,
+        request: 0,
+        requested: 0,
+        ret: 0,
+    })
+}
+
+fn insert(event: &RawPtraceEvent) -> Result<u32, u32> {
+    unsafe { syscall::enter(&raw mut PTRACE_PENDING, event) }
+}
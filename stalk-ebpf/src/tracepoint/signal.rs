@@ -0,0 +1,100 @@
+use aya_ebpf::{
+    EbpfContext,
+    helpers::{bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_probe_read_kernel},
+    macros::{map, raw_tracepoint, tracepoint},
+    maps::RingBuf,
+    programs::{RawTracePointContext, TracePointContext},
+};
+use stalk_common::{RawSignalEvent, SignalDeliverInfo, SignalEventKind};
+
+use crate::task;
+
+/// `SEND_SIG_NOINFO` and `SEND_SIG_PRIV`, passed instead of a siginfo pointer.
+const SEND_SIG_NOINFO: u64 = 0;
+const SEND_SIG_PRIV: u64 = 1;
+const SI_USER: i32 = 0;
+const SI_KERNEL: i32 = 0x80;
+
+#[map]
+static mut SIGNAL_EVENTS: RingBuf = RingBuf::with_byte_size(256 * 1024, 0);
+
+/// Attached raw to get the target task, whose thread group the tracepoint record lacks. The
+/// arguments are `(int sig, struct kernel_siginfo *info, struct task_struct *task, int group,
+/// int result)`.
+#[raw_tracepoint(tracepoint = "signal_generate")]
+pub fn stalk_signal_generate(ctx: RawTracePointContext) -> i32 {
+    match try_stalk_signal_generate(ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+#[tracepoint]
+pub fn stalk_signal_deliver(ctx: TracePointContext) -> u32 {
+    try_stalk_signal_deliver(ctx).unwrap_or_else(|ret| ret)
+}
+
+fn try_stalk_signal_generate(ctx: RawTracePointContext) -> Result<i32, i64> {
+    let tgid = (bpf_get_current_pid_tgid() >> 32) as u32;
+    let comm = bpf_get_current_comm()?;
+    unsafe {
+        let args = ctx.as_ptr() as *const u64;
+        let info = *args.add(1);
+        let target = *args.add(2) as *const u8;
+        // TP_STORE_SIGINFO in include/trace/events/signal.h
+        let (errno, code) = match info {
+            SEND_SIG_NOINFO => (0, SI_USER),
+            SEND_SIG_PRIV => (0, SI_KERNEL),
+            _ => {
+                let [_, errno, code] = bpf_probe_read_kernel(info as *const [i32; 3])?;
+                (errno, code)
+            }
+        };
+        let event = RawSignalEvent {
+            kind: SignalEventKind::Generate,
+            tgid,
+            comm,
+            target_pid: task::pid(target)?,
+            target_tgid: task::tgid(target)?,
+            target_comm: task::process_comm(target)?,
+            sig: *args as i32,
+            errno,
+            code,
+            result: *args.add(4) as i32,
+        };
+        submit(event);
+    }
+    Ok(0)
+}
+
+fn try_stalk_signal_deliver(ctx: TracePointContext) -> Result<u32, u32> {
+    let tgid = (bpf_get_current_pid_tgid() >> 32) as u32;
+    let comm = bpf_get_current_comm().map_err(|e| e as u32)?;
+    unsafe {
+        let signal_info: *const SignalDeliverInfo = ctx.as_ptr() as *const SignalDeliverInfo;
+        let event = RawSignalEvent {
+            kind: SignalEventKind::Deliver,
+            tgid,
+            comm,
+            target_pid: 0,
+            target_tgid: 0,
+            target_comm: [0; 16],
+            sig: (*signal_info).sig,
+            errno: (*signal_info).errno,
+            code: (*signal_info).code,
+            result: 0,
+        };
+        submit(event);
+    }
+    Ok(0)
+}
+
+unsafe fn submit(event: RawSignalEvent) {
+    unsafe {
+        let event_map = &raw mut SIGNAL_EVENTS;
+        if let Some(mut buf) = (*event_map).reserve::<RawSignalEvent>(0) {
+            buf.write(event);
+            buf.submit(0);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use aya_ebpf::{
+    EbpfContext,
+    helpers::{bpf_probe_read_kernel, bpf_probe_read_user},
+    macros::{map, tracepoint},
+    maps::{LruHashMap, RingBuf},
+    programs::TracePointContext,
+};
+use stalk_common::{
+    InetSockSetStateInfo, RawSocketEvent, SocketEventKind, SysEnterBindInfo, SysEnterListenInfo,
+};
+
+use crate::syscall;
+
+const AF_INET: u16 = 2;
+const AF_INET6: u16 = 10;
+const TCP_LISTEN: i32 = 10;
+const IPPROTO_TCP: u16 = 6;
+
+/// Offsets leading from a `struct sock` to the inode number of its socket, set by userspace
+/// from BTF. `SOCKET_INODE_OFFSET` is where `vfs_inode` follows `socket` in `socket_alloc`.
+#[unsafe(no_mangle)]
+static SOCK_SOCKET_OFFSET: u32 = 640;
+#[unsafe(no_mangle)]
+static SOCKET_INODE_OFFSET: u32 = 128;
+#[unsafe(no_mangle)]
+static INODE_INO_OFFSET: u32 = 64;
+
+#[map]
+static mut SOCKET_EVENTS: RingBuf = RingBuf::with_byte_size(256 * 1024, 0);
+
+/// Thread -> call waiting for its syscall to return.
+#[map]
+static mut SOCKET_PENDING: LruHashMap<u64, RawSocketEvent> = LruHashMap::with_max_entries(10240, 0);
+
+#[tracepoint]
+pub fn stalk_bind(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterBindInfo) };
+    let Ok(mut event) = new_event(SocketEventKind::Bind) else {
+        return 0;
+    };
+    event.fd = info.fd as i32;
+    unsafe {
+        // sockaddr_in and sockaddr_in6 share family and port; the address follows at 4 or 8.
+        event.family = bpf_probe_read_user(info.umyaddr as *const u16).unwrap_or(0);
+        let port = bpf_probe_read_user(info.umyaddr.add(2) as *const u16).unwrap_or(0);
+        event.port = u16::from_be(port);
+        match event.family {
+            AF_INET => {
+                let addr = bpf_probe_read_user(info.umyaddr.add(4) as *const [u8; 4]);
+                event.addr[..4].copy_from_slice(&addr.unwrap_or([0; 4]));
+            }
+            AF_INET6 => {
+                let addr = bpf_probe_read_user(info.umyaddr.add(8) as *const [u8; 16]);
+                event.addr = addr.unwrap_or([0; 16]);
+            }
+            // Unix, netlink and packet sockets.
+            _ => return 0,
+        }
+    }
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+#[tracepoint]
+pub fn stalk_listen(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const SysEnterListenInfo) };
+    let Ok(mut event) = new_event(SocketEventKind::Listen) else {
+        return 0;
+    };
+    event.fd = info.fd as i32;
+    event.backlog = info.backlog as i32;
+    insert(&event).unwrap_or_else(|ret| ret)
+}
+
+/// Attached to `sys_exit_bind` and `sys_exit_listen`.
+#[tracepoint]
+pub fn stalk_socket_exit(ctx: TracePointContext) -> u32 {
+    unsafe { syscall::exit(&ctx, &raw mut SOCKET_PENDING, &raw mut SOCKET_EVENTS) }
+        .unwrap_or_else(|ret| ret)
+}
+
+/// Only TCP transitions into or out of `TCP_LISTEN` are reported.
+#[tracepoint]
+pub fn stalk_inet_sock_set_state(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const InetSockSetStateInfo) };
+    if info.protocol != IPPROTO_TCP || (info.newstate != TCP_LISTEN && info.oldstate != TCP_LISTEN)
+    {
+        return 0;
+    }
+    let Ok(mut event) = new_event(SocketEventKind::StateChange) else {
+        return 0;
+    };
+    event.oldstate = info.oldstate;
+    event.newstate = info.newstate;
+    event.family = info.family;
+    event.protocol = info.protocol;
+    event.port = info.sport;
+    if info.family == AF_INET6 {
+        event.addr = info.saddr_v6;
+    } else {
+        event.addr[..4].copy_from_slice(&info.saddr);
+    }
+    event.inode = unsafe { socket_inode(info.skaddr as *const u8) }.unwrap_or(0);
+    unsafe { syscall::output(&raw mut SOCKET_EVENTS, event) };
+    0
+}
+
+/// Inode number of the socket `sk` belongs to, as in `/proc/net` and `/proc/<pid>/fd`.
+unsafe fn socket_inode(sk: *const u8) -> Result<u64, i64> {
+    unsafe {
+        let socket_offset = core::ptr::read_volatile(&raw const SOCK_SOCKET_OFFSET) as usize;
+        let inode_offset = core::ptr::read_volatile(&raw const SOCKET_INODE_OFFSET) as usize;
+        let ino_offset = core::ptr::read_volatile(&raw const INODE_INO_OFFSET) as usize;
+        let socket: *const u8 = bpf_probe_read_kernel(sk.add(socket_offset) as *const *const u8)?;
+        if socket.is_null() {
+            return Err(0);
+        }
+        bpf_probe_read_kernel(socket.add(inode_offset + ino_offset) as *const u64)
+    }
+}
+
+fn new_event(kind: SocketEventKind) -> Result<RawSocketEvent, u32> {
+    let caller = syscall::caller()?;
+    Ok(RawSocketEvent {
+        kind,
+        pid: caller.pid,
+        tgid: caller.tgid,
+        uid: caller.uid,
+        comm: caller.comm,
+        fd: -1,
+        backlog: 0,
+        oldstate: 0,
+        newstate: 0,
+        family: 0,
+        protocol: 0,
+        port: 0,
+        padding: 0,
+        addr: [0; 16],
+        inode: 0,
+        ret: 0,
+    })
+}
+
+fn insert(event: &RawSocketEvent) -> Result<u32, u32> {
+    unsafe { syscall::enter(&raw mut SOCKET_PENDING, event) }
+}
@@ -0,0 +1,129 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use aya_ebpf::{
+    EbpfContext,
+    helpers::{bpf_get_current_pid_tgid, bpf_ktime_get_ns},
+    macros::{map, tracepoint},
+    maps::{Array, LruHashMap},
+    programs::TracePointContext,
+};
+use stalk_common::{RawSysEnterInfo, RawSysExitInfo, SyscallErrorKey, SyscallKey, SyscallStats};
+
+/// Another CPU may have created the entry in the meantime; keep its counts.
+const BPF_NOEXIST: u64 = 1;
+
+/// Numbers of `exit` and `exit_group`, which never return, set by userspace for its arch.
+#[unsafe(no_mangle)]
+static SYS_EXIT_NR: u32 = 60;
+#[unsafe(no_mangle)]
+static SYS_EXIT_GROUP_NR: u32 = 231;
+
+/// Thread -> (syscall number, entry time) of the syscall it is in. Threads killed in a syscall
+/// leave their entry behind until it is evicted.
+#[map]
+static mut SYSCALL_START: LruHashMap<u64, (u64, u64)> = LruHashMap::with_max_entries(10240, 0);
+
+/// Which of the two pairs of maps below exits count into. Userspace flips it, then drains the
+/// other pair.
+#[map]
+static mut SYSCALL_ACTIVE: Array<u32> = Array::with_max_entries(1, 0);
+
+/// (tgid, syscall) -> totals since userspace last drained the map.
+#[map]
+static mut SYSCALL_STATS_0: LruHashMap<SyscallKey, SyscallStats> =
+    LruHashMap::with_max_entries(65536, 0);
+#[map]
+static mut SYSCALL_STATS_1: LruHashMap<SyscallKey, SyscallStats> =
+    LruHashMap::with_max_entries(65536, 0);
+
+/// (tgid, syscall, errno) -> failed calls since userspace last drained the map.
+#[map]
+static mut SYSCALL_ERRORS_0: LruHashMap<SyscallErrorKey, u64> =
+    LruHashMap::with_max_entries(65536, 0);
+#[map]
+static mut SYSCALL_ERRORS_1: LruHashMap<SyscallErrorKey, u64> =
+    LruHashMap::with_max_entries(65536, 0);
+
+#[tracepoint]
+pub fn stalk_sys_enter(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const RawSysEnterInfo) };
+    unsafe {
+        let id = info.id as u64;
+        if id == core::ptr::read_volatile(&raw const SYS_EXIT_NR) as u64
+            || id == core::ptr::read_volatile(&raw const SYS_EXIT_GROUP_NR) as u64
+        {
+            return 0;
+        }
+    }
+    let tgid_pid = bpf_get_current_pid_tgid();
+    let start = (info.id as u64, unsafe { bpf_ktime_get_ns() });
+    unsafe {
+        let starts = &raw mut SYSCALL_START;
+        let _ = (*starts).insert(&tgid_pid, &start, 0);
+    }
+    0
+}
+
+#[tracepoint]
+pub fn stalk_sys_exit(ctx: TracePointContext) -> u32 {
+    match try_stalk_sys_exit(ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+fn try_stalk_sys_exit(ctx: TracePointContext) -> Result<u32, i64> {
+    let info = unsafe { *(ctx.as_ptr() as *const RawSysExitInfo) };
+    let tgid_pid = bpf_get_current_pid_tgid();
+    let (nr, ts) = unsafe {
+        let starts = &raw mut SYSCALL_START;
+        let Some(start) = (*starts).get(&tgid_pid).copied() else {
+            return Ok(0);
+        };
+        let _ = (*starts).remove(&tgid_pid);
+        start
+    };
+    // A syscall restarted after a signal enters again under the same thread.
+    if nr != info.id as u64 {
+        return Ok(0);
+    }
+    let key = SyscallKey {
+        tgid: (tgid_pid >> 32) as u32,
+        nr: nr as u32,
+    };
+    let failed = (-4095..0).contains(&info.ret);
+    unsafe {
+        let active = &raw mut SYSCALL_ACTIVE;
+        let (stats_map, errors_map) = if (*active).get(0).copied().unwrap_or(0) == 0 {
+            (&raw mut SYSCALL_STATS_0, &raw mut SYSCALL_ERRORS_0)
+        } else {
+            (&raw mut SYSCALL_STATS_1, &raw mut SYSCALL_ERRORS_1)
+        };
+        if (*stats_map).get_ptr_mut(&key).is_none() {
+            let _ = (*stats_map).insert(&key, &SyscallStats::default(), BPF_NOEXIST);
+        }
+        let stats = (*stats_map).get_ptr_mut(&key).ok_or(1i64)?;
+        add(&raw mut (*stats).count, 1);
+        add(&raw mut (*stats).total_ns, bpf_ktime_get_ns() - ts);
+        if failed {
+            add(&raw mut (*stats).errors, 1);
+            let key = SyscallErrorKey {
+                tgid: key.tgid,
+                nr: key.nr,
+                errno: -info.ret as u32,
+                padding: 0,
+            };
+            if (*errors_map).get_ptr_mut(&key).is_none() {
+                let _ = (*errors_map).insert(&key, &0, BPF_NOEXIST);
+            }
+            let count = (*errors_map).get_ptr_mut(&key).ok_or(1i64)?;
+            add(count, 1);
+        }
+    }
+    Ok(0)
+}
+
+/// Adds atomically, as threads of a process update the same entry from several CPUs.
+unsafe fn add(counter: *mut u64, value: u64) {
+    unsafe { AtomicU64::from_ptr(counter) }.fetch_add(value, Ordering::Relaxed);
+}
@@ -0,0 +1,147 @@
+use aya_ebpf::{
+    EbpfContext,
+    helpers::bpf_ktime_get_ns,
+    macros::{map, tracepoint},
+    maps::{LruHashMap, RingBuf},
+    programs::TracePointContext,
+};
+use stalk_common::{RawTcpEvent, TcpEventKind, TcpEventSkInfo, TcpEventSkSkbInfo, TcpProbeInfo};
+
+use crate::kprobe::tcp::SOCK_OWNERS;
+
+const AF_INET6: u16 = 10;
+/// RTT samples are reported at most this often per connection.
+const RTT_INTERVAL_NS: u64 = 1_000_000_000;
+
+/// Offsets of `srtt` and `skaddr` (0 when absent) in `tcp_probe`, set by userspace from the
+/// tracepoint's format file.
+#[unsafe(no_mangle)]
+static TCP_PROBE_SRTT_OFFSET: u32 = 100;
+#[unsafe(no_mangle)]
+static TCP_PROBE_SKADDR_OFFSET: u32 = 0;
+
+#[map]
+static mut TCP_EVENTS: RingBuf = RingBuf::with_byte_size(256 * 1024, 0);
+
+/// Local and remote `sockaddr` of a connection -> last RTT sample reported.
+#[map]
+static mut TCP_RTT_LAST: LruHashMap<[u8; 56], u64> = LruHashMap::with_max_entries(16384, 0);
+
+#[tracepoint]
+pub fn stalk_tcp_retransmit(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const TcpEventSkSkbInfo) };
+    let mut event = sk_skb_event(TcpEventKind::Retransmit, &info);
+    event.state = info.state;
+    submit(event);
+    0
+}
+
+#[tracepoint]
+pub fn stalk_tcp_send_reset(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const TcpEventSkSkbInfo) };
+    submit(sk_skb_event(TcpEventKind::SendReset, &info));
+    0
+}
+
+#[tracepoint]
+pub fn stalk_tcp_receive_reset(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const TcpEventSkInfo) };
+    let mut event = new_event(TcpEventKind::ReceiveReset, info.skaddr, info.family);
+    event.sport = info.sport;
+    event.dport = info.dport;
+    if info.family == AF_INET6 {
+        event.saddr = info.saddr_v6;
+        event.daddr = info.daddr_v6;
+    } else {
+        event.saddr[..4].copy_from_slice(&info.saddr);
+        event.daddr[..4].copy_from_slice(&info.daddr);
+    }
+    submit(event);
+    0
+}
+
+/// Fires on every incoming ACK, so samples are throttled per connection.
+#[tracepoint]
+pub fn stalk_tcp_probe(ctx: TracePointContext) -> u32 {
+    let info = unsafe { *(ctx.as_ptr() as *const TcpProbeInfo) };
+    let mut key = [0u8; 56];
+    key[..28].copy_from_slice(&info.saddr);
+    key[28..].copy_from_slice(&info.daddr);
+    let now = unsafe { bpf_ktime_get_ns() };
+    unsafe {
+        let last = &raw mut TCP_RTT_LAST;
+        if let Some(ts) = (*last).get(&key)
+            && now - *ts < RTT_INTERVAL_NS
+        {
+            return 0;
+        }
+        let _ = (*last).insert(&key, &now, 0);
+    }
+    let srtt_offset = unsafe { core::ptr::read_volatile(&raw const TCP_PROBE_SRTT_OFFSET) };
+    let skaddr_offset = unsafe { core::ptr::read_volatile(&raw const TCP_PROBE_SKADDR_OFFSET) };
+    let skaddr = if skaddr_offset == 0 {
+        0
+    } else {
+        unsafe { ctx.read_at::<u64>(skaddr_offset as usize) }.unwrap_or(0)
+    };
+    // Both sockaddrs start with the family, then the port in network order.
+    let family = u16::from_ne_bytes([info.saddr[0], info.saddr[1]]);
+    let mut event = new_event(TcpEventKind::Rtt, skaddr, family);
+    event.sport = info.sport;
+    event.dport = info.dport;
+    if family == AF_INET6 {
+        // sin6_addr follows sin6_flowinfo.
+        event.saddr.copy_from_slice(&info.saddr[8..24]);
+        event.daddr.copy_from_slice(&info.daddr[8..24]);
+    } else {
+        event.saddr[..4].copy_from_slice(&info.saddr[4..8]);
+        event.daddr[..4].copy_from_slice(&info.daddr[4..8]);
+    }
+    event.srtt_us = unsafe { ctx.read_at::<u32>(srtt_offset as usize) }.unwrap_or(0);
+    submit(event);
+    0
+}
+
+fn sk_skb_event(kind: TcpEventKind, info: &TcpEventSkSkbInfo) -> RawTcpEvent {
+    let mut event = new_event(kind, info.skaddr, info.family);
+    event.sport = info.sport;
+    event.dport = info.dport;
+    if info.family == AF_INET6 {
+        event.saddr = info.saddr_v6;
+        event.daddr = info.daddr_v6;
+    } else {
+        event.saddr[..4].copy_from_slice(&info.saddr);
+        event.daddr[..4].copy_from_slice(&info.daddr);
+    }
+    event
+}
+
+fn new_event(kind: TcpEventKind, skaddr: u64, family: u16) -> RawTcpEvent {
+    let owner = unsafe {
+        let owners = &raw mut SOCK_OWNERS;
+        (*owners).get(&skaddr).copied()
+    };
+    RawTcpEvent {
+        kind,
+        tgid: owner.map_or(0, |owner| owner.tgid),
+        comm: owner.map_or([0; 16], |owner| owner.comm),
+        family,
+        sport: 0,
+        dport: 0,
+        padding: 0,
+        saddr: [0; 16],
+        daddr: [0; 16],
+        srtt_us: 0,
+        state: 0,
+    }
+}
+
+fn submit(event: RawTcpEvent) {
+    unsafe {
+        let event_map = &raw mut TCP_EVENTS;
+        if let Some(mut buf) = (*event_map).reserve::<RawTcpEvent>(0) {
+            buf.write(event);
+            buf.submit(0);
+        }
+    }
+}
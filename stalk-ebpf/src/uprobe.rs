@@ -0,0 +1,3 @@
+mod probe;
+mod shell;
+mod ssl;
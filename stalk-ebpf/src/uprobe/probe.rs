@@ -0,0 +1,156 @@
+use aya_ebpf::{
+    helpers::{
+        bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_ktime_get_ns, bpf_probe_read_kernel,
+        bpf_probe_read_user,
+    },
+    macros::{map, uprobe, uretprobe},
+    maps::{LruHashMap, RingBuf},
+    programs::{ProbeContext, RetProbeContext},
+};
+use stalk_common::{RawUprobeEvent, UPROBE_ARGS, UsdtArgKind, UsdtArgSpec};
+
+/// Non-zero when `stalk_uretprobe` is attached too, in which case the entry is also held until
+/// the function returns, for a second event with the return value and latency.
+#[unsafe(no_mangle)]
+static UPROBE_PAIRED: u32 = 0;
+
+/// Arguments of the USDT probe `stalk_usdt` is attached to, set by userspace from its note.
+#[unsafe(no_mangle)]
+static USDT_ARGS: [UsdtArgSpec; UPROBE_ARGS] = [UsdtArgSpec::UNUSED; UPROBE_ARGS];
+#[unsafe(no_mangle)]
+static USDT_ARG_COUNT: u32 = 0;
+
+#[map]
+static mut UPROBE_EVENTS: RingBuf = RingBuf::with_byte_size(256 * 1024, 0);
+
+/// Thread -> call waiting for its function to return. Calls that never return, through
+/// `longjmp` or a thread exiting, are evicted eventually.
+#[map]
+static mut UPROBE_PENDING: LruHashMap<u64, RawUprobeEvent> = LruHashMap::with_max_entries(10240, 0);
+
+/// Entry of a user-defined function.
+#[uprobe]
+pub fn stalk_uprobe(ctx: ProbeContext) -> u32 {
+    match try_stalk_uprobe(ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+/// A USDT probe, whose arguments are wherever its spec says rather than in the argument
+/// registers.
+#[uprobe]
+pub fn stalk_usdt(ctx: ProbeContext) -> u32 {
+    match try_stalk_usdt(ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+#[uretprobe]
+pub fn stalk_uretprobe(ctx: RetProbeContext) -> u32 {
+    match try_stalk_uretprobe(ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+fn try_stalk_uprobe(ctx: ProbeContext) -> Result<u32, i64> {
+    let mut event = new_event()?;
+    for (i, arg) in event.args.iter_mut().enumerate() {
+        *arg = ctx.arg(i).unwrap_or(0);
+    }
+    unsafe {
+        if core::ptr::read_volatile(&raw const UPROBE_PAIRED) != 0 {
+            let pending = &raw mut UPROBE_PENDING;
+            (*pending).insert(&bpf_get_current_pid_tgid(), &event, 0)?;
+        }
+        submit(event);
+    }
+    Ok(0)
+}
+
+fn try_stalk_usdt(ctx: ProbeContext) -> Result<u32, i64> {
+    let mut event = new_event()?;
+    unsafe {
+        let count = core::ptr::read_volatile(&raw const USDT_ARG_COUNT) as usize;
+        for (i, arg) in event.args.iter_mut().enumerate() {
+            if i >= count {
+                break;
+            }
+            let spec =
+                core::ptr::read_volatile((&raw const USDT_ARGS).cast::<UsdtArgSpec>().add(i));
+            *arg = usdt_arg(&ctx, &spec).unwrap_or(0);
+        }
+        submit(event);
+    }
+    Ok(0)
+}
+
+/// Reads an argument the way libbpf's `bpf_usdt_arg` does, then narrows it to its size.
+unsafe fn usdt_arg(ctx: &ProbeContext, spec: &UsdtArgSpec) -> Result<u64, i64> {
+    unsafe {
+        let reg = (ctx.regs as *const u8).add(spec.reg_offset as usize) as *const u64;
+        let value = match spec.kind {
+            UsdtArgKind::Const => spec.value as u64,
+            UsdtArgKind::Reg => bpf_probe_read_kernel(reg)?,
+            UsdtArgKind::RegDeref => {
+                let base: u64 = bpf_probe_read_kernel(reg)?;
+                bpf_probe_read_user(base.wrapping_add(spec.value as u64) as *const u64)?
+            }
+        };
+        // Userspace only sets sizes of 1, 2, 4 and 8 bytes.
+        let shift = (8 - spec.size.min(8)) * 8;
+        if shift == 0 || shift >= 64 {
+            return Ok(value);
+        }
+        Ok(if spec.signed != 0 {
+            (((value << shift) as i64) >> shift) as u64
+        } else {
+            (value << shift) >> shift
+        })
+    }
+}
+
+fn new_event() -> Result<RawUprobeEvent, i64> {
+    let tgid_pid = bpf_get_current_pid_tgid();
+    Ok(RawUprobeEvent {
+        pid: (tgid_pid & 0xFFFFFFFF) as u32,
+        tgid: (tgid_pid >> 32) as u32,
+        comm: bpf_get_current_comm()?,
+        args: [0; UPROBE_ARGS],
+        ts: unsafe { bpf_ktime_get_ns() },
+        returned: 0,
+        ret: 0,
+        duration_ns: 0,
+    })
+}
+
+fn try_stalk_uretprobe(ctx: RetProbeContext) -> Result<u32, i64> {
+    let tgid_pid = bpf_get_current_pid_tgid();
+    unsafe {
+        let pending = &raw mut UPROBE_PENDING;
+        let Some(event) = (*pending).get(&tgid_pid) else {
+            return Ok(0);
+        };
+        let event = RawUprobeEvent {
+            returned: 1,
+            ret: ctx.ret().unwrap_or(0),
+            duration_ns: bpf_ktime_get_ns() - event.ts,
+            ..*event
+        };
+        let _ = (*pending).remove(&tgid_pid);
+        submit(event);
+    }
+    Ok(0)
+}
+
+unsafe fn submit(event: RawUprobeEvent) {
+    unsafe {
+        let event_map = &raw mut UPROBE_EVENTS;
+        if let Some(mut buf) = (*event_map).reserve::<RawUprobeEvent>(0) {
+            buf.write(event);
+            buf.submit(0);
+        }
+    }
+}
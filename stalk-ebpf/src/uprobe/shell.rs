@@ -0,0 +1,52 @@
+use aya_ebpf::{
+    helpers::{
+        bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_get_current_uid_gid,
+        bpf_probe_read_user, bpf_probe_read_user_str_bytes,
+    },
+    macros::{map, uretprobe},
+    maps::RingBuf,
+    programs::RetProbeContext,
+};
+use stalk_common::RawShellCommandEvent;
+
+#[map]
+static mut SHELL_EVENTS: RingBuf = RingBuf::with_byte_size(256 * 1024, 0);
+
+/// `char *readline(const char *prompt)` returns the line typed at the prompt, or NULL on EOF.
+#[uretprobe]
+pub fn stalk_readline(ctx: RetProbeContext) -> u32 {
+    match try_stalk_readline(ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+fn try_stalk_readline(ctx: RetProbeContext) -> Result<u32, i64> {
+    let line: *const u8 = ctx.ret().ok_or(1i64)?;
+    if line.is_null() {
+        return Ok(0);
+    }
+    let tgid_pid = bpf_get_current_pid_tgid();
+    unsafe {
+        let event_map = &raw mut SHELL_EVENTS;
+        let Some(mut buf) = (*event_map).reserve::<RawShellCommandEvent>(0) else {
+            return Ok(0);
+        };
+        let event = buf.as_mut_ptr();
+        (*event).pid = (tgid_pid & 0xFFFFFFFF) as u32;
+        (*event).tgid = (tgid_pid >> 32) as u32;
+        (*event).uid = (bpf_get_current_uid_gid() & 0xFFFFFFFF) as u32;
+        (*event).comm = bpf_get_current_comm().unwrap_or([0; 16]);
+        let Ok(read) = bpf_probe_read_user_str_bytes(line, &mut (*event).line) else {
+            buf.discard(0);
+            return Ok(0);
+        };
+        // A full buffer is cut short unless the line ends right there.
+        let len = read.len();
+        (*event).truncated = (len == (*event).line.len() - 1
+            && bpf_probe_read_user::<u8>(line.add(len)).unwrap_or(0) != 0)
+            as u32;
+        buf.submit(0);
+    }
+    Ok(0)
+}
@@ -0,0 +1,137 @@
+use aya_ebpf::{
+    helpers::{
+        bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_get_current_uid_gid,
+        bpf_probe_read_user, r#gen,
+    },
+    macros::{map, uprobe, uretprobe},
+    maps::{HashMap, RingBuf},
+    programs::{ProbeContext, RetProbeContext},
+};
+use stalk_common::{RawTlsDataEvent, TLS_MAX_CAPTURE, TlsDirection};
+
+/// Plaintext bytes captured per call, set by userspace from the configuration.
+#[unsafe(no_mangle)]
+static TLS_CAPTURE_SIZE: u32 = 256;
+
+/// The plaintext buffer of a call waiting to return, and where the `_ex` variants store the
+/// number of bytes transferred.
+#[derive(Copy, Clone)]
+struct PendingSsl {
+    direction: TlsDirection,
+    buf: *const u8,
+    transferred: *const usize,
+}
+
+#[map]
+static mut TLS_EVENTS: RingBuf = RingBuf::with_byte_size(4 * 1024 * 1024, 0);
+
+#[map]
+static mut TLS_PENDING: HashMap<u64, PendingSsl> = HashMap::with_max_entries(10240, 0);
+
+/// `int SSL_read(SSL *ssl, void *buf, int num)`
+#[uprobe]
+pub fn stalk_ssl_read(ctx: ProbeContext) -> u32 {
+    enter(&ctx, TlsDirection::Read, false).unwrap_or(0)
+}
+
+/// `int SSL_write(SSL *ssl, const void *buf, int num)`
+#[uprobe]
+pub fn stalk_ssl_write(ctx: ProbeContext) -> u32 {
+    enter(&ctx, TlsDirection::Write, false).unwrap_or(0)
+}
+
+/// `int SSL_read_ex(SSL *ssl, void *buf, size_t num, size_t *readbytes)`
+#[uprobe]
+pub fn stalk_ssl_read_ex(ctx: ProbeContext) -> u32 {
+    enter(&ctx, TlsDirection::Read, true).unwrap_or(0)
+}
+
+/// `int SSL_write_ex(SSL *s, const void *buf, size_t num, size_t *written)`
+#[uprobe]
+pub fn stalk_ssl_write_ex(ctx: ProbeContext) -> u32 {
+    enter(&ctx, TlsDirection::Write, true).unwrap_or(0)
+}
+
+/// Attached to the return of every function above. `SSL_read` and `SSL_write` return the
+/// number of plaintext bytes transferred, the `_ex` variants 1 and store it. The buffer is
+/// only filled by the time a read returns.
+#[uretprobe]
+pub fn stalk_ssl_ret(ctx: RetProbeContext) -> u32 {
+    match try_stalk_ssl_ret(ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+fn enter(ctx: &ProbeContext, direction: TlsDirection, ex: bool) -> Result<u32, i64> {
+    let buf: *const u8 = ctx.arg(1).ok_or(1i64)?;
+    let transferred: *const usize = if ex {
+        ctx.arg(3).ok_or(1i64)?
+    } else {
+        core::ptr::null()
+    };
+    let tgid_pid = bpf_get_current_pid_tgid();
+    let call = PendingSsl {
+        direction,
+        buf,
+        transferred,
+    };
+    unsafe {
+        let pending = &raw mut TLS_PENDING;
+        (*pending).insert(&tgid_pid, &call, 0)?;
+    }
+    Ok(0)
+}
+
+fn try_stalk_ssl_ret(ctx: RetProbeContext) -> Result<u32, i64> {
+    let tgid_pid = bpf_get_current_pid_tgid();
+    let call = unsafe {
+        let pending = &raw mut TLS_PENDING;
+        let Some(call) = (*pending).get(&tgid_pid).copied() else {
+            return Ok(0);
+        };
+        let _ = (*pending).remove(&tgid_pid);
+        call
+    };
+    let mut ret: i32 = ctx.ret().ok_or(1i64)?;
+    if ret > 0 && !call.transferred.is_null() {
+        let transferred = unsafe { bpf_probe_read_user(call.transferred)? };
+        ret = transferred.min(i32::MAX as usize) as i32;
+    }
+    if ret <= 0 {
+        return Ok(0);
+    }
+    let mut captured = unsafe { core::ptr::read_volatile(&raw const TLS_CAPTURE_SIZE) };
+    if captured > ret as u32 {
+        captured = ret as u32;
+    }
+    if captured > TLS_MAX_CAPTURE as u32 {
+        captured = TLS_MAX_CAPTURE as u32;
+    }
+    unsafe {
+        let event_map = &raw mut TLS_EVENTS;
+        let Some(mut buf) = (*event_map).reserve::<RawTlsDataEvent>(0) else {
+            return Ok(0);
+        };
+        let event = buf.as_mut_ptr();
+        (*event).direction = call.direction;
+        (*event).pid = (tgid_pid & 0xFFFFFFFF) as u32;
+        (*event).tgid = (tgid_pid >> 32) as u32;
+        (*event).uid = (bpf_get_current_uid_gid() & 0xFFFFFFFF) as u32;
+        (*event).comm = bpf_get_current_comm().unwrap_or([0; 16]);
+        (*event).len = ret as u32;
+        (*event).captured = captured;
+        if captured > 0
+            && r#gen::bpf_probe_read_user(
+                (*event).data.as_mut_ptr() as *mut _,
+                captured,
+                call.buf as *const _,
+            ) != 0
+        {
+            buf.discard(0);
+            return Ok(0);
+        }
+        buf.submit(0);
+    }
+    Ok(0)
+}
@@ -1,2 +1,19 @@
+pub mod bio;
+pub mod btf;
+pub mod dns;
+pub mod elf;
+pub mod flamegraph;
+pub mod histogram;
+pub mod layouts;
+pub mod listeners;
+pub mod oom;
+pub mod process;
+pub mod profile;
+pub mod rank;
+pub mod series;
 pub mod server;
 pub mod state;
+pub mod syscalls;
+pub mod tcp;
+pub mod tracefs;
+pub mod usdt;
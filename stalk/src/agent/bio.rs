@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{agent::histogram::Histogram, event::BioEvent};
+
+/// What `/rank/bio` is sorted by.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BioSort {
+    /// Bytes read and written.
+    #[default]
+    Bytes,
+    Read,
+    Write,
+    Count,
+    P99,
+}
+
+/// Whether `/rank/bio` lists processes or devices.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BioGroup {
+    #[default]
+    Process,
+    Device,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BioStats {
+    /// Last command name of the process, empty for devices.
+    pub comm: String,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    pub errors: u64,
+    /// Device latency in us.
+    pub latency: Histogram,
+}
+
+impl BioStats {
+    fn record(&mut self, ev: &BioEvent) {
+        if ev.is_read() {
+            self.read_bytes += ev.bytes as u64;
+        }
+        if ev.is_write() {
+            self.write_bytes += ev.bytes as u64;
+        }
+        if ev.error != 0 {
+            self.errors += 1;
+        }
+        self.latency.record(ev.latency_us);
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BioRow {
+    /// Command name of the process, or name of the device.
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tgid: Option<u32>,
+    pub count: u64,
+    pub errors: u64,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    pub p50_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+}
+
+/// Completed block I/O requests by process and by device.
+#[derive(Debug, Default)]
+pub struct BioTable {
+    pub processes: HashMap<u32, BioStats>,
+    /// Keyed by kernel `dev_t`.
+    pub devices: HashMap<u32, BioStats>,
+    pub all: Histogram,
+}
+
+impl BioTable {
+    pub fn record(&mut self, ev: &BioEvent) {
+        let process = self.processes.entry(ev.pid).or_default();
+        process.comm.clone_from(&ev.comm);
+        process.record(ev);
+        self.devices.entry(ev.dev).or_default().record(ev);
+        self.all.record(ev.latency_us);
+    }
+
+    pub fn rank(&self, group: BioGroup, sort: BioSort) -> Vec<BioRow> {
+        let mut rows: Vec<_> = match group {
+            BioGroup::Process => self
+                .processes
+                .iter()
+                .map(|(tgid, stats)| row(stats.comm.clone(), Some(*tgid), stats))
+                .collect(),
+            BioGroup::Device => self
+                .devices
+                .iter()
+                .map(|(dev, stats)| row(device_name(*dev), None, stats))
+                .collect(),
+        };
+        rows.sort_by_key(|row| {
+            std::cmp::Reverse(match sort {
+                BioSort::Bytes => row.read_bytes + row.write_bytes,
+                BioSort::Read => row.read_bytes,
+                BioSort::Write => row.write_bytes,
+                BioSort::Count => row.count,
+                BioSort::P99 => row.p99_us,
+            })
+        });
+        rows
+    }
+
+    /// Latency of one process, one device by name, or all requests.
+    pub fn histogram(&self, tgid: Option<u32>, device: Option<&str>) -> Option<&Histogram> {
+        match (tgid, device) {
+            (Some(tgid), _) => self.processes.get(&tgid).map(|stats| &stats.latency),
+            (None, Some(device)) => self
+                .devices
+                .iter()
+                .find(|(dev, _)| device_name(**dev) == device)
+                .map(|(_, stats)| &stats.latency),
+            (None, None) => Some(&self.all),
+        }
+    }
+}
+
+fn row(name: String, tgid: Option<u32>, stats: &BioStats) -> BioRow {
+    BioRow {
+        name,
+        tgid,
+        count: stats.latency.count,
+        errors: stats.errors,
+        read_bytes: stats.read_bytes,
+        write_bytes: stats.write_bytes,
+        p50_us: stats.latency.percentile(0.5),
+        p99_us: stats.latency.percentile(0.99),
+        max_us: stats.latency.max,
+    }
+}
+
+/// `DEVNAME` of a kernel `dev_t` from sysfs (e.g. `nvme0n1p2`), or `major:minor`.
+pub fn device_name(dev: u32) -> String {
+    let (major, minor) = (dev >> 20, dev & 0xFFFFF);
+    std::fs::read_to_string(format!("/sys/dev/block/{major}:{minor}/uevent"))
+        .ok()
+        .and_then(|uevent| {
+            uevent
+                .lines()
+                .find_map(|line| line.strip_prefix("DEVNAME=").map(str::to_string))
+        })
+        .unwrap_or_else(|| format!("{major}:{minor}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::Instant;
+
+    use super::*;
+
+    fn event(pid: u32, rwbs: &str, bytes: u32, latency_us: u64) -> BioEvent {
+        BioEvent {
+            pid,
+            comm: format!("proc{pid}"),
+            // 4095:4095 doesn't exist.
+            dev: (4095 << 20) | 4095,
+            sector: 0,
+            bytes,
+            rwbs: rwbs.to_string(),
+            error: 0,
+            latency_us,
+            start_time: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_rank() {
+        let mut table = BioTable::default();
+        table.record(&event(1, "R", 4096, 100));
+        table.record(&event(2, "WS", 65536, 2000));
+        table.record(&event(2, "FWS", 0, 3000));
+
+        let processes = table.rank(BioGroup::Process, BioSort::Bytes);
+        assert_eq!(processes[0].tgid, Some(2));
+        assert_eq!(processes[0].name, "proc2");
+        assert_eq!(processes[0].count, 2);
+        assert_eq!(processes[0].write_bytes, 65536);
+        assert_eq!(processes[1].read_bytes, 4096);
+
+        let devices = table.rank(BioGroup::Device, BioSort::Count);
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].name, "4095:4095");
+        assert_eq!(devices[0].count, 3);
+        assert_eq!(table.histogram(None, Some("4095:4095")).unwrap().count, 3);
+        assert_eq!(table.histogram(Some(1), None).unwrap().count, 1);
+    }
+}
@@ -0,0 +1,319 @@
+//! Just enough of the kernel's BTF to find where probes should read struct fields and function
+//! arguments, instead of guessing them from the kernel version.
+
+use anyhow::{Context, anyhow, bail};
+
+use crate::agent::elf::{read_u16, read_u32};
+
+const KERNEL_BTF: &str = "/sys/kernel/btf/vmlinux";
+const BTF_MAGIC: u16 = 0xeb9f;
+const TYPE_SIZE: usize = 12;
+
+const KIND_INT: u32 = 1;
+const KIND_ARRAY: u32 = 3;
+const KIND_STRUCT: u32 = 4;
+const KIND_UNION: u32 = 5;
+const KIND_ENUM: u32 = 6;
+const KIND_TYPEDEF: u32 = 8;
+const KIND_VOLATILE: u32 = 9;
+const KIND_CONST: u32 = 10;
+const KIND_RESTRICT: u32 = 11;
+const KIND_FUNC: u32 = 12;
+const KIND_FUNC_PROTO: u32 = 13;
+const KIND_VAR: u32 = 14;
+const KIND_DATASEC: u32 = 15;
+const KIND_DECL_TAG: u32 = 17;
+const KIND_TYPE_TAG: u32 = 18;
+const KIND_ENUM64: u32 = 19;
+
+struct Type {
+    name: u32,
+    kind: u32,
+    /// Size for structs and unions, the referenced type for pointers, typedefs and functions.
+    size_or_type: u32,
+    /// Members of structs and unions, parameters of function prototypes.
+    members: Vec<Member>,
+}
+
+struct Member {
+    name: u32,
+    ty: u32,
+    bit_offset: u32,
+}
+
+pub struct Btf {
+    types: Vec<Type>,
+    strings: Vec<u8>,
+}
+
+impl Btf {
+    /// Reads the running kernel's BTF, which needs `CONFIG_DEBUG_INFO_BTF`.
+    pub fn kernel() -> anyhow::Result<Btf> {
+        let data = std::fs::read(KERNEL_BTF).with_context(|| format!("reading {KERNEL_BTF}"))?;
+        Btf::parse(&data)
+    }
+
+    pub fn parse(data: &[u8]) -> anyhow::Result<Btf> {
+        let invalid = || anyhow!("truncated BTF");
+        if read_u16(data, 0) != Some(BTF_MAGIC) {
+            bail!("not little-endian BTF");
+        }
+        let header_len = read_u32(data, 4).ok_or_else(invalid)? as usize;
+        let section = |offset: usize| -> anyhow::Result<&[u8]> {
+            let start = header_len + read_u32(data, offset).ok_or_else(invalid)? as usize;
+            let len = read_u32(data, offset + 4).ok_or_else(invalid)? as usize;
+            data.get(start..start + len).ok_or_else(invalid)
+        };
+        let type_data = section(8)?;
+        let strings = section(16)?.to_vec();
+
+        let mut types = Vec::new();
+        let mut offset = 0;
+        while offset < type_data.len() {
+            let name = read_u32(type_data, offset).ok_or_else(invalid)?;
+            let info = read_u32(type_data, offset + 4).ok_or_else(invalid)?;
+            let size_or_type = read_u32(type_data, offset + 8).ok_or_else(invalid)?;
+            offset += TYPE_SIZE;
+            let kind = (info >> 24) & 0x1f;
+            let vlen = (info & 0xffff) as usize;
+            let bitfields = info >> 31 == 1;
+            let mut members = Vec::new();
+            let extra = match kind {
+                KIND_INT | KIND_VAR | KIND_DECL_TAG => 4,
+                KIND_ARRAY => 12,
+                KIND_STRUCT | KIND_UNION => {
+                    for i in 0..vlen {
+                        let member = offset + i * 12;
+                        let bit_offset = read_u32(type_data, member + 8).ok_or_else(invalid)?;
+                        members.push(Member {
+                            name: read_u32(type_data, member).ok_or_else(invalid)?,
+                            ty: read_u32(type_data, member + 4).ok_or_else(invalid)?,
+                            bit_offset: if bitfields {
+                                bit_offset & 0xff_ffff
+                            } else {
+                                bit_offset
+                            },
+                        });
+                    }
+                    vlen * 12
+                }
+                KIND_ENUM => vlen * 8,
+                KIND_FUNC_PROTO => {
+                    for i in 0..vlen {
+                        let param = offset + i * 8;
+                        members.push(Member {
+                            name: read_u32(type_data, param).ok_or_else(invalid)?,
+                            ty: read_u32(type_data, param + 4).ok_or_else(invalid)?,
+                            bit_offset: 0,
+                        });
+                    }
+                    vlen * 8
+                }
+                KIND_DATASEC | KIND_ENUM64 => vlen * 12,
+                0..=KIND_ENUM64 => 0,
+                _ => bail!("unknown BTF kind {kind}"),
+            };
+            offset += extra;
+            types.push(Type {
+                name,
+                kind,
+                size_or_type,
+                members,
+            });
+        }
+        Ok(Btf { types, strings })
+    }
+
+    /// Byte offset of `path` in struct `ty`, with `.` between the members of nested structs.
+    /// Members of anonymous structs and unions are found as if they were direct members.
+    pub fn offset(&self, ty: &str, path: &str) -> anyhow::Result<u32> {
+        let mut id = self
+            .find(ty, &[KIND_STRUCT, KIND_UNION])
+            .ok_or_else(|| anyhow!("no struct {ty} in BTF"))?;
+        let mut bits = 0;
+        for field in path.split('.') {
+            let (offset, member) = self
+                .member(id, field)
+                .ok_or_else(|| anyhow!("no member {path} in struct {ty}"))?;
+            bits += offset;
+            id = self.resolve(member);
+        }
+        if bits % 8 != 0 {
+            bail!("{ty}.{path} is a bitfield");
+        }
+        Ok(bits / 8)
+    }
+
+    /// Position of parameter `param` of kernel function `func`, for `ProbeContext::arg`.
+    pub fn param(&self, func: &str, param: &str) -> anyhow::Result<u32> {
+        let id = self
+            .find(func, &[KIND_FUNC])
+            .ok_or_else(|| anyhow!("no function {func} in BTF"))?;
+        self.get(self.get(id).map_or(0, |func| func.size_or_type))
+            .filter(|proto| proto.kind == KIND_FUNC_PROTO)
+            .and_then(|proto| {
+                proto
+                    .members
+                    .iter()
+                    .position(|member| self.name(member.name) == param)
+            })
+            .map(|index| index as u32)
+            .ok_or_else(|| anyhow!("no parameter {param} of {func} in BTF"))
+    }
+
+    fn get(&self, id: u32) -> Option<&Type> {
+        self.types.get((id as usize).checked_sub(1)?)
+    }
+
+    fn name(&self, offset: u32) -> &str {
+        let bytes = self.strings.get(offset as usize..).unwrap_or_default();
+        let len = bytes.iter().position(|&c| c == 0).unwrap_or(bytes.len());
+        std::str::from_utf8(&bytes[..len]).unwrap_or_default()
+    }
+
+    /// Id of the first type named `name` of one of `kinds`, skipping empty declarations.
+    fn find(&self, name: &str, kinds: &[u32]) -> Option<u32> {
+        self.types
+            .iter()
+            .position(|ty| {
+                kinds.contains(&ty.kind)
+                    && (ty.kind == KIND_FUNC || !ty.members.is_empty())
+                    && self.name(ty.name) == name
+            })
+            .map(|index| index as u32 + 1)
+    }
+
+    /// Bit offset and type of member `name` of struct or union `id`.
+    fn member(&self, id: u32, name: &str) -> Option<(u32, u32)> {
+        let ty = self.get(id)?;
+        if ty.kind != KIND_STRUCT && ty.kind != KIND_UNION {
+            return None;
+        }
+        if let Some(member) = ty.members.iter().find(|m| self.name(m.name) == name) {
+            return Some((member.bit_offset, member.ty));
+        }
+        ty.members
+            .iter()
+            .filter(|member| member.name == 0)
+            .find_map(|anonymous| {
+                let (offset, ty) = self.member(self.resolve(anonymous.ty), name)?;
+                Some((anonymous.bit_offset + offset, ty))
+            })
+    }
+
+    /// Follows typedefs and qualifiers to the underlying type.
+    fn resolve(&self, mut id: u32) -> u32 {
+        while let Some(ty) = self.get(id) {
+            match ty.kind {
+                KIND_TYPEDEF | KIND_VOLATILE | KIND_CONST | KIND_RESTRICT | KIND_TYPE_TAG => {
+                    id = ty.size_or_type
+                }
+                _ => break,
+            }
+        }
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// String section holding `names`, and the offset of each name in it.
+    fn strings<'a>(names: &[&'a str]) -> (Vec<u8>, std::collections::HashMap<&'a str, u32>) {
+        let mut strings = vec![0u8];
+        let mut offsets = std::collections::HashMap::new();
+        for name in names {
+            offsets.insert(*name, strings.len() as u32);
+            strings.extend_from_slice(name.as_bytes());
+            strings.push(0);
+        }
+        (strings, offsets)
+    }
+
+    /// Builds BTF from the words of each type and its trailing data.
+    fn build(types: &[&[u32]], strings: &[u8]) -> Vec<u8> {
+        let type_data: Vec<u8> = types
+            .iter()
+            .flat_map(|words| words.iter())
+            .flat_map(|word| word.to_le_bytes())
+            .collect();
+        let mut data = Vec::new();
+        data.extend_from_slice(&BTF_MAGIC.to_le_bytes());
+        data.extend_from_slice(&[1, 0]);
+        let header = [24, 0, type_data.len(), type_data.len(), strings.len()];
+        for word in header {
+            data.extend_from_slice(&(word as u32).to_le_bytes());
+        }
+        data.extend(type_data);
+        data.extend_from_slice(strings);
+        data
+    }
+
+    #[test]
+    fn test_offsets_and_params() {
+        let (strings, n) = strings(&[
+            "int",
+            "cred",
+            "uid",
+            "task_struct",
+            "pid",
+            "tgid",
+            "cred_copy",
+            "send_signal",
+            "sig",
+            "task",
+        ]);
+        let btf = build(
+            &[
+                // 1: int
+                &[n["int"], KIND_INT << 24, 4, 32],
+                // 2: struct cred { int usage; int uid; }
+                &[
+                    n["cred"],
+                    KIND_STRUCT << 24 | 2,
+                    8,
+                    0,
+                    1,
+                    0,
+                    n["uid"],
+                    1,
+                    32,
+                ],
+                // 3: const struct cred
+                &[0, KIND_CONST << 24, 2],
+                // 4: union { int tgid; }
+                &[0, KIND_UNION << 24 | 1, 4, n["tgid"], 1, 0],
+                // 5: struct task_struct { int pid; union { int tgid; }; const struct cred cred_copy; }
+                &[
+                    n["task_struct"],
+                    KIND_STRUCT << 24 | 3,
+                    16,
+                    n["pid"],
+                    1,
+                    0,
+                    0,
+                    4,
+                    32,
+                    n["cred_copy"],
+                    3,
+                    64,
+                ],
+                // 6: int (int sig, int task)
+                &[0, KIND_FUNC_PROTO << 24 | 2, 1, n["sig"], 1, n["task"], 1],
+                // 7: send_signal
+                &[n["send_signal"], KIND_FUNC << 24, 6],
+            ],
+            &strings,
+        );
+        let btf = Btf::parse(&btf).unwrap();
+        assert_eq!(btf.offset("task_struct", "pid").unwrap(), 0);
+        assert_eq!(btf.offset("task_struct", "tgid").unwrap(), 4);
+        assert_eq!(btf.offset("task_struct", "cred_copy.uid").unwrap(), 12);
+        assert!(btf.offset("task_struct", "comm").is_err());
+        assert!(btf.offset("mm_struct", "pid").is_err());
+        assert_eq!(btf.param("send_signal", "task").unwrap(), 1);
+        assert!(btf.param("send_signal", "info").is_err());
+        assert!(Btf::parse(b"\x9f\xeb").is_err());
+    }
+}
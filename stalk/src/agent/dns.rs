@@ -0,0 +1,302 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::IpAddr,
+};
+
+use serde::Serialize;
+
+use crate::event::DnsEvent;
+
+const TYPE_A: u16 = 1;
+const TYPE_CNAME: u16 = 5;
+const TYPE_AAAA: u16 = 28;
+/// Compression pointers followed before a name is deemed malformed.
+const MAX_POINTERS: usize = 16;
+/// Addresses remembered by [`DnsCache`].
+const CACHE_SIZE: usize = 16384;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DnsQuestion {
+    pub name: String,
+    pub qtype: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DnsData {
+    A(IpAddr),
+    Aaaa(IpAddr),
+    Cname(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DnsAnswer {
+    pub name: String,
+    pub ttl: u32,
+    pub data: DnsData,
+}
+
+/// The parts of a DNS message stalk cares about. Answers of other types are skipped.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DnsMessage {
+    pub id: u16,
+    pub response: bool,
+    pub rcode: u8,
+    pub questions: Vec<DnsQuestion>,
+    pub answers: Vec<DnsAnswer>,
+}
+
+/// Parses a DNS message, keeping whatever was read before the data ran out or turned out
+/// malformed (messages are truncated to `DNS_MAX_LEN` in the kernel).
+pub fn parse(data: &[u8]) -> Option<DnsMessage> {
+    let id = read_u16(data, 0)?;
+    let flags = read_u16(data, 2)?;
+    let qdcount = read_u16(data, 4)?;
+    let ancount = read_u16(data, 6)?;
+    let mut message = DnsMessage {
+        id,
+        response: flags & 0x8000 != 0,
+        rcode: (flags & 0xF) as u8,
+        ..Default::default()
+    };
+    let mut offset = 12;
+    for _ in 0..qdcount {
+        let Some((name, next)) = read_name(data, offset) else {
+            return Some(message);
+        };
+        let Some(qtype) = read_u16(data, next) else {
+            return Some(message);
+        };
+        message.questions.push(DnsQuestion {
+            name,
+            qtype: type_name(qtype),
+        });
+        offset = next + 4;
+    }
+    for _ in 0..ancount {
+        let Some((name, next)) = read_name(data, offset) else {
+            break;
+        };
+        let (Some(rtype), Some(ttl), Some(rdlength)) = (
+            read_u16(data, next),
+            read_u32(data, next + 4),
+            read_u16(data, next + 8),
+        ) else {
+            break;
+        };
+        let rdata = next + 10;
+        let Some(bytes) = data.get(rdata..rdata + rdlength as usize) else {
+            break;
+        };
+        let record = match rtype {
+            TYPE_A => <[u8; 4]>::try_from(bytes)
+                .ok()
+                .map(|a| DnsData::A(a.into())),
+            TYPE_AAAA => <[u8; 16]>::try_from(bytes)
+                .ok()
+                .map(|a| DnsData::Aaaa(a.into())),
+            TYPE_CNAME => read_name(data, rdata).map(|(cname, _)| DnsData::Cname(cname)),
+            _ => None,
+        };
+        if let Some(data) = record {
+            message.answers.push(DnsAnswer { name, ttl, data });
+        }
+        offset = rdata + rdlength as usize;
+    }
+    Some(message)
+}
+
+/// Name of an `rcode` as in RFC 1035 and RFC 2136.
+pub fn rcode_name(rcode: u8) -> String {
+    match rcode {
+        0 => "NOERROR".to_string(),
+        1 => "FORMERR".to_string(),
+        2 => "SERVFAIL".to_string(),
+        3 => "NXDOMAIN".to_string(),
+        4 => "NOTIMP".to_string(),
+        5 => "REFUSED".to_string(),
+        6 => "YXDOMAIN".to_string(),
+        7 => "YXRRSET".to_string(),
+        8 => "NXRRSET".to_string(),
+        9 => "NOTAUTH".to_string(),
+        10 => "NOTZONE".to_string(),
+        other => format!("RCODE{other}"),
+    }
+}
+
+fn type_name(qtype: u16) -> String {
+    match qtype {
+        TYPE_A => "A".to_string(),
+        2 => "NS".to_string(),
+        TYPE_CNAME => "CNAME".to_string(),
+        6 => "SOA".to_string(),
+        12 => "PTR".to_string(),
+        15 => "MX".to_string(),
+        16 => "TXT".to_string(),
+        TYPE_AAAA => "AAAA".to_string(),
+        33 => "SRV".to_string(),
+        65 => "HTTPS".to_string(),
+        255 => "ANY".to_string(),
+        other => format!("TYPE{other}"),
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Reads a possibly compressed name at `offset`, returning it and the offset just past it.
+fn read_name(data: &[u8], offset: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut pos = offset;
+    let mut end = None;
+    let mut pointers = 0;
+    loop {
+        let len = *data.get(pos)? as usize;
+        match len {
+            0 => break,
+            _ if len & 0xC0 == 0xC0 => {
+                pointers += 1;
+                if pointers > MAX_POINTERS {
+                    return None;
+                }
+                let target = (read_u16(data, pos)? & 0x3FFF) as usize;
+                end.get_or_insert(pos + 2);
+                pos = target;
+            }
+            _ if len & 0xC0 == 0 => {
+                let label = data.get(pos + 1..pos + 1 + len)?;
+                labels.push(String::from_utf8_lossy(label).to_string());
+                pos += 1 + len;
+            }
+            _ => return None,
+        }
+    }
+    let name = if labels.is_empty() {
+        ".".to_string()
+    } else {
+        labels.join(".")
+    };
+    Some((name, end.unwrap_or(pos + 1)))
+}
+
+/// Address -> the name last resolved to it, bounded by evicting the oldest entries.
+#[derive(Debug, Default)]
+pub struct DnsCache {
+    names: HashMap<IpAddr, String>,
+    order: VecDeque<IpAddr>,
+}
+
+impl DnsCache {
+    /// Remembers the addresses in a response under the name that was asked for, so that a
+    /// CNAME chain still maps back to what the process looked up.
+    pub fn apply(&mut self, ev: &DnsEvent) {
+        let Some(question) = ev.message.questions.first() else {
+            return;
+        };
+        for answer in &ev.message.answers {
+            if let DnsData::A(addr) | DnsData::Aaaa(addr) = answer.data {
+                self.insert(addr, question.name.clone());
+            }
+        }
+    }
+
+    pub fn insert(&mut self, addr: IpAddr, name: String) {
+        if self.names.insert(addr, name).is_none() {
+            self.order.push_back(addr);
+        }
+        while self.order.len() > CACHE_SIZE {
+            if let Some(old) = self.order.pop_front() {
+                self.names.remove(&old);
+            }
+        }
+    }
+
+    pub fn get(&self, addr: &IpAddr) -> Option<&String> {
+        self.names.get(addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() {
+        // example.com A, answered with a CNAME to www.example.com (compressed) and its address.
+        let mut data = vec![
+            0x12, 0x34, 0x81, 0x80, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00,
+        ];
+        data.extend_from_slice(b"\x07example\x03com\x00\x00\x01\x00\x01");
+        data.extend_from_slice(&[0xC0, 0x0C, 0x00, 0x05, 0x00, 0x01, 0, 0, 0, 60, 0x00, 0x06]);
+        data.extend_from_slice(b"\x03www\xC0\x0C");
+        data.extend_from_slice(&[0xC0, 0x29, 0x00, 0x01, 0x00, 0x01, 0, 0, 0, 30, 0x00, 0x04]);
+        data.extend_from_slice(&[93, 184, 216, 34]);
+        let message = parse(&data).unwrap();
+        assert_eq!(message.id, 0x1234);
+        assert!(message.response);
+        assert_eq!(message.rcode, 0);
+        assert_eq!(
+            message.questions,
+            vec![DnsQuestion {
+                name: "example.com".to_string(),
+                qtype: "A".to_string()
+            }]
+        );
+        assert_eq!(
+            message.answers,
+            vec![
+                DnsAnswer {
+                    name: "example.com".to_string(),
+                    ttl: 60,
+                    data: DnsData::Cname("www.example.com".to_string()),
+                },
+                DnsAnswer {
+                    name: "www.example.com".to_string(),
+                    ttl: 30,
+                    data: DnsData::A(IpAddr::from([93, 184, 216, 34])),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_truncated() {
+        let mut data = vec![0, 1, 0x81, 0x83, 0, 1, 0, 1, 0, 0, 0, 0];
+        data.extend_from_slice(b"\x04nope\x00\x00\x1C\x00\x01\xC0\x0C\x00");
+        let message = parse(&data).unwrap();
+        assert_eq!(rcode_name(message.rcode), "NXDOMAIN");
+        assert_eq!(message.questions[0].qtype, "AAAA");
+        assert!(message.answers.is_empty());
+        assert!(read_name(b"\xC0\x00", 0).is_none());
+    }
+
+    #[test]
+    fn test_cache_evicts_oldest() {
+        let addr = |i: usize| IpAddr::from((i as u32).to_be_bytes());
+        let mut cache = DnsCache::default();
+        for i in 0..CACHE_SIZE {
+            cache.insert(addr(i), format!("host{i}"));
+        }
+        // A new name for a known address keeps its place.
+        cache.insert(addr(0), "renamed".to_string());
+        assert_eq!(cache.order.len(), CACHE_SIZE);
+        assert_eq!(cache.get(&addr(0)).unwrap(), "renamed");
+
+        cache.insert(addr(CACHE_SIZE), "new".to_string());
+        cache.insert(addr(CACHE_SIZE + 1), "newer".to_string());
+        assert_eq!(cache.get(&addr(0)), None);
+        assert_eq!(cache.get(&addr(1)), None);
+        assert_eq!(cache.get(&addr(2)).unwrap(), "host2");
+        assert_eq!(cache.get(&addr(CACHE_SIZE + 1)).unwrap(), "newer");
+        assert_eq!(cache.names.len(), CACHE_SIZE);
+    }
+}
@@ -0,0 +1,191 @@
+use std::path::Path;
+
+use anyhow::bail;
+
+pub const SHT_NOTE: u32 = 7;
+const SHT_SYMTAB: u32 = 2;
+const SHT_DYNSYM: u32 = 11;
+const PT_LOAD: u32 = 1;
+const STT_FUNC: u8 = 2;
+const SYMBOL_SIZE: usize = 24;
+
+pub struct Section {
+    pub name: u32,
+    pub kind: u32,
+    pub addr: u64,
+    pub offset: u64,
+    pub size: u64,
+    /// Index of the associated section, e.g. the string table of a symbol table.
+    pub link: u32,
+}
+
+/// A `PT_LOAD` segment.
+pub struct Segment {
+    pub offset: u64,
+    pub vaddr: u64,
+    pub filesz: u64,
+}
+
+/// A function from `.symtab`, or `.dynsym` in stripped binaries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub addr: u64,
+    pub size: u64,
+    pub name: String,
+}
+
+/// Only 64-bit little-endian ELF is handled.
+pub fn check_header(data: &[u8], path: &Path) -> anyhow::Result<()> {
+    if data.get(..6) != Some(b"\x7fELF\x02\x01".as_slice()) {
+        bail!("{} is not a 64-bit little-endian ELF", path.display());
+    }
+    Ok(())
+}
+
+pub fn sections(data: &[u8]) -> Option<Vec<Section>> {
+    let shoff = read_u64(data, 0x28)? as usize;
+    let shentsize = read_u16(data, 0x3A)? as usize;
+    let shnum = read_u16(data, 0x3C)? as usize;
+    (0..shnum)
+        .map(|i| {
+            let header = data.get(shoff.checked_add(i.checked_mul(shentsize)?)?..)?;
+            Some(Section {
+                name: read_u32(header, 0)?,
+                kind: read_u32(header, 4)?,
+                addr: read_u64(header, 16)?,
+                offset: read_u64(header, 24)?,
+                size: read_u64(header, 32)?,
+                link: read_u32(header, 40)?,
+            })
+        })
+        .collect()
+}
+
+/// Finds a section by name through the section name table.
+pub fn find_section<'a>(data: &[u8], sections: &'a [Section], name: &str) -> Option<&'a Section> {
+    let names = sections.get(read_u16(data, 0x3E)? as usize)?;
+    sections.iter().find(|section| {
+        names
+            .offset
+            .checked_add(section.name as u64)
+            .is_some_and(|offset| read_str(data, offset as usize) == name)
+    })
+}
+
+pub fn section_data<'a>(data: &'a [u8], section: &Section) -> Option<&'a [u8]> {
+    data.get(section.offset as usize..section.offset.checked_add(section.size)? as usize)
+}
+
+pub fn load_segments(data: &[u8]) -> Option<Vec<Segment>> {
+    let phoff = read_u64(data, 0x20)? as usize;
+    let phentsize = read_u16(data, 0x36)? as usize;
+    let phnum = read_u16(data, 0x38)? as usize;
+    let mut segments = Vec::new();
+    for i in 0..phnum {
+        let header = data.get(phoff.checked_add(i.checked_mul(phentsize)?)?..)?;
+        if read_u32(header, 0)? == PT_LOAD {
+            segments.push(Segment {
+                offset: read_u64(header, 8)?,
+                vaddr: read_u64(header, 16)?,
+                filesz: read_u64(header, 32)?,
+            });
+        }
+    }
+    Some(segments)
+}
+
+/// Maps a virtual address to its offset in the file.
+pub fn file_offset(segments: &[Segment], addr: u64) -> Option<u64> {
+    segments.iter().find_map(|s| {
+        let end = s.vaddr.checked_add(s.filesz)?;
+        if !(s.vaddr..end).contains(&addr) {
+            return None;
+        }
+        (addr - s.vaddr).checked_add(s.offset)
+    })
+}
+
+/// Maps an offset in the file to the virtual address it is loaded at.
+pub fn virtual_address(segments: &[Segment], offset: u64) -> Option<u64> {
+    segments.iter().find_map(|s| {
+        let end = s.offset.checked_add(s.filesz)?;
+        if !(s.offset..end).contains(&offset) {
+            return None;
+        }
+        (offset - s.offset).checked_add(s.vaddr)
+    })
+}
+
+/// Functions of the binary sorted by address, from `.symtab` if present.
+pub fn symbols(data: &[u8], sections: &[Section]) -> Vec<Symbol> {
+    let table = sections
+        .iter()
+        .find(|s| s.kind == SHT_SYMTAB)
+        .or_else(|| sections.iter().find(|s| s.kind == SHT_DYNSYM));
+    let (Some(table), Some(strings)) = (table, table.and_then(|t| sections.get(t.link as usize)))
+    else {
+        return Vec::new();
+    };
+    let entries = section_data(data, table).unwrap_or_default();
+    let mut symbols: Vec<_> = entries
+        .chunks_exact(SYMBOL_SIZE)
+        .filter_map(|entry| {
+            let name = strings.offset.checked_add(read_u32(entry, 0)? as u64)?;
+            let addr = read_u64(entry, 8)?;
+            (entry[4] & 0xF == STT_FUNC && addr != 0).then(|| Symbol {
+                addr,
+                size: read_u64(entry, 16).unwrap_or(0),
+                name: read_str(data, name as usize),
+            })
+        })
+        .collect();
+    symbols.sort_by_key(|symbol| symbol.addr);
+    symbols
+}
+
+fn read_str(data: &[u8], offset: usize) -> String {
+    let bytes = data.get(offset..).unwrap_or_default();
+    let len = bytes.iter().position(|&c| c == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..len]).to_string()
+}
+
+pub fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset.checked_add(2)?)?.try_into().ok()?,
+    ))
+}
+
+pub fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset.checked_add(4)?)?.try_into().ok()?,
+    ))
+}
+
+pub fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        data.get(offset..offset.checked_add(8)?)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offsets_past_the_end() {
+        let mut data = vec![0u8; 64];
+        data[0x28..0x30].copy_from_slice(&u64::MAX.to_le_bytes());
+        data[0x3A..0x3C].copy_from_slice(&64u16.to_le_bytes());
+        data[0x3C..0x3E].copy_from_slice(&2u16.to_le_bytes());
+        assert!(sections(&data).is_none());
+
+        let segments = [Segment {
+            offset: 0x1000,
+            vaddr: u64::MAX - 0x10,
+            filesz: 0x100,
+        }];
+        assert_eq!(file_offset(&segments, u64::MAX - 1), None);
+        assert_eq!(virtual_address(&segments, 0x1008), Some(u64::MAX - 8));
+        assert_eq!(virtual_address(&segments, 0x1020), None);
+    }
+}
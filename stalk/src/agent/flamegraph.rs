@@ -0,0 +1,165 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+const WIDTH: f64 = 1200.0;
+const FRAME_HEIGHT: f64 = 16.0;
+const PAD_SIDE: f64 = 10.0;
+const PAD_TOP: f64 = 40.0;
+const PAD_BOTTOM: f64 = 10.0;
+const FONT_SIZE: f64 = 12.0;
+/// Approximate width of a character at `FONT_SIZE` in a sans-serif font.
+const CHAR_WIDTH: f64 = 7.0;
+/// Frames narrower than this many pixels are left out.
+const MIN_WIDTH: f64 = 0.1;
+
+#[derive(Default)]
+struct Node {
+    count: u64,
+    children: BTreeMap<String, Node>,
+}
+
+impl Node {
+    fn depth(&self) -> usize {
+        self.children
+            .values()
+            .map(|child| child.depth() + 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Renders folded stacks (`frame;frame;frame`, outermost first) as an SVG flame graph,
+/// with frames sorted by name and sized by their counts, which are in `unit`.
+pub fn render(folded: &[(String, u64)], title: &str, unit: &str) -> String {
+    let mut root = Node::default();
+    for (stack, count) in folded {
+        root.count += count;
+        let mut node = &mut root;
+        for frame in stack.split(';') {
+            node = node.children.entry(frame.to_string()).or_default();
+            node.count += count;
+        }
+    }
+    let height = PAD_TOP + (root.depth() + 1) as f64 * FRAME_HEIGHT + PAD_BOTTOM;
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{height}" viewBox="0 0 {WIDTH} {height}" font-family="Verdana, sans-serif" font-size="{FONT_SIZE}">"#
+    );
+    let _ = writeln!(
+        svg,
+        r##"<rect width="100%" height="100%" fill="#f8f8f8"/><text x="{}" y="24" text-anchor="middle" font-size="17">{}</text>"##,
+        WIDTH / 2.0,
+        escape(title)
+    );
+    if root.count > 0 {
+        let mut canvas = Canvas {
+            svg,
+            total: root.count,
+            scale: (WIDTH - 2.0 * PAD_SIDE) / root.count as f64,
+            height,
+            unit,
+        };
+        canvas.draw("all", &root, PAD_SIDE, 0);
+        svg = canvas.svg;
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+struct Canvas<'a> {
+    svg: String,
+    /// Samples in the whole graph.
+    total: u64,
+    /// Pixels per sample.
+    scale: f64,
+    height: f64,
+    unit: &'a str,
+}
+
+impl Canvas<'_> {
+    /// Draws `node` at `depth` levels above the bottom, then its children left to right.
+    fn draw(&mut self, name: &str, node: &Node, x: f64, depth: usize) {
+        let width = node.count as f64 * self.scale;
+        if width < MIN_WIDTH {
+            return;
+        }
+        let y = self.height - PAD_BOTTOM - (depth + 1) as f64 * FRAME_HEIGHT;
+        let percent = node.count as f64 * 100.0 / self.total as f64;
+        let _ = write!(
+            self.svg,
+            r#"<g><title>{} ({} {}, {percent:.2}%)</title><rect x="{x:.1}" y="{y:.1}" width="{width:.1}" height="{}" fill="{}" rx="2"/>"#,
+            escape(name),
+            node.count,
+            self.unit,
+            FRAME_HEIGHT - 1.0,
+            color(name),
+        );
+        let chars = ((width - 6.0) / CHAR_WIDTH) as usize;
+        if chars >= 3 {
+            let label: String = if name.chars().count() > chars {
+                name.chars().take(chars - 2).chain("..".chars()).collect()
+            } else {
+                name.to_string()
+            };
+            let _ = write!(
+                self.svg,
+                r#"<text x="{:.1}" y="{:.1}">{}</text>"#,
+                x + 3.0,
+                y + FRAME_HEIGHT - 4.0,
+                escape(&label)
+            );
+        }
+        self.svg.push_str("</g>\n");
+        let mut x = x;
+        for (name, child) in &node.children {
+            self.draw(name, child, x, depth + 1);
+            x += child.count as f64 * self.scale;
+        }
+    }
+}
+
+/// The "hot" palette of flamegraph.pl, derived from the name so that colors are stable,
+/// with kernel frames in orange.
+fn color(name: &str) -> String {
+    let hash = name
+        .bytes()
+        .fold(5381u32, |hash, b| hash.wrapping_mul(33) ^ b as u32);
+    let v = (hash % 1000) as f64 / 1000.0;
+    if name.ends_with("_[k]") {
+        return format!("rgb(230,{},40)", 130 + (v * 60.0) as u32);
+    }
+    format!(
+        "rgb({},{},{})",
+        205 + (v * 50.0) as u32,
+        (v * 230.0) as u32,
+        (v * 55.0) as u32
+    )
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let folded = vec![
+            ("app;main;work".to_string(), 3),
+            ("app;main;std::vector<int>::push_back".to_string(), 1),
+        ];
+        let svg = render(&folded, "CPU", "samples");
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("<title>all (4 samples, 100.00%)</title>"));
+        assert!(svg.contains("<title>work (3 samples, 75.00%)</title>"));
+        assert!(svg.contains("std::vector&lt;int&gt;::push_back"));
+        // One frame per distinct prefix, plus the root.
+        assert_eq!(svg.matches("<rect x=").count(), 5);
+        assert!(render(&[], "CPU", "samples").ends_with("</svg>\n"));
+    }
+}
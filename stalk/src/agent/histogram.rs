@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+
+/// Bucket `0` holds zero, bucket `i` holds values in `[2^(i-1), 2^i)`.
+const BUCKETS: usize = 65;
+
+/// Log2 histogram of latencies, in microseconds.
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    pub count: u64,
+    pub sum: u64,
+    pub max: u64,
+    buckets: [u64; BUCKETS],
+}
+
+/// Statistic a histogram can be ranked by.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistogramStat {
+    Count,
+    #[default]
+    Sum,
+    P50,
+    P90,
+    P99,
+    Max,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistogramBucket {
+    /// Inclusive upper bound of the bucket.
+    pub le: u64,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistogramSummary {
+    pub count: u64,
+    pub sum: u64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub max: u64,
+    pub buckets: Vec<HistogramBucket>,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram {
+            count: 0,
+            sum: 0,
+            max: 0,
+            buckets: [0; BUCKETS],
+        }
+    }
+}
+
+impl Histogram {
+    pub fn record(&mut self, value: u64) {
+        let bucket = (u64::BITS - value.leading_zeros()) as usize;
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.sum = self.sum.saturating_add(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Upper bound of the bucket holding the `q` quantile, capped at the observed maximum.
+    pub fn percentile(&self, q: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let target = ((self.count as f64 * q).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= target {
+                return bucket_upper_bound(i).min(self.max);
+            }
+        }
+        self.max
+    }
+
+    pub fn stat(&self, stat: HistogramStat) -> u64 {
+        match stat {
+            HistogramStat::Count => self.count,
+            HistogramStat::Sum => self.sum,
+            HistogramStat::P50 => self.percentile(0.50),
+            HistogramStat::P90 => self.percentile(0.90),
+            HistogramStat::P99 => self.percentile(0.99),
+            HistogramStat::Max => self.max,
+        }
+    }
+
+    pub fn summary(&self) -> HistogramSummary {
+        HistogramSummary {
+            count: self.count,
+            sum: self.sum,
+            p50: self.percentile(0.50),
+            p90: self.percentile(0.90),
+            p99: self.percentile(0.99),
+            max: self.max,
+            buckets: self
+                .buckets
+                .iter()
+                .enumerate()
+                .filter(|(_, n)| **n > 0)
+                .map(|(i, n)| HistogramBucket {
+                    le: bucket_upper_bound(i),
+                    count: *n,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn bucket_upper_bound(bucket: usize) -> u64 {
+    match bucket {
+        0 => 0,
+        64.. => u64::MAX,
+        i => (1u64 << i) - 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let mut hist = Histogram::default();
+        for _ in 0..98 {
+            hist.record(5);
+        }
+        hist.record(1000);
+        hist.record(5_000_000);
+
+        assert_eq!(hist.count, 100);
+        assert_eq!(hist.sum, 98 * 5 + 1000 + 5_000_000);
+        assert_eq!(hist.stat(HistogramStat::P50), 7);
+        assert_eq!(hist.stat(HistogramStat::P90), 7);
+        assert_eq!(hist.stat(HistogramStat::P99), 1023);
+        assert_eq!(hist.stat(HistogramStat::Max), 5_000_000);
+    }
+
+    #[test]
+    fn test_percentile_capped_at_max() {
+        let mut hist = Histogram::default();
+        hist.record(0);
+        hist.record(600);
+
+        assert_eq!(hist.percentile(0.5), 0);
+        assert_eq!(hist.percentile(1.0), 600);
+        assert_eq!(hist.summary().buckets.len(), 2);
+    }
+}
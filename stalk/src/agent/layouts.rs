@@ -0,0 +1,375 @@
+use std::mem::offset_of;
+
+use stalk_common::{
+    BlockBioQueueInfo, BlockRqCompleteInfo, BlockRqIssueInfo, InetSockSetStateInfo, ModuleLoadInfo,
+    RawSysEnterInfo, RawSysExitInfo, SchedProcessExecInfo, SchedProcessExitInfo,
+    SchedProcessForkInfo, SchedSwitchInfo, SchedWakeupInfo, SignalDeliverInfo, SysEnterBindInfo,
+    SysEnterBpfInfo, SysEnterChmodInfo, SysEnterChownInfo, SysEnterChrootInfo, SysEnterClone3Info,
+    SysEnterCloneInfo, SysEnterDeleteModuleInfo, SysEnterExecveInfo, SysEnterExecveatInfo,
+    SysEnterFchmodInfo, SysEnterFchmodatInfo, SysEnterFchownInfo, SysEnterFchownatInfo,
+    SysEnterFinitModuleInfo, SysEnterFremovexattrInfo, SysEnterFsetxattrInfo,
+    SysEnterFtruncateInfo, SysEnterLinkInfo, SysEnterLinkatInfo, SysEnterListenInfo,
+    SysEnterMemfdCreateInfo, SysEnterMkdirInfo, SysEnterMkdiratInfo, SysEnterMmapInfo,
+    SysEnterMountInfo, SysEnterMprotectInfo, SysEnterOpenatInfo, SysEnterPivotRootInfo,
+    SysEnterProcessVmInfo, SysEnterPtraceInfo, SysEnterReadInfo, SysEnterRecvfromInfo,
+    SysEnterRecvmmsgInfo, SysEnterRecvmsgInfo, SysEnterRemovexattrInfo, SysEnterRenameInfo,
+    SysEnterRenameat2Info, SysEnterRenameatInfo, SysEnterRmdirInfo, SysEnterSetnsInfo,
+    SysEnterSetxattrInfo, SysEnterSymlinkInfo, SysEnterSymlinkatInfo, SysEnterTruncateInfo,
+    SysEnterUmountInfo, SysEnterUnlinkInfo, SysEnterUnlinkatInfo, SysEnterUnshareInfo, SysExitInfo,
+    SysExitReadInfo, TaskNewtaskInfo, TcpEventSkInfo, TcpEventSkSkbInfo, TcpProbeInfo,
+};
+
+/// Where a compiled `*Info` struct puts a field of the tracepoint record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldLayout {
+    /// Name of the field in the tracefs format.
+    pub name: &'static str,
+    pub offset: usize,
+    pub size: usize,
+}
+
+/// The struct an eBPF program casts its context to, and the fields it relies on.
+pub struct StructLayout {
+    pub ty: &'static str,
+    pub fields: Vec<FieldLayout>,
+}
+
+fn size_of_field<T, F>(_: impl Fn(&T) -> &F) -> usize {
+    size_of::<F>()
+}
+
+/// Lists the header and the given fields of a struct, `field => "name"` for those named
+/// differently in the format file.
+macro_rules! layout {
+    ($ty:ty { $($field:ident $(=> $name:literal)?),* $(,)? }) => {
+        StructLayout {
+            ty: stringify!($ty),
+            fields: vec![
+                layout!(@field $ty, common_type),
+                layout!(@field $ty, common_flags),
+                layout!(@field $ty, common_preempt_count),
+                layout!(@field $ty, common_pid),
+                $(layout!(@field $ty, $field $(=> $name)?)),*
+            ],
+        }
+    };
+    (@field $ty:ty, $field:ident $(=> $name:literal)?) => {
+        FieldLayout {
+            name: layout!(@name $field $($name)?),
+            offset: offset_of!($ty, $field),
+            size: size_of_field(|s: &$ty| &s.$field),
+        }
+    };
+    (@name $field:ident $name:literal) => {
+        $name
+    };
+    (@name $field:ident) => {
+        stringify!($field)
+    };
+}
+
+/// [`layout!`] for `sys_enter_*`/`sys_exit_*`, whose syscall number is `__syscall_nr`.
+macro_rules! syscall_layout {
+    ($ty:ty { $($field:ident $(=> $name:literal)?),* $(,)? }) => {
+        layout!($ty { syscall_nr => "__syscall_nr", $($field $(=> $name)?),* })
+    };
+}
+
+/// Layout expected by a tracepoint program, or `None` for programs that don't read their
+/// context (or only through [`crate::agent::tracefs`]).
+pub fn expected_layout(program: &str) -> Option<StructLayout> {
+    let layout = match program {
+        "stalk_execve" => syscall_layout!(SysEnterExecveInfo {
+            filename,
+            argv,
+            envp
+        }),
+        "stalk_openat" => syscall_layout!(SysEnterOpenatInfo {
+            dfd,
+            filename,
+            flags,
+            mode
+        }),
+        "stalk_read" => syscall_layout!(SysEnterReadInfo { fd, buf, count }),
+        "stalk_read_exit" => syscall_layout!(SysExitReadInfo { ret }),
+        "stalk_task_newtask" => layout!(TaskNewtaskInfo { pid, clone_flags }),
+        "stalk_process_fork" => layout!(SchedProcessForkInfo {
+            parent_comm,
+            parent_pid,
+            child_comm,
+            child_pid
+        }),
+        "stalk_process_exec" => layout!(SchedProcessExecInfo {
+            filename_loc => "filename",
+            pid,
+            old_pid
+        }),
+        "stalk_process_exit" => layout!(SchedProcessExitInfo { comm, pid, prio }),
+        "stalk_signal_deliver" => layout!(SignalDeliverInfo {
+            sig,
+            errno,
+            code,
+            sa_handler,
+            sa_flags
+        }),
+        "stalk_fileops_exit"
+        | "stalk_perms_exit"
+        | "stalk_modules_exit"
+        | "stalk_bpf_exit"
+        | "stalk_namespaces_exit"
+        | "stalk_ptrace_exit"
+        | "stalk_execmem_exit"
+        | "stalk_execveat_exit"
+        | "stalk_socket_exit"
+        | "stalk_dns_exit"
+        | "stalk_dns_connect_exit" => syscall_layout!(SysExitInfo { ret }),
+        "stalk_unlinkat" => syscall_layout!(SysEnterUnlinkatInfo {
+            dfd,
+            pathname,
+            flag
+        }),
+        "stalk_renameat2" => syscall_layout!(SysEnterRenameat2Info {
+            olddfd,
+            oldname,
+            newdfd,
+            newname,
+            flags
+        }),
+        "stalk_linkat" => syscall_layout!(SysEnterLinkatInfo {
+            olddfd,
+            oldname,
+            newdfd,
+            newname,
+            flags
+        }),
+        "stalk_symlinkat" => syscall_layout!(SysEnterSymlinkatInfo {
+            oldname,
+            newdfd,
+            newname
+        }),
+        "stalk_mkdirat" => syscall_layout!(SysEnterMkdiratInfo {
+            dfd,
+            pathname,
+            mode
+        }),
+        "stalk_rmdir" => syscall_layout!(SysEnterRmdirInfo { pathname }),
+        "stalk_unlink" => syscall_layout!(SysEnterUnlinkInfo { pathname }),
+        "stalk_renameat" => syscall_layout!(SysEnterRenameatInfo {
+            olddfd,
+            oldname,
+            newdfd,
+            newname
+        }),
+        "stalk_rename" => syscall_layout!(SysEnterRenameInfo { oldname, newname }),
+        "stalk_link" => syscall_layout!(SysEnterLinkInfo { oldname, newname }),
+        "stalk_symlink" => syscall_layout!(SysEnterSymlinkInfo { oldname, newname }),
+        "stalk_mkdir" => syscall_layout!(SysEnterMkdirInfo { pathname, mode }),
+        "stalk_truncate" => syscall_layout!(SysEnterTruncateInfo { path, length }),
+        "stalk_ftruncate" => syscall_layout!(SysEnterFtruncateInfo { fd, length }),
+        "stalk_fchmodat" => syscall_layout!(SysEnterFchmodatInfo {
+            dfd,
+            filename,
+            mode
+        }),
+        "stalk_chmod" => syscall_layout!(SysEnterChmodInfo { filename, mode }),
+        "stalk_fchmod" => syscall_layout!(SysEnterFchmodInfo { fd, mode }),
+        "stalk_chown" => syscall_layout!(SysEnterChownInfo {
+            filename,
+            user,
+            group
+        }),
+        "stalk_fchown" => syscall_layout!(SysEnterFchownInfo { fd, user, group }),
+        "stalk_fchownat" => syscall_layout!(SysEnterFchownatInfo {
+            dfd,
+            filename,
+            user,
+            group,
+            flag
+        }),
+        "stalk_setxattr" => syscall_layout!(SysEnterSetxattrInfo {
+            pathname,
+            name,
+            value,
+            size,
+            flags
+        }),
+        "stalk_fsetxattr" => syscall_layout!(SysEnterFsetxattrInfo {
+            fd,
+            name,
+            value,
+            size,
+            flags
+        }),
+        "stalk_removexattr" => syscall_layout!(SysEnterRemovexattrInfo { pathname, name }),
+        "stalk_fremovexattr" => syscall_layout!(SysEnterFremovexattrInfo { fd, name }),
+        "stalk_finit_module" => syscall_layout!(SysEnterFinitModuleInfo { fd, uargs, flags }),
+        "stalk_delete_module" => syscall_layout!(SysEnterDeleteModuleInfo { name_user, flags }),
+        "stalk_module_load" => layout!(ModuleLoadInfo {
+            taints,
+            name_loc => "name"
+        }),
+        "stalk_bpf" => syscall_layout!(SysEnterBpfInfo { cmd, uattr, size }),
+        "stalk_mount" => syscall_layout!(SysEnterMountInfo {
+            dev_name,
+            dir_name,
+            fs_type => "type",
+            flags,
+            data
+        }),
+        "stalk_umount" => syscall_layout!(SysEnterUmountInfo { name, flags }),
+        "stalk_setns" => syscall_layout!(SysEnterSetnsInfo { fd, flags }),
+        "stalk_unshare" => syscall_layout!(SysEnterUnshareInfo { unshare_flags }),
+        "stalk_pivot_root" => syscall_layout!(SysEnterPivotRootInfo { new_root, put_old }),
+        "stalk_chroot" => syscall_layout!(SysEnterChrootInfo { filename }),
+        "stalk_clone" => syscall_layout!(SysEnterCloneInfo { clone_flags }),
+        "stalk_clone3" => syscall_layout!(SysEnterClone3Info { uargs, size }),
+        "stalk_ptrace" => syscall_layout!(SysEnterPtraceInfo {
+            request,
+            pid,
+            addr,
+            data
+        }),
+        "stalk_process_vm_readv" | "stalk_process_vm_writev" => {
+            syscall_layout!(SysEnterProcessVmInfo {
+                pid,
+                lvec,
+                liovcnt,
+                rvec,
+                riovcnt,
+                flags
+            })
+        }
+        "stalk_mmap" => syscall_layout!(SysEnterMmapInfo {
+            addr,
+            len,
+            prot,
+            flags,
+            fd,
+            off
+        }),
+        "stalk_mprotect" => syscall_layout!(SysEnterMprotectInfo { start, len, prot }),
+        "stalk_memfd_create" => syscall_layout!(SysEnterMemfdCreateInfo { uname, flags }),
+        "stalk_execveat" => syscall_layout!(SysEnterExecveatInfo {
+            fd,
+            filename,
+            argv,
+            envp,
+            flags
+        }),
+        "stalk_bind" => syscall_layout!(SysEnterBindInfo {
+            fd,
+            umyaddr,
+            addrlen
+        }),
+        "stalk_listen" => syscall_layout!(SysEnterListenInfo { fd, backlog }),
+        "stalk_inet_sock_set_state" => layout!(InetSockSetStateInfo {
+            skaddr,
+            oldstate,
+            newstate,
+            sport,
+            dport,
+            family,
+            protocol,
+            saddr,
+            daddr,
+            saddr_v6,
+            daddr_v6
+        }),
+        "stalk_dns_recvfrom" => syscall_layout!(SysEnterRecvfromInfo {
+            fd,
+            ubuf,
+            size,
+            flags,
+            addr,
+            addr_len
+        }),
+        "stalk_dns_recvmsg" => syscall_layout!(SysEnterRecvmsgInfo { fd, msg, flags }),
+        "stalk_dns_recvmmsg" => syscall_layout!(SysEnterRecvmmsgInfo {
+            fd,
+            mmsg,
+            vlen,
+            flags,
+            timeout
+        }),
+        "stalk_dns_read" => syscall_layout!(SysEnterReadInfo { fd, buf, count }),
+        "stalk_dns_connect" => syscall_layout!(SysEnterBindInfo {
+            fd,
+            umyaddr => "uservaddr",
+            addrlen
+        }),
+        "stalk_sys_enter" => layout!(RawSysEnterInfo { id, args }),
+        "stalk_sys_exit" => layout!(RawSysExitInfo { id, ret }),
+        "stalk_sched_switch" => layout!(SchedSwitchInfo {
+            prev_comm,
+            prev_pid,
+            prev_state,
+            next_pid
+        }),
+        "stalk_sched_wakeup" => layout!(SchedWakeupInfo { pid }),
+        "stalk_bio_queue" => layout!(BlockBioQueueInfo { dev, sector }),
+        "stalk_rq_issue" => layout!(BlockRqIssueInfo { dev, sector, bytes }),
+        "stalk_rq_complete" => layout!(BlockRqCompleteInfo { dev, sector, error }),
+        "stalk_tcp_retransmit" | "stalk_tcp_send_reset" => layout!(TcpEventSkSkbInfo {
+            skaddr,
+            state,
+            sport,
+            dport,
+            family,
+            saddr,
+            daddr,
+            saddr_v6,
+            daddr_v6
+        }),
+        "stalk_tcp_receive_reset" => layout!(TcpEventSkInfo {
+            skaddr,
+            sport,
+            dport,
+            family,
+            saddr,
+            daddr,
+            saddr_v6,
+            daddr_v6
+        }),
+        "stalk_tcp_probe" => layout!(TcpProbeInfo {
+            saddr,
+            daddr,
+            sport,
+            dport
+        }),
+        _ => return None,
+    };
+    Some(layout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::tracefs::{check_layout, parse_format};
+
+    const SYS_ENTER_OPENAT: &str = "name: sys_enter_openat
+ID: 633
+format:
+\tfield:unsigned short common_type;\toffset:0;\tsize:2;\tsigned:0;
+\tfield:unsigned char common_flags;\toffset:2;\tsize:1;\tsigned:0;
+\tfield:unsigned char common_preempt_count;\toffset:3;\tsize:1;\tsigned:0;
+\tfield:int common_pid;\toffset:4;\tsize:4;\tsigned:1;
+
+\tfield:int __syscall_nr;\toffset:8;\tsize:4;\tsigned:1;
+\tfield:int dfd;\toffset:16;\tsize:8;\tsigned:0;
+\tfield:const char * filename;\toffset:24;\tsize:8;\tsigned:0;
+\tfield:int flags;\toffset:32;\tsize:8;\tsigned:0;
+\tfield:umode_t mode;\toffset:40;\tsize:8;\tsigned:0;
+";
+
+    #[test]
+    fn test_check_layout() {
+        let format = parse_format(SYS_ENTER_OPENAT).unwrap();
+        let layout = expected_layout("stalk_openat").unwrap();
+        check_layout(&format, &layout.fields).unwrap();
+
+        // A kernel that dropped the padding before the arguments.
+        let shifted = SYS_ENTER_OPENAT.replace("offset:16;", "offset:12;");
+        let err = check_layout(&parse_format(&shifted).unwrap(), &layout.fields).unwrap_err();
+        assert!(err.to_string().contains("dfd"));
+    }
+}
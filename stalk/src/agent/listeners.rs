@@ -0,0 +1,282 @@
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+};
+
+use serde::Serialize;
+
+use crate::event::SocketEvent;
+
+/// `/proc/net` tables and the state that marks a socket as accepting: `TCP_LISTEN` for TCP,
+/// `TCP_CLOSE` (bound and unconnected) for UDP.
+const PROC_NET: [(&str, &str); 4] = [("tcp", "0A"), ("tcp6", "0A"), ("udp", "07"), ("udp6", "07")];
+
+/// Default of `net.ipv4.ip_local_port_range`.
+const EPHEMERAL_PORTS: (u16, u16) = (32768, 60999);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Listener {
+    pub protocol: String,
+    pub addr: IpAddr,
+    pub port: u16,
+    pub tgid: Option<u32>,
+    pub comm: Option<String>,
+    /// Unix time in ms
+    pub since: Option<u64>,
+    /// Socket inode, which tells apart sockets sharing a port with `SO_REUSEPORT`.
+    #[serde(skip)]
+    pub inode: u64,
+    /// Found in `/proc/net` at startup instead of being seen binding.
+    pub seeded: bool,
+}
+
+/// Listening TCP sockets and UDP sockets bound to a port, by socket inode.
+#[derive(Debug, Default)]
+pub struct ListenerTable {
+    listeners: HashMap<u64, Listener>,
+}
+
+impl ListenerTable {
+    pub fn apply(&mut self, ev: &SocketEvent, now: u64) {
+        let (Some(protocol), Some(addr), Some(port), Some(inode)) =
+            (&ev.protocol, ev.addr, ev.port, ev.inode)
+        else {
+            return;
+        };
+        let listener = Listener {
+            protocol: protocol.clone(),
+            addr,
+            port,
+            tgid: Some(ev.tgid),
+            comm: Some(ev.comm.clone()),
+            since: Some(now),
+            inode,
+            seeded: false,
+        };
+        match ev.op {
+            "tcp_unlisten" => {
+                self.listeners.remove(&inode);
+            }
+            // TCP binds are picked up once they listen, and UDP binds to port 0 are clients.
+            "bind" if ev.ret == 0 && protocol.starts_with("udp") && port != 0 => {
+                self.insert(listener)
+            }
+            "tcp_listen" => self.insert(listener),
+            _ => {}
+        }
+    }
+
+    pub fn list(&self) -> Vec<&Listener> {
+        let mut listeners: Vec<_> = self.listeners.values().collect();
+        listeners.sort_by(|a, b| (&a.protocol, a.port, a.addr).cmp(&(&b.protocol, b.port, b.addr)));
+        listeners
+    }
+
+    /// Drops UDP sockets whose inode is not in `bound`, from [`udp_inodes`], as nothing
+    /// traces their close.
+    pub fn prune_udp(&mut self, bound: &HashSet<u64>) {
+        self.listeners.retain(|inode, listener| {
+            !listener.protocol.starts_with("udp") || bound.contains(inode)
+        });
+    }
+
+    /// Replaces the table with `listeners`, from [`scan`].
+    pub fn seed(&mut self, listeners: Vec<Listener>) {
+        self.listeners.clear();
+        for listener in listeners {
+            self.insert(listener);
+        }
+    }
+
+    fn insert(&mut self, listener: Listener) {
+        self.listeners.insert(listener.inode, listener);
+    }
+}
+
+/// The sockets currently in `/proc/net`. Unconnected UDP sockets on ephemeral ports are left
+/// out: most are clients the kernel bound on their first send.
+pub fn scan() -> Vec<Listener> {
+    let mut listeners = Vec::new();
+    let owners = socket_owners();
+    let ephemeral = ephemeral_ports();
+    for (protocol, state) in PROC_NET {
+        let Ok(contents) = std::fs::read_to_string(format!("/proc/net/{protocol}")) else {
+            continue;
+        };
+        for (addr, port, inode) in parse_proc_net(&contents, state) {
+            if protocol.starts_with("udp") && (ephemeral.0..=ephemeral.1).contains(&port) {
+                continue;
+            }
+            let (tgid, comm) = owners.get(&inode).cloned().unzip();
+            listeners.push(Listener {
+                protocol: protocol.to_string(),
+                addr,
+                port,
+                tgid,
+                comm,
+                since: None,
+                inode,
+                seeded: true,
+            });
+        }
+    }
+    listeners
+}
+
+/// Inodes of the unconnected UDP sockets in `/proc/net`.
+pub fn udp_inodes() -> HashSet<u64> {
+    ["udp", "udp6"]
+        .iter()
+        .filter_map(|protocol| std::fs::read_to_string(format!("/proc/net/{protocol}")).ok())
+        .flat_map(|contents| parse_proc_net(&contents, "07"))
+        .map(|(_, _, inode)| inode)
+        .collect()
+}
+
+/// Range the kernel picks unbound ports from, shared by IPv4 and IPv6.
+fn ephemeral_ports() -> (u16, u16) {
+    std::fs::read_to_string("/proc/sys/net/ipv4/ip_local_port_range")
+        .ok()
+        .and_then(|range| {
+            let mut ports = range.split_whitespace().map(|port| port.parse().ok());
+            Some((ports.next()??, ports.next()??))
+        })
+        .unwrap_or(EPHEMERAL_PORTS)
+}
+
+/// Protocol and inode of socket `fd` of `tgid`, looked up in `/proc/net`.
+pub fn socket_protocol(tgid: u32, fd: i32) -> Option<(String, u64)> {
+    let link = std::fs::read_link(format!("/proc/{tgid}/fd/{fd}")).ok()?;
+    let inode: u64 = link
+        .to_str()?
+        .strip_prefix("socket:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()?;
+    PROC_NET.iter().find_map(|(protocol, _)| {
+        let contents = std::fs::read_to_string(format!("/proc/net/{protocol}")).ok()?;
+        parse_proc_net(&contents, "")
+            .iter()
+            .any(|(_, _, i)| *i == inode)
+            .then(|| (protocol.to_string(), inode))
+    })
+}
+
+/// Socket inode -> owning (tgid, comm), from the fds of every process.
+fn socket_owners() -> HashMap<u64, (u32, String)> {
+    let mut owners = HashMap::new();
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return owners;
+    };
+    for entry in entries.flatten() {
+        let Some(tgid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let Ok(fds) = std::fs::read_dir(format!("/proc/{tgid}/fd")) else {
+            continue;
+        };
+        let comm = crate::agent::process::comm(tgid).unwrap_or_default();
+        for fd in fds.flatten() {
+            let Some(inode) = std::fs::read_link(fd.path()).ok().and_then(|link| {
+                link.to_str()?
+                    .strip_prefix("socket:[")?
+                    .strip_suffix(']')?
+                    .parse::<u64>()
+                    .ok()
+            }) else {
+                continue;
+            };
+            owners.entry(inode).or_insert_with(|| (tgid, comm.clone()));
+        }
+    }
+    owners
+}
+
+/// (local addr, local port, inode) of the rows of a `/proc/net/{tcp,udp}[6]` table in `state`,
+/// or of every row when `state` is empty.
+fn parse_proc_net(contents: &str, state: &str) -> Vec<(IpAddr, u16, u64)> {
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<_> = line.split_whitespace().collect();
+            if !state.is_empty() && *fields.get(3)? != state {
+                return None;
+            }
+            let (addr, port) = fields.get(1)?.split_once(':')?;
+            let port = u16::from_str_radix(port, 16).ok()?;
+            let inode = fields.get(9)?.parse().ok()?;
+            Some((parse_hex_addr(addr)?, port, inode))
+        })
+        .collect()
+}
+
+/// Addresses are printed as native-endian 32-bit words.
+fn parse_hex_addr(hex: &str) -> Option<IpAddr> {
+    let mut bytes = Vec::with_capacity(16);
+    for i in (0..hex.len()).step_by(8) {
+        let word = u32::from_str_radix(hex.get(i..i + 8)?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+    match bytes.len() {
+        4 => Some(IpAddr::from(<[u8; 4]>::try_from(bytes).ok()?)),
+        16 => Some(IpAddr::from(<[u8; 16]>::try_from(bytes).ok()?)),
+        _ => None,
+    }
+}
+
+#[cfg(all(test, target_endian = "little"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_net() {
+        let tcp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0277 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 1234 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:A2C4 0100007F:0277 01 00000000:00000000 00:00000000 00000000  1000        0 5678 1 0000000000000000 20 4 30 10 -1";
+        let listeners = parse_proc_net(tcp, "0A");
+        assert_eq!(listeners, vec![("127.0.0.1".parse().unwrap(), 631, 1234)]);
+        assert_eq!(parse_proc_net(tcp, "").len(), 2);
+
+        let tcp6 = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000001000000:0016 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 42 1 0000000000000000 100 0 0 10 0";
+        assert_eq!(
+            parse_proc_net(tcp6, "0A"),
+            vec![("::1".parse().unwrap(), 22, 42)]
+        );
+    }
+
+    fn listen(op: &'static str, tgid: u32, inode: u64) -> SocketEvent {
+        SocketEvent {
+            op,
+            pid: tgid,
+            tgid,
+            uid: 0,
+            comm: "nginx".to_string(),
+            protocol: Some("tcp".to_string()),
+            addr: Some("0.0.0.0".parse().unwrap()),
+            port: Some(80),
+            backlog: None,
+            fd: -1,
+            inode: Some(inode),
+            ret: 0,
+            start_time: tokio::time::Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_reuseport_listeners() {
+        let mut table = ListenerTable::default();
+        table.apply(&listen("tcp_listen", 1, 10), 0);
+        table.apply(&listen("tcp_listen", 2, 11), 0);
+        assert_eq!(table.list().len(), 2);
+        table.apply(&listen("tcp_unlisten", 1, 10), 0);
+        let listeners = table.list();
+        assert_eq!(listeners.len(), 1);
+        assert_eq!((listeners[0].tgid, listeners[0].inode), (Some(2), 11));
+    }
+}
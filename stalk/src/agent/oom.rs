@@ -0,0 +1,284 @@
+use std::{
+    collections::HashMap,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{
+    agent::tracefs::{TraceFormat, decode_field},
+    event::OomEvent,
+};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// One line of a PSI file: the share of wall time tasks were stalled on memory.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PressureLine {
+    /// Percentages over the last 10s, 60s and 300s.
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+    /// Total stall time in us.
+    pub total_us: u64,
+}
+
+/// Memory pressure of a cgroup, or of the whole system for `/`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Pressure {
+    /// At least one task was stalled.
+    pub some: PressureLine,
+    /// All non-idle tasks were stalled at once.
+    pub full: PressureLine,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PressureRow {
+    pub cgroup: String,
+    #[serde(flatten)]
+    pub pressure: Pressure,
+}
+
+/// Cgroups by the share of the last 10s some task in them was stalled on memory.
+pub fn pressure_rank(samples: &HashMap<String, Pressure>) -> Vec<PressureRow> {
+    let mut rows: Vec<_> = samples
+        .iter()
+        .map(|(cgroup, pressure)| PressureRow {
+            cgroup: cgroup.clone(),
+            pressure: pressure.clone(),
+        })
+        .collect();
+    rows.sort_by(|a, b| b.pressure.some.avg10.total_cmp(&a.pressure.some.avg10));
+    rows
+}
+
+/// Parses `/proc/pressure/memory` or a cgroup's `memory.pressure`.
+pub fn parse_pressure(contents: &str) -> Option<Pressure> {
+    let mut pressure = Pressure::default();
+    for line in contents.lines() {
+        let mut words = line.split_whitespace();
+        let target = match words.next()? {
+            "some" => &mut pressure.some,
+            "full" => &mut pressure.full,
+            _ => continue,
+        };
+        for word in words {
+            let (key, value) = word.split_once('=')?;
+            match key {
+                "avg10" => target.avg10 = value.parse().ok()?,
+                "avg60" => target.avg60 = value.parse().ok()?,
+                "avg300" => target.avg300 = value.parse().ok()?,
+                "total" => target.total_us = value.parse().ok()?,
+                _ => {}
+            }
+        }
+    }
+    Some(pressure)
+}
+
+/// Memory pressure of every cgroup, keyed by path relative to the cgroup2 mount, with the
+/// system-wide figures under `/`.
+pub fn sample_pressure() -> Vec<(String, Pressure)> {
+    let mut samples = Vec::new();
+    if let Some(pressure) = std::fs::read_to_string("/proc/pressure/memory")
+        .ok()
+        .and_then(|contents| parse_pressure(&contents))
+    {
+        samples.push(("/".to_string(), pressure));
+    }
+    for dir in cgroup_dirs() {
+        if let Some(pressure) = std::fs::read_to_string(dir.join("memory.pressure"))
+            .ok()
+            .and_then(|contents| parse_pressure(&contents))
+        {
+            samples.push((relative_path(&dir), pressure));
+        }
+    }
+    samples
+}
+
+/// Path of the cgroup with this id, which on cgroup v2 is the inode of its directory.
+pub fn cgroup_path(id: u64) -> Option<String> {
+    cgroup_dirs()
+        .into_iter()
+        .find(|dir| std::fs::metadata(dir).is_ok_and(|meta| meta.ino() == id))
+        .map(|dir| relative_path(&dir))
+}
+
+/// Fills in the victim from the `oom:mark_victim` record. Kernels before 6.8 only record the
+/// pid, so the rest is read from `/proc` while the victim is still being torn down.
+pub fn decode_victim(event: &mut OomEvent, format: &TraceFormat) {
+    let record = &event.record;
+    let field = |name: &str| {
+        format
+            .field(name)
+            .and_then(|field| decode_field(record, field))
+    };
+    let number = |name: &str| field(name).and_then(|value| value.parse::<i64>().ok());
+    let pid = number("pid").unwrap_or_default() as u32;
+    let comm = field("comm").or_else(|| crate::agent::process::comm(pid));
+    let rss_kb = match (number("anon_rss"), number("file_rss"), number("shmem_rss")) {
+        (Some(anon), Some(file), Some(shmem)) => Some((anon + file + shmem) as u64),
+        _ => status_rss_kb(pid),
+    };
+    let oom_score_adj = number("oom_score_adj").or_else(|| {
+        std::fs::read_to_string(format!("/proc/{pid}/oom_score_adj"))
+            .ok()
+            .and_then(|adj| adj.trim().parse().ok())
+    });
+    event.pid = pid;
+    event.comm = comm.unwrap_or_default();
+    event.rss_kb = rss_kb;
+    event.oom_score_adj = oom_score_adj;
+}
+
+/// Whether `event` is a kill. A task that is already exiting is marked as the victim without
+/// `oom_kill_process`, so that it frees its memory, and nothing is killed. `constraint` comes
+/// from that kprobe; without it, a victim other than the thread that ran out is taken as a kill.
+pub fn is_kill(event: &OomEvent, kprobe_attached: bool) -> bool {
+    if kprobe_attached {
+        event.constraint.is_some()
+    } else {
+        event.pid != event.trigger_thread
+    }
+}
+
+fn status_rss_kb(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Every directory of the cgroup2 hierarchy, parents before children.
+fn cgroup_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from(CGROUP_ROOT)];
+    let mut i = 0;
+    while let Some(dir) = dirs.get(i) {
+        let children: Vec<_> = std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|ty| ty.is_dir()))
+            .map(|entry| entry.path())
+            .collect();
+        dirs.extend(children);
+        i += 1;
+    }
+    dirs
+}
+
+fn relative_path(dir: &Path) -> String {
+    match dir.strip_prefix(CGROUP_ROOT) {
+        Ok(rel) if !rel.as_os_str().is_empty() => format!("/{}", rel.display()),
+        _ => "/".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::tracefs::parse_format;
+
+    const MARK_VICTIM: &str = "name: mark_victim
+ID: 500
+format:
+\tfield:unsigned short common_type;\toffset:0;\tsize:2;\tsigned:0;
+\tfield:unsigned char common_flags;\toffset:2;\tsize:1;\tsigned:0;
+\tfield:unsigned char common_preempt_count;\toffset:3;\tsize:1;\tsigned:0;
+\tfield:int common_pid;\toffset:4;\tsize:4;\tsigned:1;
+
+\tfield:int pid;\toffset:8;\tsize:4;\tsigned:1;
+\tfield:__data_loc char[] comm;\toffset:12;\tsize:4;\tsigned:0;
+\tfield:unsigned long total_vm;\toffset:16;\tsize:8;\tsigned:0;
+\tfield:unsigned long anon_rss;\toffset:24;\tsize:8;\tsigned:0;
+\tfield:unsigned long file_rss;\toffset:32;\tsize:8;\tsigned:0;
+\tfield:unsigned long shmem_rss;\toffset:40;\tsize:8;\tsigned:0;
+\tfield:uid_t uid;\toffset:48;\tsize:4;\tsigned:0;
+\tfield:unsigned long pgtables;\toffset:56;\tsize:8;\tsigned:0;
+\tfield:short oom_score_adj;\toffset:64;\tsize:2;\tsigned:1;
+
+print fmt: \"pid=%d\", REC->pid
+";
+
+    fn event(record: Vec<u8>) -> OomEvent {
+        OomEvent {
+            pid: 0,
+            comm: String::new(),
+            rss_kb: None,
+            oom_score_adj: None,
+            constraint: None,
+            cgroup: None,
+            trigger_pid: 10,
+            trigger_comm: "java".to_string(),
+            pressure: None,
+            trigger_thread: 12,
+            memcg_id: 0,
+            record,
+            start_time: tokio::time::Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_decode_victim() {
+        let format = parse_format(MARK_VICTIM).unwrap();
+        let mut record = vec![0u8; 68];
+        record[8..12].copy_from_slice(&4321i32.to_ne_bytes());
+        record[12..16].copy_from_slice(&((5u32 << 16) | 68).to_ne_bytes());
+        record[24..32].copy_from_slice(&100u64.to_ne_bytes());
+        record[32..40].copy_from_slice(&20u64.to_ne_bytes());
+        record[40..48].copy_from_slice(&3u64.to_ne_bytes());
+        record[64..66].copy_from_slice(&(-500i16).to_ne_bytes());
+        record.extend_from_slice(b"java\0");
+        let mut victim = event(record);
+        decode_victim(&mut victim, &format);
+        assert_eq!((victim.pid, victim.comm.as_str()), (4321, "java"));
+        assert_eq!(
+            (victim.rss_kb, victim.oom_score_adj),
+            (Some(123), Some(-500))
+        );
+
+        // A record cut short keeps what was copied and leaves the rest unknown.
+        let mut victim = event(vec![0, 0, 0, 0, 0, 0, 0, 0, 0xF0, 0xFF, 0xFF, 0x7F]);
+        decode_victim(&mut victim, &format);
+        assert_eq!(victim.pid, 0x7FFF_FFF0);
+        assert_eq!((victim.comm.as_str(), victim.rss_kb), ("", None));
+    }
+
+    #[test]
+    fn test_is_kill() {
+        let mut victim = event(Vec::new());
+        victim.pid = 12;
+        // Marked without oom_kill_process: the thread that ran out was already exiting.
+        assert!(!is_kill(&victim, true));
+        victim.constraint = Some("cgroup");
+        assert!(is_kill(&victim, true));
+
+        victim.constraint = None;
+        assert!(!is_kill(&victim, false));
+        victim.pid = 30;
+        assert!(is_kill(&victim, false));
+    }
+
+    #[test]
+    fn test_parse_pressure() {
+        let pressure = parse_pressure(
+            "some avg10=1.50 avg60=0.25 avg300=0.00 total=123456\n\
+             full avg10=0.75 avg60=0.00 avg300=0.00 total=6789\n",
+        )
+        .unwrap();
+        assert_eq!(pressure.some.avg10, 1.5);
+        assert_eq!(pressure.some.total_us, 123456);
+        assert_eq!(pressure.full.avg10, 0.75);
+        assert_eq!(pressure.full.total_us, 6789);
+        assert!(parse_pressure("some avg10=x").is_none());
+        assert_eq!(relative_path(Path::new("/sys/fs/cgroup/a/b")), "/a/b");
+        assert_eq!(relative_path(Path::new("/sys/fs/cgroup")), "/");
+    }
+}
@@ -0,0 +1,367 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    os::unix::fs::MetadataExt,
+    path::PathBuf,
+};
+
+use serde::Serialize;
+use stalk_common::ProcessEventKind;
+
+use crate::event::{ExitStatus, ProcessEvent};
+
+/// Exited processes kept around for inspection before being dropped.
+const MAX_EXITED: usize = 4096;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecRecord {
+    /// Unix time in ms
+    pub time: u64,
+    pub filename: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessInfo {
+    pub tgid: u32,
+    pub ppid: u32,
+    pub comm: String,
+    /// Current executable, inherited from the parent until the process execs.
+    pub exe: Option<String>,
+    pub children: Vec<u32>,
+    /// Execs seen while stalk was running.
+    pub execs: Vec<ExecRecord>,
+    /// Unix time in ms
+    pub start_time: Option<u64>,
+    /// Unix time in ms
+    pub end_time: Option<u64>,
+    pub exit_status: Option<ExitStatus>,
+    pub lifetime_ms: Option<u64>,
+    /// Found in `/proc` at startup instead of being seen forking.
+    pub seeded: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProcessTree {
+    pub tgid: u32,
+    pub comm: String,
+    pub running: bool,
+    pub children: Vec<ProcessTree>,
+}
+
+#[derive(Debug, Default)]
+pub struct ProcessTable {
+    processes: HashMap<u32, ProcessInfo>,
+    exited: VecDeque<u32>,
+}
+
+impl ProcessInfo {
+    fn new(tgid: u32, ppid: u32, comm: String) -> Self {
+        ProcessInfo {
+            tgid,
+            ppid,
+            comm,
+            exe: None,
+            children: Vec::new(),
+            execs: Vec::new(),
+            start_time: None,
+            end_time: None,
+            exit_status: None,
+            lifetime_ms: None,
+            seeded: false,
+        }
+    }
+}
+
+impl ProcessTable {
+    pub fn apply(&mut self, ev: &ProcessEvent, now: u64) {
+        match ev.kind {
+            ProcessEventKind::Fork => self.fork(ev.ppid, ev.pid, &ev.comm, now),
+            ProcessEventKind::Exec => self.exec(ev.tgid, &ev.comm, &ev.filename, now),
+            ProcessEventKind::Exit => self.exit(ev.tgid, now),
+        }
+    }
+
+    pub fn set_exit_status(&mut self, tgid: u32, status: ExitStatus) {
+        if let Some(process) = self.processes.get_mut(&tgid) {
+            process.exit_status = Some(status);
+        }
+    }
+
+    pub fn get(&self, tgid: u32) -> Option<&ProcessInfo> {
+        self.processes.get(&tgid)
+    }
+
+    pub fn list(&self) -> Vec<&ProcessInfo> {
+        let mut processes: Vec<_> = self.processes.values().collect();
+        processes.sort_by_key(|p| p.tgid);
+        processes
+    }
+
+    pub fn tree(&self, tgid: u32) -> Option<ProcessTree> {
+        self.subtree(tgid, &mut HashSet::new())
+    }
+
+    /// Replaces the table with processes found by [`scan`].
+    pub fn seed(&mut self, processes: Vec<ProcessInfo>) {
+        self.processes = processes.into_iter().map(|p| (p.tgid, p)).collect();
+        self.exited.clear();
+        let links: Vec<_> = self.processes.values().map(|p| (p.ppid, p.tgid)).collect();
+        for (ppid, tgid) in links {
+            if let Some(parent) = self.processes.get_mut(&ppid) {
+                parent.children.push(tgid);
+            }
+        }
+    }
+
+    fn fork(&mut self, ppid: u32, tgid: u32, comm: &str, now: u64) {
+        // The pid may have been reused since the last process holding it.
+        self.remove(tgid);
+        let mut process = ProcessInfo::new(tgid, ppid, comm.to_string());
+        process.start_time = Some(now);
+        process.exe = self
+            .processes
+            .get(&ppid)
+            .and_then(|parent| parent.exe.clone());
+        self.processes.insert(tgid, process);
+        if let Some(parent) = self.processes.get_mut(&ppid) {
+            parent.children.push(tgid);
+        }
+    }
+
+    fn exec(&mut self, tgid: u32, comm: &str, filename: &str, now: u64) {
+        let process = self
+            .processes
+            .entry(tgid)
+            .or_insert_with(|| ProcessInfo::new(tgid, 0, String::new()));
+        process.comm = comm.to_string();
+        process.exe = Some(filename.to_string());
+        process.execs.push(ExecRecord {
+            time: now,
+            filename: filename.to_string(),
+        });
+    }
+
+    fn exit(&mut self, tgid: u32, now: u64) {
+        let Some(process) = self.processes.get_mut(&tgid) else {
+            return;
+        };
+        process.end_time = Some(now);
+        process.lifetime_ms = process.start_time.map(|start| now.saturating_sub(start));
+        self.exited.push_back(tgid);
+        while self.exited.len() > MAX_EXITED {
+            if let Some(tgid) = self.exited.pop_front()
+                && self
+                    .processes
+                    .get(&tgid)
+                    .is_some_and(|p| p.end_time.is_some())
+            {
+                self.remove(tgid);
+            }
+        }
+    }
+
+    fn remove(&mut self, tgid: u32) {
+        if let Some(process) = self.processes.remove(&tgid)
+            && let Some(parent) = self.processes.get_mut(&process.ppid)
+        {
+            parent.children.retain(|child| *child != tgid);
+        }
+    }
+
+    fn subtree(&self, tgid: u32, visited: &mut HashSet<u32>) -> Option<ProcessTree> {
+        if !visited.insert(tgid) {
+            return None;
+        }
+        let process = self.processes.get(&tgid)?;
+        Some(ProcessTree {
+            tgid,
+            comm: process.comm.clone(),
+            running: process.end_time.is_none(),
+            children: process
+                .children
+                .iter()
+                .filter_map(|child| self.subtree(*child, visited))
+                .collect(),
+        })
+    }
+}
+
+/// Processes currently in `/proc`, for [`ProcessTable::seed`].
+pub fn scan() -> Vec<ProcessInfo> {
+    let boot_time = boot_time_ms().unwrap_or_default();
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as u64;
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let tgid = entry.file_name().to_str()?.parse().ok()?;
+            let stat = std::fs::read_to_string(format!("/proc/{tgid}/stat")).ok()?;
+            let (comm, ppid, start_ticks) = parse_stat(&stat)?;
+            let mut process = ProcessInfo::new(tgid, ppid, comm);
+            process.start_time = Some(boot_time + start_ticks * 1000 / ticks);
+            process.seeded = true;
+            process.exe = std::fs::read_link(format!("/proc/{tgid}/exe"))
+                .ok()
+                .map(|exe| exe.to_string_lossy().to_string());
+            Some(process)
+        })
+        .collect()
+}
+
+/// Current `comm` of `pid`, if it is still running.
+pub fn comm(pid: u32) -> Option<String> {
+    std::fs::read_to_string(format!("/proc/{pid}/comm"))
+        .ok()
+        .map(|comm| comm.trim_end().to_string())
+}
+
+/// Terminal on the standard input of process `tgid`, e.g. `/dev/pts/3`.
+pub fn tty(tgid: u32) -> Option<String> {
+    let path = std::fs::read_link(format!("/proc/{tgid}/fd/0")).ok()?;
+    let path = path.to_string_lossy();
+    path.starts_with("/dev/").then(|| path.to_string())
+}
+
+/// Shared objects whose file name starts with `prefix` (e.g. `libssl.so`) mapped by any
+/// running process. Paths go through `/proc/<pid>/root` so that libraries inside containers
+/// are found too, and each file is listed once, with its `(dev, inode)`, however many
+/// processes map it. Files whose `(dev, inode)` is in `seen` are skipped.
+pub fn find_libraries(prefix: &str, seen: &HashSet<(u64, u64)>) -> Vec<((u64, u64), PathBuf)> {
+    let mut libraries = Vec::new();
+    let mut found = HashSet::new();
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return libraries;
+    };
+    for entry in entries.flatten() {
+        let Some(tgid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let Ok(maps) = std::fs::read_to_string(format!("/proc/{tgid}/maps")) else {
+            continue;
+        };
+        for line in maps.lines() {
+            let Some(path) = line.splitn(6, ' ').nth(5).map(str::trim) else {
+                continue;
+            };
+            let matches = path.starts_with('/')
+                && path
+                    .rsplit('/')
+                    .next()
+                    .is_some_and(|name| name.starts_with(prefix));
+            if !matches {
+                continue;
+            }
+            let library = PathBuf::from(format!("/proc/{tgid}/root{path}"));
+            let Ok(metadata) = std::fs::metadata(&library) else {
+                continue;
+            };
+            let key = (metadata.dev(), metadata.ino());
+            if !seen.contains(&key) && found.insert(key) {
+                libraries.push((key, library));
+            }
+        }
+    }
+    libraries
+}
+
+/// Makes `path` absolute against `dfd` of process `tgid`, reading the directory (or, for an
+/// empty path, the file itself) from `/proc`. Falls back to `path` when the process is gone.
+pub fn resolve_path(tgid: u32, dfd: i32, path: &str) -> String {
+    if path.starts_with('/') {
+        return path.to_string();
+    }
+    let base = if dfd == libc::AT_FDCWD {
+        format!("/proc/{tgid}/cwd")
+    } else {
+        format!("/proc/{tgid}/fd/{dfd}")
+    };
+    match std::fs::read_link(base) {
+        Ok(dir) if path.is_empty() => dir.to_string_lossy().to_string(),
+        Ok(dir) => dir.join(path).to_string_lossy().to_string(),
+        Err(_) => path.to_string(),
+    }
+}
+
+fn boot_time_ms() -> Option<u64> {
+    let stat = std::fs::read_to_string("/proc/stat").ok()?;
+    let btime = stat.lines().find_map(|line| line.strip_prefix("btime "))?;
+    btime.trim().parse::<u64>().ok().map(|secs| secs * 1000)
+}
+
+/// Extracts comm, ppid and start time in clock ticks from `/proc/<pid>/stat`.
+fn parse_stat(stat: &str) -> Option<(String, u32, u64)> {
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let comm = stat.get(open + 1..close)?.to_string();
+    let fields: Vec<_> = stat.get(close + 1..)?.split_whitespace().collect();
+    let ppid = fields.get(1)?.parse().ok()?;
+    let start_ticks = fields.get(19)?.parse().ok()?;
+    Some((comm, ppid, start_ticks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: ProcessEventKind, pid: u32, tgid: u32, ppid: u32) -> ProcessEvent {
+        ProcessEvent {
+            kind,
+            pid,
+            tgid,
+            ppid,
+            comm: "sh".to_string(),
+            filename: "/bin/sh".to_string(),
+            start_time: tokio::time::Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_parse_stat() {
+        let stat = "1234 (my (odd) comm) S 1 1234 1234 0 -1 4194560 100 0 0 0 1 2 0 0 20 0 1 0 5678 1000 100";
+        assert_eq!(
+            parse_stat(stat),
+            Some(("my (odd) comm".to_string(), 1, 5678))
+        );
+    }
+
+    #[test]
+    fn test_lifecycle() {
+        let mut table = ProcessTable::default();
+        table.apply(&event(ProcessEventKind::Fork, 20, 20, 10), 1000);
+        table.apply(&event(ProcessEventKind::Fork, 30, 30, 20), 1100);
+        table.apply(&event(ProcessEventKind::Exec, 30, 30, 0), 1200);
+        table.apply(&event(ProcessEventKind::Exit, 30, 30, 0), 1500);
+        table.set_exit_status(30, ExitStatus::Exited { code: 1 });
+
+        let child = table.get(30).unwrap();
+        assert_eq!(child.ppid, 20);
+        assert_eq!(child.execs.len(), 1);
+        assert_eq!(child.lifetime_ms, Some(400));
+        assert_eq!(child.exit_status, Some(ExitStatus::Exited { code: 1 }));
+        let tree = table.tree(20).unwrap();
+        assert_eq!(tree.children.len(), 1);
+        assert!(!tree.children[0].running);
+    }
+
+    #[test]
+    fn test_seed() {
+        let seeded = |tgid, ppid| ProcessInfo {
+            seeded: true,
+            ..ProcessInfo::new(tgid, ppid, "init".to_string())
+        };
+        let mut table = ProcessTable::default();
+        table.apply(&event(ProcessEventKind::Fork, 99, 99, 1), 1000);
+        table.seed(vec![seeded(1, 0), seeded(20, 1)]);
+        table.apply(&event(ProcessEventKind::Fork, 30, 30, 20), 1100);
+
+        assert!(table.get(99).is_none());
+        assert_eq!(table.get(1).unwrap().children, vec![20]);
+        assert_eq!(table.get(20).unwrap().children, vec![30]);
+        assert!(!table.get(30).unwrap().seeded);
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use serde::Serialize;
+
+use crate::agent::elf::{
+    Segment, Symbol, check_header, load_segments, sections, symbols, virtual_address,
+};
+
+/// A file-backed region of `/proc/<pid>/maps`.
+struct Mapping {
+    start: u64,
+    end: u64,
+    offset: u64,
+    path: String,
+}
+
+/// What's needed to turn an offset in a binary into a function name.
+struct ElfSymbols {
+    segments: Vec<Segment>,
+    symbols: Vec<Symbol>,
+}
+
+impl ElfSymbols {
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        let data = std::fs::read(path)?;
+        check_header(&data, path)?;
+        Ok(ElfSymbols {
+            segments: load_segments(&data).unwrap_or_default(),
+            symbols: sections(&data)
+                .map(|sections| symbols(&data, &sections))
+                .unwrap_or_default(),
+        })
+    }
+
+    fn lookup(&self, offset: u64) -> Option<&str> {
+        let addr = virtual_address(&self.segments, offset)?;
+        let i = self.symbols.partition_point(|symbol| symbol.addr <= addr);
+        let symbol = self.symbols.get(i.checked_sub(1)?)?;
+        // Symbols without a size (e.g. hand-written assembly) extend to the next one.
+        (symbol.size == 0 || addr < symbol.addr + symbol.size).then_some(symbol.name.as_str())
+    }
+}
+
+/// Turns stacks of instruction pointers into folded stacks, caching symbol tables by binary.
+pub struct Symbolizer {
+    kernel: BTreeMap<u64, String>,
+    binaries: HashMap<String, Option<ElfSymbols>>,
+    processes: HashMap<u32, Vec<Mapping>>,
+}
+
+impl Symbolizer {
+    /// `kernel` maps addresses from `/proc/kallsyms` to symbol names.
+    pub fn new(kernel: BTreeMap<u64, String>) -> Self {
+        Symbolizer {
+            kernel,
+            binaries: HashMap::new(),
+            processes: HashMap::new(),
+        }
+    }
+
+    /// `comm;user frames;kernel frames`, outermost first, kernel frames suffixed with `_[k]`
+    /// as in Brendan Gregg's `stackcollapse` scripts. Stacks are innermost first, as read
+    /// from the stack trace map.
+    pub fn fold(&mut self, tgid: u32, comm: &[u8; 16], user: &[u64], kernel: &[u64]) -> String {
+        let len = comm.iter().position(|&c| c == 0).unwrap_or(16);
+        let mut frames = vec![String::from_utf8_lossy(&comm[..len]).to_string()];
+        frames.extend(user.iter().rev().map(|&ip| self.user_symbol(tgid, ip)));
+        frames.extend(
+            kernel
+                .iter()
+                .rev()
+                .map(|&ip| format!("{}_[k]", self.kernel_symbol(ip))),
+        );
+        frames
+            .iter()
+            .map(|frame| frame.replace(';', ":"))
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    /// Memory maps are re-read on the next fold, as processes exec and map libraries.
+    pub fn forget_processes(&mut self) {
+        self.processes.clear();
+    }
+
+    fn kernel_symbol(&self, ip: u64) -> &str {
+        self.kernel
+            .range(..=ip)
+            .next_back()
+            .map_or("[unknown]", |(_, name)| name.as_str())
+    }
+
+    fn user_symbol(&mut self, tgid: u32, ip: u64) -> String {
+        let mappings = self
+            .processes
+            .entry(tgid)
+            .or_insert_with(|| read_maps(tgid));
+        let Some(mapping) = mappings.iter().find(|m| (m.start..m.end).contains(&ip)) else {
+            return "[unknown]".to_string();
+        };
+        let offset = ip - mapping.start + mapping.offset;
+        let symbols = self
+            .binaries
+            .entry(mapping.path.clone())
+            .or_insert_with(|| {
+                // Through the process's root so that binaries in containers are found.
+                let path = format!("/proc/{tgid}/root{}", mapping.path);
+                ElfSymbols::load(Path::new(&path)).ok()
+            });
+        match symbols.as_ref().and_then(|symbols| symbols.lookup(offset)) {
+            Some(name) => name.to_string(),
+            None => {
+                let file = mapping.path.rsplit('/').next().unwrap_or_default();
+                format!("[{file}]")
+            }
+        }
+    }
+}
+
+/// Off-CPU time of one folded stack.
+#[derive(Debug, Clone)]
+pub struct OffCpuStack {
+    pub tgid: u32,
+    pub comm: String,
+    /// Waiting for a CPU rather than blocked; the stack ends with a `[runqueue]` frame.
+    pub runqueue: bool,
+    pub stack: String,
+    pub us: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OffCpuTotal {
+    pub tgid: u32,
+    pub comm: String,
+    pub blocked_us: u64,
+    pub runqueue_us: u64,
+}
+
+/// Off-CPU time per process, longest first.
+pub fn offcpu_rank(stacks: &[OffCpuStack]) -> Vec<OffCpuTotal> {
+    let mut totals: HashMap<u32, OffCpuTotal> = HashMap::new();
+    for stack in stacks {
+        let total = totals.entry(stack.tgid).or_insert_with(|| OffCpuTotal {
+            tgid: stack.tgid,
+            comm: stack.comm.clone(),
+            ..Default::default()
+        });
+        if stack.runqueue {
+            total.runqueue_us += stack.us;
+        } else {
+            total.blocked_us += stack.us;
+        }
+    }
+    let mut totals: Vec<_> = totals.into_values().collect();
+    totals.sort_by_key(|total| std::cmp::Reverse(total.blocked_us + total.runqueue_us));
+    totals
+}
+
+fn read_maps(tgid: u32) -> Vec<Mapping> {
+    let Ok(maps) = std::fs::read_to_string(format!("/proc/{tgid}/maps")) else {
+        return Vec::new();
+    };
+    maps.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(6, ' ');
+            let (start, end) = fields.next()?.split_once('-')?;
+            let offset = fields.nth(1)?;
+            let path = fields.nth(2)?.trim();
+            path.starts_with('/').then_some(Mapping {
+                start: u64::from_str_radix(start, 16).ok()?,
+                end: u64::from_str_radix(end, 16).ok()?,
+                offset: u64::from_str_radix(offset, 16).ok()?,
+                path: path.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold() {
+        let kernel = BTreeMap::from([
+            (0x1000, "entry_SYSCALL_64".to_string()),
+            (0x2000, "do_sys_openat2".to_string()),
+        ]);
+        let mut symbolizer = Symbolizer::new(kernel);
+        let mut comm = [0u8; 16];
+        comm[..4].copy_from_slice(b"curl");
+        assert_eq!(
+            symbolizer.fold(u32::MAX, &comm, &[0x10], &[0x2010, 0x1008]),
+            "curl;[unknown];entry_SYSCALL_64_[k];do_sys_openat2_[k]"
+        );
+
+        let symbols = ElfSymbols {
+            segments: vec![Segment {
+                offset: 0x1000,
+                vaddr: 0x401000,
+                filesz: 0x1000,
+            }],
+            symbols: vec![
+                Symbol {
+                    addr: 0x401000,
+                    size: 0x20,
+                    name: "main".to_string(),
+                },
+                Symbol {
+                    addr: 0x401100,
+                    size: 0,
+                    name: "_start".to_string(),
+                },
+            ],
+        };
+        assert_eq!(symbols.lookup(0x1010), Some("main"));
+        assert_eq!(symbols.lookup(0x1030), None);
+        assert_eq!(symbols.lookup(0x1200), Some("_start"));
+    }
+}
@@ -0,0 +1,42 @@
+use std::{cmp::Reverse, collections::HashMap, hash::Hash};
+
+use crate::agent::series::{TimeSeries, Window, now_ms};
+
+/// Cumulative counts per key, with rollups to answer windowed queries.
+#[derive(Debug)]
+pub struct Rank<K> {
+    pub counts: HashMap<K, u64>,
+    pub series: TimeSeries<K>,
+}
+
+impl<K: Hash + Eq + Clone> Default for Rank<K> {
+    fn default() -> Self {
+        Rank {
+            counts: HashMap::new(),
+            series: TimeSeries::default(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone> Rank<K> {
+    pub fn record(&mut self, now: u64, key: K, amount: u64) {
+        self.series.record(now, &key, amount);
+        *self.counts.entry(key).or_insert(0) += amount;
+    }
+
+    /// Largest entries since startup, or within `window` when given.
+    pub fn top(&self, window: Option<Window>, num: Option<usize>) -> Vec<(K, u64)> {
+        match window {
+            Some(window) => top(self.series.totals(now_ms() / 1000, window), num),
+            None => top(self.counts.clone(), num),
+        }
+    }
+}
+
+/// The `num` (default 10) largest entries, largest first.
+pub fn top<K>(rank: HashMap<K, u64>, num: Option<usize>) -> Vec<(K, u64)> {
+    let mut sorted: Vec<_> = rank.into_iter().collect();
+    sorted.sort_by_key(|(_, count)| Reverse(*count));
+    sorted.truncate(num.unwrap_or(10));
+    sorted
+}
@@ -0,0 +1,242 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Bucket width in seconds and number of buckets kept, finest first.
+/// Covers 2 minutes at 1s, 15 minutes at 10s, 2 hours at 1m and a day at 10m.
+const RESOLUTIONS: [(u64, usize); 4] = [(1, 120), (10, 90), (60, 120), (600, 144)];
+/// Longest window the coarsest resolution can answer, in seconds.
+const MAX_WINDOW: u64 = RESOLUTIONS[3].0 * RESOLUTIONS[3].1 as u64;
+
+/// Counts per key rolled up into fixed-width time buckets at several resolutions.
+#[derive(Debug)]
+pub struct TimeSeries<K> {
+    rollups: Vec<Rollup<K>>,
+}
+
+#[derive(Debug)]
+struct Rollup<K> {
+    width: u64,
+    retention: usize,
+    /// (bucket start in unix seconds, key -> count), oldest first
+    buckets: VecDeque<(u64, HashMap<K, u64>)>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SeriesPoint {
+    /// Bucket start in unix seconds.
+    pub time: u64,
+    pub count: u64,
+}
+
+/// A look-back window such as `30s`, `5m`, `1h` or `1d`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Window(pub u64);
+
+impl<K: Hash + Eq + Clone> Default for TimeSeries<K> {
+    fn default() -> Self {
+        TimeSeries {
+            rollups: RESOLUTIONS
+                .iter()
+                .map(|&(width, retention)| Rollup {
+                    width,
+                    retention,
+                    buckets: VecDeque::new(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone> TimeSeries<K> {
+    pub fn record(&mut self, now: u64, key: &K, amount: u64) {
+        for rollup in &mut self.rollups {
+            rollup.record(now, key, amount);
+        }
+    }
+
+    /// Totals per key over the last `window` seconds.
+    pub fn totals(&self, now: u64, window: Window) -> HashMap<K, u64> {
+        let rollup = self.rollup_for(window);
+        let mut totals = HashMap::new();
+        for (_, counts) in rollup.buckets_in(now, window) {
+            for (key, count) in counts {
+                *totals.entry(key.clone()).or_insert(0) += count;
+            }
+        }
+        totals
+    }
+
+    /// One point per bucket over the last `window` seconds, zero-filled.
+    pub fn points(&self, key: &K, now: u64, window: Window) -> Vec<SeriesPoint> {
+        let rollup = self.rollup_for(window);
+        let counts: HashMap<u64, u64> = rollup
+            .buckets_in(now, window)
+            .map(|(start, counts)| (*start, counts.get(key).copied().unwrap_or(0)))
+            .collect();
+        let last = rollup.bucket_start(now);
+        let first = rollup.bucket_start(now.saturating_sub(window.0.saturating_sub(1)));
+        (first..=last)
+            .step_by(rollup.width as usize)
+            .map(|time| SeriesPoint {
+                time,
+                count: counts.get(&time).copied().unwrap_or(0),
+            })
+            .collect()
+    }
+
+    /// The finest resolution that still covers `window`.
+    fn rollup_for(&self, window: Window) -> &Rollup<K> {
+        self.rollups
+            .iter()
+            .find(|rollup| rollup.width * rollup.retention as u64 >= window.0)
+            .unwrap_or_else(|| self.rollups.last().unwrap())
+    }
+}
+
+impl<K: Hash + Eq + Clone> Rollup<K> {
+    fn bucket_start(&self, time: u64) -> u64 {
+        time - time % self.width
+    }
+
+    fn record(&mut self, now: u64, key: &K, amount: u64) {
+        let start = self.bucket_start(now);
+        if self.buckets.back().is_none_or(|(last, _)| *last < start) {
+            self.buckets.push_back((start, HashMap::new()));
+        }
+        let oldest = start.saturating_sub(self.width * (self.retention as u64 - 1));
+        while self
+            .buckets
+            .front()
+            .is_some_and(|(first, _)| *first < oldest)
+        {
+            self.buckets.pop_front();
+        }
+        // Late events land in the newest bucket that is not after them.
+        if let Some((_, counts)) = self
+            .buckets
+            .iter_mut()
+            .rev()
+            .find(|(bucket, _)| *bucket <= start)
+        {
+            *counts.entry(key.clone()).or_insert(0) += amount;
+        }
+    }
+
+    fn buckets_in(
+        &self,
+        now: u64,
+        window: Window,
+    ) -> impl Iterator<Item = &(u64, HashMap<K, u64>)> {
+        let since = self.bucket_start(now.saturating_sub(window.0.saturating_sub(1)));
+        self.buckets
+            .iter()
+            .filter(move |(start, _)| *start >= since)
+    }
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Window(600)
+    }
+}
+
+impl FromStr for Window {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (value, unit) = s.split_at(split);
+        let value: u64 = value.parse().map_err(|_| format!("invalid window: {s}"))?;
+        let scale = match unit {
+            "" | "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            "d" => 86400,
+            _ => return Err(format!("invalid window unit: {unit}")),
+        };
+        let seconds = value
+            .checked_mul(scale)
+            .filter(|&seconds| seconds > 0)
+            .ok_or_else(|| format!("invalid window: {s}"))?;
+        if seconds > MAX_WINDOW {
+            return Err(format!("window {s} is longer than the {MAX_WINDOW}s kept"));
+        }
+        Ok(Window(seconds))
+    }
+}
+
+impl<'de> Deserialize<'de> for Window {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Wall clock time in milliseconds, which series buckets and event timestamps are based on.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_window() {
+        assert_eq!("30s".parse(), Ok(Window(30)));
+        assert_eq!("5m".parse(), Ok(Window(300)));
+        assert_eq!("2h".parse(), Ok(Window(7200)));
+        assert_eq!("45".parse(), Ok(Window(45)));
+        assert!("0m".parse::<Window>().is_err());
+        assert!("5w".parse::<Window>().is_err());
+        assert!("m".parse::<Window>().is_err());
+        assert_eq!("1d".parse(), Ok(Window(86400)));
+        assert!("25h".parse::<Window>().is_err());
+        assert!("99999999999d".parse::<Window>().is_err());
+        assert!("99999999999999999999".parse::<Window>().is_err());
+    }
+
+    #[test]
+    fn test_totals_within_window() {
+        let mut series = TimeSeries::default();
+        series.record(1000, &"a", 1);
+        series.record(1050, &"a", 2);
+        series.record(1059, &"b", 4);
+
+        let totals = series.totals(1059, Window(10));
+        assert_eq!(totals.get("a"), Some(&2));
+        assert_eq!(totals.get("b"), Some(&4));
+        let totals = series.totals(1059, Window(60));
+        assert_eq!(totals.get("a"), Some(&3));
+    }
+
+    #[test]
+    fn test_old_buckets_expire() {
+        let mut series = TimeSeries::default();
+        series.record(0, &"a", 1);
+        series.record(200, &"a", 1);
+
+        assert_eq!(series.totals(200, Window(120)).get("a"), Some(&1));
+        assert_eq!(series.totals(200, Window(600)).get("a"), Some(&2));
+    }
+
+    #[test]
+    fn test_points_are_zero_filled() {
+        let mut series = TimeSeries::default();
+        series.record(100, &"a", 3);
+        series.record(102, &"a", 1);
+
+        let points = series.points(&"a", 102, Window(4));
+        let counts: Vec<_> = points.iter().map(|p| (p.time, p.count)).collect();
+        assert_eq!(counts, vec![(99, 0), (100, 3), (101, 0), (102, 1)]);
+    }
+}
@@ -5,11 +5,24 @@ use tokio::sync::RwLock;
 pub type Server = Serve<tokio::net::TcpListener, axum::Router, axum::Router>;
 
 use crate::agent::state::{
-    TuiState, get_execve_logs, get_execve_rank, get_exit_logs, get_exit_rank, get_net_logs,
-    get_net_rank, get_openat_logs, get_openat_rank, get_read_logs, get_read_rank,
+    TuiState, get_bio_hist, get_bio_rank, get_bpf_logs, get_bpf_rank, get_creds_logs, get_dns_logs,
+    get_dns_rank, get_execmem_logs, get_execve_logs, get_execve_rank, get_exit_logs, get_exit_rank,
+    get_fileops_logs, get_fileops_rank, get_listeners, get_memory_pressure, get_modules_logs,
+    get_namespaces_logs, get_namespaces_rank, get_net_health, get_net_logs, get_net_rank,
+    get_offcpu_flamegraph, get_offcpu_folded, get_offcpu_rank, get_oom_logs, get_openat_logs,
+    get_openat_rank, get_perms_logs, get_perms_rank, get_probe_hist, get_probe_logs,
+    get_probe_rank, get_process, get_process_syscalls_rank, get_process_tree, get_processes,
+    get_profile_flamegraph, get_profile_folded, get_ptrace_logs, get_ptrace_rank, get_read_hist,
+    get_read_logs, get_read_rank, get_reset_rank, get_retransmit_rank, get_series, get_setid_logs,
+    get_shell_logs, get_signal_logs, get_signal_rank, get_signal_target_rank, get_socket_logs,
+    get_syscalls_rank, get_tls_logs, get_tracepoint_logs, get_tracepoint_rank,
 };
 
-pub async fn web_server(shared_state: Arc<RwLock<TuiState>>, port: u16) -> anyhow::Result<Server> {
+pub async fn web_server(
+    shared_state: Arc<RwLock<TuiState>>,
+    address: std::net::IpAddr,
+    port: u16,
+) -> anyhow::Result<Server> {
     let app = axum::Router::new()
         .route("/state", get(get_state))
         .route("/logs/execve", get(get_execve_logs))
@@ -17,13 +30,60 @@ pub async fn web_server(shared_state: Arc<RwLock<TuiState>>, port: u16) -> anyho
         .route("/logs/read", get(get_read_logs))
         .route("/logs/openat", get(get_openat_logs))
         .route("/logs/net", get(get_net_logs))
+        .route("/logs/signal", get(get_signal_logs))
+        .route("/logs/fileops", get(get_fileops_logs))
+        .route("/logs/perms", get(get_perms_logs))
+        .route("/logs/perms/setid", get(get_setid_logs))
+        .route("/logs/creds", get(get_creds_logs))
+        .route("/logs/modules", get(get_modules_logs))
+        .route("/logs/bpf", get(get_bpf_logs))
+        .route("/logs/namespaces", get(get_namespaces_logs))
+        .route("/logs/ptrace", get(get_ptrace_logs))
+        .route("/logs/execmem", get(get_execmem_logs))
+        .route("/logs/socket", get(get_socket_logs))
+        .route("/logs/dns", get(get_dns_logs))
+        .route("/logs/tls", get(get_tls_logs))
+        .route("/logs/shell", get(get_shell_logs))
+        .route("/logs/probes", get(get_probe_logs))
+        .route("/logs/tracepoints", get(get_tracepoint_logs))
+        .route("/logs/oom", get(get_oom_logs))
         .route("/rank/execve", get(get_execve_rank))
         .route("/rank/exit", get(get_exit_rank))
         .route("/rank/read", get(get_read_rank))
         .route("/rank/openat", get(get_openat_rank))
         .route("/rank/net", get(get_net_rank))
+        .route("/rank/signal", get(get_signal_rank))
+        .route("/rank/signal/target", get(get_signal_target_rank))
+        .route("/rank/fileops", get(get_fileops_rank))
+        .route("/rank/perms", get(get_perms_rank))
+        .route("/rank/bpf", get(get_bpf_rank))
+        .route("/rank/namespaces", get(get_namespaces_rank))
+        .route("/rank/ptrace", get(get_ptrace_rank))
+        .route("/rank/dns", get(get_dns_rank))
+        .route("/rank/retransmits", get(get_retransmit_rank))
+        .route("/rank/resets", get(get_reset_rank))
+        .route("/rank/probes", get(get_probe_rank))
+        .route("/rank/tracepoints", get(get_tracepoint_rank))
+        .route("/rank/syscalls", get(get_syscalls_rank))
+        .route("/rank/syscalls/{tgid}", get(get_process_syscalls_rank))
+        .route("/profile/folded", get(get_profile_folded))
+        .route("/profile/flamegraph.svg", get(get_profile_flamegraph))
+        .route("/offcpu/folded", get(get_offcpu_folded))
+        .route("/offcpu/flamegraph.svg", get(get_offcpu_flamegraph))
+        .route("/rank/offcpu", get(get_offcpu_rank))
+        .route("/hist/read/{pid}", get(get_read_hist))
+        .route("/hist/probes/{probe}", get(get_probe_hist))
+        .route("/rank/bio", get(get_bio_rank))
+        .route("/hist/bio", get(get_bio_hist))
+        .route("/series/{item}", get(get_series))
+        .route("/processes", get(get_processes))
+        .route("/processes/{tgid}", get(get_process))
+        .route("/processes/{tgid}/tree", get(get_process_tree))
+        .route("/net/listeners", get(get_listeners))
+        .route("/net/health", get(get_net_health))
+        .route("/memory/pressure", get(get_memory_pressure))
         .with_state(shared_state);
-    let addr = std::net::SocketAddr::from(([0, 0, 0, 0], port));
+    let addr = std::net::SocketAddr::new(address, port);
     let listener = tokio::net::TcpListener::bind(addr).await?;
     Ok(axum::serve(listener, app))
 }
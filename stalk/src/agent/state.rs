@@ -1,49 +1,171 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
 
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
+    http::header,
     response::IntoResponse,
 };
 use serde::Deserialize;
+use stalk_common::{ProcessEventKind, SignalEventKind, SyscallErrorKey, SyscallKey, SyscallStats};
 use tokio::sync::{RwLock, mpsc};
 
-use crate::event::{Event, ExecveEvent, ExitEvent, OpenatEvent, ReadEvent, XdpEvent};
+use crate::{
+    agent::{
+        bio::{BioGroup, BioSort, BioTable},
+        dns::DnsCache,
+        flamegraph,
+        histogram::{Histogram, HistogramStat},
+        listeners::{Listener, ListenerTable, udp_inodes},
+        oom::{Pressure, pressure_rank},
+        process::{ProcessInfo, ProcessTable},
+        profile::{OffCpuStack, offcpu_rank},
+        rank::{Rank, top},
+        series::{TimeSeries, Window, now_ms},
+        syscalls::{SyscallSort, SyscallTable},
+        tcp::{TcpGroup, TcpHealthTable, TcpSort},
+    },
+    event::{
+        BioEvent, BpfEvent, CredsEvent, DnsEvent, Event, ExecMemEvent, ExecveEvent, ExitEvent,
+        FileOpEvent, ModuleEvent, NamespaceEvent, OomEvent, OpenatEvent, PermEvent, ProcessEvent,
+        PtraceEvent, ReadEvent, ShellCommandEvent, SignalEvent, SocketEvent, TcpEvent,
+        TlsDataEvent, TracepointEvent, UprobeEvent, XdpEvent, signal_name,
+    },
+};
+
+/// TLS events kept, the oldest being dropped first. Each holds up to `TLS_MAX_CAPTURE` bytes
+/// of plaintext, and busy clients make many.
+const TLS_LOGS_MAX: usize = 10_000;
+
 #[derive(Debug)]
 pub enum StalkEvent {
+    /// Processes found in `/proc` once the process tracepoints are attached.
+    ProcessSeed(Vec<ProcessInfo>),
+    /// Sockets found in `/proc/net` once the socket tracepoints are attached.
+    ListenerSeed(Vec<Listener>),
     Execve(ExecveEvent),
     Exit(ExitEvent),
     Read(ReadEvent),
     Openat(OpenatEvent),
     Xdp(XdpEvent),
+    Process(ProcessEvent),
+    Signal(SignalEvent),
+    FileOps(FileOpEvent),
+    Perms(PermEvent),
+    Creds(CredsEvent),
+    Module(ModuleEvent),
+    Bpf(BpfEvent),
+    Namespace(NamespaceEvent),
+    Ptrace(PtraceEvent),
+    ExecMem(ExecMemEvent),
+    Socket(SocketEvent),
+    Dns(DnsEvent),
+    Tls(TlsDataEvent),
+    Shell(ShellCommandEvent),
+    Uprobe(UprobeEvent),
+    Tracepoint(TracepointEvent),
+    /// Syscall counts since the previous read of the in-kernel maps.
+    Syscalls(Vec<(SyscallKey, SyscallStats)>, Vec<(SyscallErrorKey, u64)>),
+    /// Folded stacks with their sample counts since the profiler started.
+    Profile(Vec<(String, u64)>),
+    /// Off-CPU time per folded stack since tracing started.
+    OffCpu(Vec<OffCpuStack>),
+    Bio(BioEvent),
+    Tcp(TcpEvent),
+    Oom(OomEvent),
+    /// Memory pressure of every cgroup, replacing the previous sample.
+    MemoryPressure(Vec<(String, Pressure)>),
 }
 
 pub struct TuiState {
     /// Path -> count
-    pub execve_rank: HashMap<String, usize>,
+    pub execve_rank: Rank<String>,
     pub execve_logs: Vec<String>,
-    /// Error code -> count
-    pub exit_rank: HashMap<u64, usize>,
+    /// Exit status (`exit(1)`, `SIGSEGV (core dumped)`, ...) -> count
+    pub exit_rank: Rank<String>,
     pub exit_logs: Vec<String>,
-    /// Pid -> duration in us
-    pub read_rank: HashMap<u32, u64>,
+    /// Pid -> read latency histogram in us
+    pub read_rank: HashMap<u32, Histogram>,
     pub read_logs: Vec<String>,
+    /// Pid -> summed read duration in us per bucket
+    pub read_series: TimeSeries<u32>,
     /// Path -> count
-    pub openat_rank: HashMap<String, usize>,
+    pub openat_rank: Rank<String>,
     pub openat_logs: Vec<String>,
     /// IP -> count
-    pub net_rank: HashMap<[u8; 4], usize>,
+    pub net_rank: Rank<[u8; 4]>,
     pub net_logs: Vec<String>,
+    pub processes: ProcessTable,
+    /// Signal name -> count
+    pub signal_rank: Rank<String>,
+    /// Target process `comm(tgid)` -> count
+    pub signal_target_rank: Rank<String>,
+    pub signal_logs: Vec<String>,
+    /// Path -> count
+    pub fileops_rank: Rank<String>,
+    pub fileops_logs: Vec<String>,
+    /// Changed path -> count
+    pub perms_rank: Rank<String>,
+    pub perms_logs: Vec<String>,
+    /// chmod calls that added setuid or setgid
+    pub setid_logs: Vec<String>,
+    pub creds_logs: Vec<String>,
+    pub modules_logs: Vec<String>,
+    /// Loader `comm` -> successful `BPF_PROG_LOAD` count
+    pub bpf_rank: Rank<String>,
+    pub bpf_logs: Vec<String>,
+    /// Syscall (`mount`, `setns`, ...) -> count
+    pub namespaces_rank: Rank<String>,
+    pub namespaces_logs: Vec<String>,
+    /// `tracer(tgid) -> tracee(pid)` -> count
+    pub ptrace_rank: Rank<String>,
+    pub ptrace_logs: Vec<String>,
+    pub execmem_logs: Vec<String>,
+    pub listeners: ListenerTable,
+    pub socket_logs: Vec<String>,
+    /// Queried name -> count
+    pub dns_rank: Rank<String>,
+    pub dns_logs: Vec<String>,
+    /// Address -> name it was resolved from
+    pub dns_cache: DnsCache,
+    /// The latest [`TLS_LOGS_MAX`] events.
+    pub tls_logs: VecDeque<TlsDataEvent>,
+    pub shell_logs: Vec<String>,
+    /// Probe label -> call count
+    pub probe_rank: Rank<String>,
+    /// Probe label -> call latency histogram in us, for probes with `ret`
+    pub probe_latency: HashMap<String, Histogram>,
+    pub probe_logs: Vec<String>,
+    /// `category:name` -> hit count
+    pub tracepoint_rank: Rank<String>,
+    pub tracepoint_logs: Vec<String>,
+    pub syscalls: SyscallTable,
+    pub profile: Vec<(String, u64)>,
+    pub offcpu: Vec<OffCpuStack>,
+    pub bio: BioTable,
+    pub tcp_health: TcpHealthTable,
+    /// Remote address -> retransmitted segments
+    pub retransmit_rank: Rank<String>,
+    /// Remote address -> resets sent and received
+    pub reset_rank: Rank<String>,
+    pub oom_logs: Vec<String>,
+    /// Cgroup path -> latest memory pressure, `/` for the whole system
+    pub memory_pressure: HashMap<String, Pressure>,
     pub start_time: tokio::time::Instant,
 }
 
 fn update_state(state: &mut TuiState, event: StalkEvent) {
+    let now = now_ms() / 1000;
     match event {
         StalkEvent::Execve(ev) => {
-            *state.execve_rank.entry(ev.filename.clone()).or_insert(0) += 1;
+            state.execve_rank.record(now, ev.filename.clone(), 1);
             state.execve_logs.push(ev.to_string());
         }
         StalkEvent::Exit(ev) => {
-            *state.exit_rank.entry(ev.exit_code).or_insert(0) += 1;
+            state.exit_rank.record(now, ev.status.to_string(), 1);
+            state.processes.set_exit_status(ev.pid, ev.status);
             state.exit_logs.push(ev.to_string());
         }
         StalkEvent::Read(ev) => {
@@ -51,17 +173,149 @@ fn update_state(state: &mut TuiState, event: StalkEvent) {
                 .end_time
                 .map(|t| t.duration_since(ev.start_time).as_micros())
                 .unwrap_or_default();
-            *state.read_rank.entry(ev.pid()).or_insert(0) += duration as u64;
+            state
+                .read_rank
+                .entry(ev.pid())
+                .or_default()
+                .record(duration as u64);
+            state.read_series.record(now, &ev.pid(), duration as u64);
             state.read_logs.push(ev.to_string());
         }
         StalkEvent::Openat(ev) => {
-            *state.openat_rank.entry(ev.filename.clone()).or_insert(0) += 1;
+            state.openat_rank.record(now, ev.filename.clone(), 1);
             state.openat_logs.push(ev.to_string());
         }
-        StalkEvent::Xdp(ev) => {
-            *state.net_rank.entry(ev.source_addr).or_insert(0) += 1;
+        StalkEvent::Xdp(mut ev) => {
+            ev.source_name = state.dns_cache.get(&ev.source_addr.into()).cloned();
+            state.net_rank.record(now, ev.source_addr, 1);
             state.net_logs.push(ev.to_string());
         }
+        StalkEvent::ProcessSeed(processes) => {
+            state.processes.seed(processes);
+        }
+        StalkEvent::ListenerSeed(listeners) => {
+            state.listeners.seed(listeners);
+        }
+        StalkEvent::Process(ev) => {
+            if ev.kind == ProcessEventKind::Exit {
+                state.syscalls.exit(ev.tgid);
+            }
+            state.processes.apply(&ev, now_ms());
+        }
+        StalkEvent::Signal(ev) => {
+            if ev.kind == SignalEventKind::Generate {
+                state.signal_rank.record(now, signal_name(ev.signal), 1);
+                let target = format!("{}({})", ev.target_comm, ev.target_tgid);
+                state.signal_target_rank.record(now, target, 1);
+            }
+            state.signal_logs.push(ev.to_string());
+        }
+        StalkEvent::FileOps(ev) => {
+            state.fileops_rank.record(now, ev.path.clone(), 1);
+            state.fileops_logs.push(ev.to_string());
+        }
+        StalkEvent::Perms(ev) => {
+            if ev.ret == 0 {
+                state.perms_rank.record(now, ev.path.clone(), 1);
+            }
+            if ev.setid_added() {
+                state.setid_logs.push(ev.to_string());
+            }
+            state.perms_logs.push(ev.to_string());
+        }
+        StalkEvent::Creds(ev) => {
+            state.creds_logs.push(ev.to_string());
+        }
+        StalkEvent::Module(ev) => {
+            state.modules_logs.push(ev.to_string());
+        }
+        StalkEvent::Bpf(ev) => {
+            if ev.cmd == "BPF_PROG_LOAD" && ev.ret >= 0 {
+                state.bpf_rank.record(now, ev.comm.clone(), 1);
+            }
+            state.bpf_logs.push(ev.to_string());
+        }
+        StalkEvent::Namespace(ev) => {
+            state.namespaces_rank.record(now, ev.op.to_string(), 1);
+            state.namespaces_logs.push(ev.to_string());
+        }
+        StalkEvent::Ptrace(ev) => {
+            state.ptrace_rank.record(now, ev.pair(), 1);
+            state.ptrace_logs.push(ev.to_string());
+        }
+        StalkEvent::ExecMem(ev) => {
+            state.execmem_logs.push(ev.to_string());
+        }
+        StalkEvent::Socket(ev) => {
+            state.listeners.apply(&ev, now_ms());
+            state.socket_logs.push(ev.to_string());
+        }
+        StalkEvent::Dns(ev) => {
+            if let Some(query) = ev.query() {
+                state.dns_rank.record(now, query.to_string(), 1);
+            }
+            state.dns_cache.apply(&ev);
+            state.dns_logs.push(ev.to_string());
+        }
+        StalkEvent::Tls(ev) => {
+            if state.tls_logs.len() == TLS_LOGS_MAX {
+                state.tls_logs.pop_front();
+            }
+            state.tls_logs.push_back(ev);
+        }
+        StalkEvent::Shell(ev) => {
+            state.shell_logs.push(ev.to_string());
+        }
+        StalkEvent::Uprobe(ev) => {
+            // Probes with `ret` report each call at entry and again at return.
+            match ev.latency_us {
+                Some(latency) => state
+                    .probe_latency
+                    .entry(ev.probe.clone())
+                    .or_default()
+                    .record(latency),
+                None => state.probe_rank.record(now, ev.probe.clone(), 1),
+            }
+            state.probe_logs.push(ev.to_string());
+        }
+        StalkEvent::Tracepoint(ev) => {
+            state.tracepoint_rank.record(now, ev.tracepoint.clone(), 1);
+            state.tracepoint_logs.push(ev.to_string());
+        }
+        StalkEvent::Syscalls(stats, errors) => {
+            let processes = &state.processes;
+            state.syscalls.update(stats, errors, |tgid| {
+                processes.get(tgid).is_some_and(|p| p.end_time.is_some())
+            });
+        }
+        StalkEvent::Profile(mut folded) => {
+            folded.sort();
+            state.profile = folded;
+        }
+        StalkEvent::OffCpu(mut stacks) => {
+            stacks.sort_by(|a, b| a.stack.cmp(&b.stack));
+            state.offcpu = stacks;
+        }
+        StalkEvent::Bio(ev) => {
+            state.bio.record(&ev);
+        }
+        StalkEvent::Tcp(ev) => {
+            let remote = ev.remote.ip().to_string();
+            match ev.op {
+                "retransmit" => state.retransmit_rank.record(now, remote, 1),
+                "send_reset" | "receive_reset" => state.reset_rank.record(now, remote, 1),
+                _ => {}
+            }
+            state.tcp_health.record(&ev);
+        }
+        StalkEvent::Oom(mut ev) => {
+            let cgroup = ev.cgroup.as_deref().unwrap_or("/");
+            ev.pressure = state.memory_pressure.get(cgroup).cloned();
+            state.oom_logs.push(ev.to_string());
+        }
+        StalkEvent::MemoryPressure(samples) => {
+            state.memory_pressure = samples.into_iter().collect();
+        }
     }
 }
 
@@ -79,16 +333,56 @@ pub fn run_agent(mut rx: mpsc::Receiver<StalkEvent>, shared_state: Arc<RwLock<Tu
 impl Default for TuiState {
     fn default() -> Self {
         TuiState {
-            execve_rank: HashMap::new(),
+            execve_rank: Rank::default(),
             execve_logs: Vec::new(),
-            exit_rank: HashMap::new(),
+            exit_rank: Rank::default(),
             exit_logs: Vec::new(),
             read_rank: HashMap::new(),
             read_logs: Vec::new(),
-            openat_rank: HashMap::new(),
+            read_series: TimeSeries::default(),
+            openat_rank: Rank::default(),
             openat_logs: Vec::new(),
-            net_rank: HashMap::new(),
+            net_rank: Rank::default(),
             net_logs: Vec::new(),
+            processes: ProcessTable::default(),
+            signal_rank: Rank::default(),
+            signal_target_rank: Rank::default(),
+            signal_logs: Vec::new(),
+            fileops_rank: Rank::default(),
+            fileops_logs: Vec::new(),
+            perms_rank: Rank::default(),
+            perms_logs: Vec::new(),
+            setid_logs: Vec::new(),
+            creds_logs: Vec::new(),
+            modules_logs: Vec::new(),
+            bpf_rank: Rank::default(),
+            bpf_logs: Vec::new(),
+            namespaces_rank: Rank::default(),
+            namespaces_logs: Vec::new(),
+            ptrace_rank: Rank::default(),
+            ptrace_logs: Vec::new(),
+            execmem_logs: Vec::new(),
+            listeners: ListenerTable::default(),
+            socket_logs: Vec::new(),
+            dns_rank: Rank::default(),
+            dns_logs: Vec::new(),
+            dns_cache: DnsCache::default(),
+            tls_logs: VecDeque::new(),
+            shell_logs: Vec::new(),
+            probe_rank: Rank::default(),
+            probe_latency: HashMap::new(),
+            probe_logs: Vec::new(),
+            tracepoint_rank: Rank::default(),
+            tracepoint_logs: Vec::new(),
+            syscalls: SyscallTable::default(),
+            profile: Vec::new(),
+            offcpu: Vec::new(),
+            bio: BioTable::default(),
+            tcp_health: TcpHealthTable::default(),
+            retransmit_rank: Rank::default(),
+            reset_rank: Rank::default(),
+            oom_logs: Vec::new(),
+            memory_pressure: HashMap::new(),
             start_time: tokio::time::Instant::now(),
         }
     }
@@ -97,6 +391,14 @@ impl Default for TuiState {
 #[derive(Debug, Deserialize)]
 pub struct QueryParam {
     pub num: Option<usize>,
+    pub by: Option<HistogramStat>,
+    pub window: Option<Window>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SeriesParam {
+    pub key: String,
+    pub window: Option<Window>,
 }
 
 pub async fn get_execve_logs(
@@ -174,77 +476,797 @@ pub async fn get_net_logs(
     Ok(axum::Json(logs))
 }
 
+pub async fn get_signal_logs(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let logs = shared_state
+        .read()
+        .await
+        .signal_logs
+        .clone()
+        .into_iter()
+        .take(param.num.unwrap_or(100))
+        .collect::<Vec<_>>();
+    Ok(axum::Json(logs))
+}
+
+pub async fn get_fileops_logs(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let logs = shared_state
+        .read()
+        .await
+        .fileops_logs
+        .clone()
+        .into_iter()
+        .take(param.num.unwrap_or(100))
+        .collect::<Vec<_>>();
+    Ok(axum::Json(logs))
+}
+
+pub async fn get_perms_logs(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let logs = shared_state
+        .read()
+        .await
+        .perms_logs
+        .clone()
+        .into_iter()
+        .take(param.num.unwrap_or(100))
+        .collect::<Vec<_>>();
+    Ok(axum::Json(logs))
+}
+
+pub async fn get_setid_logs(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let logs = shared_state
+        .read()
+        .await
+        .setid_logs
+        .clone()
+        .into_iter()
+        .take(param.num.unwrap_or(100))
+        .collect::<Vec<_>>();
+    Ok(axum::Json(logs))
+}
+
+pub async fn get_creds_logs(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let logs = shared_state
+        .read()
+        .await
+        .creds_logs
+        .clone()
+        .into_iter()
+        .take(param.num.unwrap_or(100))
+        .collect::<Vec<_>>();
+    Ok(axum::Json(logs))
+}
+
+pub async fn get_modules_logs(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let logs = shared_state
+        .read()
+        .await
+        .modules_logs
+        .clone()
+        .into_iter()
+        .take(param.num.unwrap_or(100))
+        .collect::<Vec<_>>();
+    Ok(axum::Json(logs))
+}
+
+pub async fn get_bpf_logs(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let logs = shared_state
+        .read()
+        .await
+        .bpf_logs
+        .clone()
+        .into_iter()
+        .take(param.num.unwrap_or(100))
+        .collect::<Vec<_>>();
+    Ok(axum::Json(logs))
+}
+
+pub async fn get_namespaces_logs(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let logs = shared_state
+        .read()
+        .await
+        .namespaces_logs
+        .clone()
+        .into_iter()
+        .take(param.num.unwrap_or(100))
+        .collect::<Vec<_>>();
+    Ok(axum::Json(logs))
+}
+
+pub async fn get_ptrace_logs(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let logs = shared_state
+        .read()
+        .await
+        .ptrace_logs
+        .clone()
+        .into_iter()
+        .take(param.num.unwrap_or(100))
+        .collect::<Vec<_>>();
+    Ok(axum::Json(logs))
+}
+
+pub async fn get_execmem_logs(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let logs = shared_state
+        .read()
+        .await
+        .execmem_logs
+        .clone()
+        .into_iter()
+        .take(param.num.unwrap_or(100))
+        .collect::<Vec<_>>();
+    Ok(axum::Json(logs))
+}
+
+pub async fn get_socket_logs(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let logs = shared_state
+        .read()
+        .await
+        .socket_logs
+        .clone()
+        .into_iter()
+        .take(param.num.unwrap_or(100))
+        .collect::<Vec<_>>();
+    Ok(axum::Json(logs))
+}
+
+pub async fn get_dns_logs(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let logs = shared_state
+        .read()
+        .await
+        .dns_logs
+        .clone()
+        .into_iter()
+        .take(param.num.unwrap_or(100))
+        .collect::<Vec<_>>();
+    Ok(axum::Json(logs))
+}
+
+pub async fn get_tls_logs(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let logs = shared_state
+        .read()
+        .await
+        .tls_logs
+        .iter()
+        .take(param.num.unwrap_or(100))
+        .cloned()
+        .collect::<Vec<_>>();
+    Ok(axum::Json(logs))
+}
+
+pub async fn get_shell_logs(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let logs = shared_state
+        .read()
+        .await
+        .shell_logs
+        .clone()
+        .into_iter()
+        .take(param.num.unwrap_or(100))
+        .collect::<Vec<_>>();
+    Ok(axum::Json(logs))
+}
+
+pub async fn get_probe_logs(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let logs = shared_state
+        .read()
+        .await
+        .probe_logs
+        .clone()
+        .into_iter()
+        .take(param.num.unwrap_or(100))
+        .collect::<Vec<_>>();
+    Ok(axum::Json(logs))
+}
+
+pub async fn get_tracepoint_logs(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let logs = shared_state
+        .read()
+        .await
+        .tracepoint_logs
+        .clone()
+        .into_iter()
+        .take(param.num.unwrap_or(100))
+        .collect::<Vec<_>>();
+    Ok(axum::Json(logs))
+}
+
 pub async fn get_execve_rank(
     State(shared_state): State<Arc<RwLock<TuiState>>>,
     Query(param): Query<QueryParam>,
 ) -> anyhow::Result<impl IntoResponse, String> {
-    let rank = shared_state.read().await.execve_rank.clone();
-    let mut sorted: Vec<_> = rank.into_iter().collect();
-    sorted.sort_by(|a, b| b.1.cmp(&a.1));
-    Ok(axum::Json(
-        sorted
-            .into_iter()
-            .take(param.num.unwrap_or(10))
-            .collect::<Vec<_>>(),
-    ))
+    let rank = shared_state
+        .read()
+        .await
+        .execve_rank
+        .top(param.window, param.num);
+    Ok(axum::Json(rank))
 }
 
 pub async fn get_exit_rank(
     State(shared_state): State<Arc<RwLock<TuiState>>>,
     Query(param): Query<QueryParam>,
 ) -> anyhow::Result<impl IntoResponse, String> {
-    let rank = shared_state.read().await.exit_rank.clone();
-    let mut sorted: Vec<_> = rank.into_iter().collect();
-    sorted.sort_by(|a, b| b.1.cmp(&a.1));
-    Ok(axum::Json(
-        sorted
-            .into_iter()
-            .take(param.num.unwrap_or(10))
-            .collect::<Vec<_>>(),
-    ))
+    let rank = shared_state
+        .read()
+        .await
+        .exit_rank
+        .top(param.window, param.num);
+    Ok(axum::Json(rank))
 }
 
 pub async fn get_read_rank(
     State(shared_state): State<Arc<RwLock<TuiState>>>,
     Query(param): Query<QueryParam>,
 ) -> anyhow::Result<impl IntoResponse, String> {
-    let rank = shared_state.read().await.read_rank.clone();
-    let mut sorted: Vec<_> = rank.into_iter().collect();
-    sorted.sort_by(|a, b| b.1.cmp(&a.1));
-    Ok(axum::Json(
-        sorted
-            .into_iter()
-            .take(param.num.unwrap_or(10))
-            .collect::<Vec<_>>(),
-    ))
+    let state = shared_state.read().await;
+    let by = param.by.unwrap_or_default();
+    let rank = match (param.window, by) {
+        (Some(window), HistogramStat::Sum) => state.read_series.totals(now_ms() / 1000, window),
+        (Some(_), _) => return Err("window is only supported with by=sum".to_string()),
+        (None, _) => state
+            .read_rank
+            .iter()
+            .map(|(pid, hist)| (*pid, hist.stat(by)))
+            .collect(),
+    };
+    Ok(axum::Json(top(rank, param.num)))
 }
 
 pub async fn get_openat_rank(
     State(shared_state): State<Arc<RwLock<TuiState>>>,
     Query(param): Query<QueryParam>,
 ) -> anyhow::Result<impl IntoResponse, String> {
-    let rank = shared_state.read().await.openat_rank.clone();
-    let mut sorted: Vec<_> = rank.into_iter().collect();
-    sorted.sort_by(|a, b| b.1.cmp(&a.1));
-    Ok(axum::Json(
-        sorted
-            .into_iter()
-            .take(param.num.unwrap_or(10))
-            .collect::<Vec<_>>(),
-    ))
+    let rank = shared_state
+        .read()
+        .await
+        .openat_rank
+        .top(param.window, param.num);
+    Ok(axum::Json(rank))
 }
 
 pub async fn get_net_rank(
     State(shared_state): State<Arc<RwLock<TuiState>>>,
     Query(param): Query<QueryParam>,
 ) -> anyhow::Result<impl IntoResponse, String> {
-    let rank = shared_state.read().await.net_rank.clone();
-    let mut sorted: Vec<_> = rank.into_iter().collect();
-    sorted.sort_by(|a, b| b.1.cmp(&a.1));
-    Ok(axum::Json(
-        sorted
-            .into_iter()
-            .take(param.num.unwrap_or(10))
-            .collect::<Vec<_>>(),
-    ))
+    let state = shared_state.read().await;
+    // `[addr, count, name]`, the name being null until a DNS answer for the address is seen.
+    let rank = state
+        .net_rank
+        .top(param.window, param.num)
+        .into_iter()
+        .map(|(addr, count)| (addr, count, state.dns_cache.get(&addr.into()).cloned()))
+        .collect::<Vec<_>>();
+    Ok(axum::Json(rank))
+}
+
+pub async fn get_signal_rank(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let rank = shared_state
+        .read()
+        .await
+        .signal_rank
+        .top(param.window, param.num);
+    Ok(axum::Json(rank))
+}
+
+pub async fn get_signal_target_rank(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let rank = shared_state
+        .read()
+        .await
+        .signal_target_rank
+        .top(param.window, param.num);
+    Ok(axum::Json(rank))
+}
+
+pub async fn get_fileops_rank(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let rank = shared_state
+        .read()
+        .await
+        .fileops_rank
+        .top(param.window, param.num);
+    Ok(axum::Json(rank))
+}
+
+pub async fn get_perms_rank(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let rank = shared_state
+        .read()
+        .await
+        .perms_rank
+        .top(param.window, param.num);
+    Ok(axum::Json(rank))
+}
+
+pub async fn get_bpf_rank(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let rank = shared_state
+        .read()
+        .await
+        .bpf_rank
+        .top(param.window, param.num);
+    Ok(axum::Json(rank))
+}
+
+pub async fn get_namespaces_rank(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let rank = shared_state
+        .read()
+        .await
+        .namespaces_rank
+        .top(param.window, param.num);
+    Ok(axum::Json(rank))
+}
+
+pub async fn get_ptrace_rank(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let rank = shared_state
+        .read()
+        .await
+        .ptrace_rank
+        .top(param.window, param.num);
+    Ok(axum::Json(rank))
+}
+
+pub async fn get_dns_rank(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let rank = shared_state
+        .read()
+        .await
+        .dns_rank
+        .top(param.window, param.num);
+    Ok(axum::Json(rank))
+}
+
+pub async fn get_retransmit_rank(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let rank = shared_state
+        .read()
+        .await
+        .retransmit_rank
+        .top(param.window, param.num);
+    Ok(axum::Json(rank))
+}
+
+pub async fn get_reset_rank(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let rank = shared_state
+        .read()
+        .await
+        .reset_rank
+        .top(param.window, param.num);
+    Ok(axum::Json(rank))
+}
+
+pub async fn get_probe_rank(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let rank = shared_state
+        .read()
+        .await
+        .probe_rank
+        .top(param.window, param.num);
+    Ok(axum::Json(rank))
+}
+
+pub async fn get_tracepoint_rank(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let rank = shared_state
+        .read()
+        .await
+        .tracepoint_rank
+        .top(param.window, param.num);
+    Ok(axum::Json(rank))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyscallParam {
+    pub num: Option<usize>,
+    pub by: Option<SyscallSort>,
+}
+
+pub async fn get_syscalls_rank(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<SyscallParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let mut rank = shared_state
+        .read()
+        .await
+        .syscalls
+        .summary(None, param.by.unwrap_or_default());
+    rank.truncate(param.num.unwrap_or(10));
+    Ok(axum::Json(rank))
+}
+
+pub async fn get_process_syscalls_rank(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Path(tgid): Path<u32>,
+    Query(param): Query<SyscallParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let mut rank = shared_state
+        .read()
+        .await
+        .syscalls
+        .summary(Some(tgid), param.by.unwrap_or_default());
+    rank.truncate(param.num.unwrap_or(10));
+    Ok(axum::Json(rank))
+}
+
+/// Folded stacks, one `frame;frame;frame count` line each.
+pub async fn get_profile_folded(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let folded: String = shared_state
+        .read()
+        .await
+        .profile
+        .iter()
+        .map(|(stack, count)| format!("{stack} {count}\n"))
+        .collect();
+    Ok(folded)
+}
+
+pub async fn get_profile_flamegraph(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let svg = flamegraph::render(
+        &shared_state.read().await.profile,
+        "CPU Flame Graph",
+        "samples",
+    );
+    Ok(([(header::CONTENT_TYPE, "image/svg+xml")], svg))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OffCpuParam {
+    pub num: Option<usize>,
+    pub tgid: Option<u32>,
+}
+
+/// Folded off-CPU stacks of every process or of `tgid`, weighted in microseconds.
+fn offcpu_folded(state: &TuiState, tgid: Option<u32>) -> Vec<(String, u64)> {
+    state
+        .offcpu
+        .iter()
+        .filter(|stack| tgid.is_none_or(|tgid| stack.tgid == tgid))
+        .map(|stack| (stack.stack.clone(), stack.us))
+        .collect()
+}
+
+pub async fn get_offcpu_folded(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<OffCpuParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let folded: String = offcpu_folded(&*shared_state.read().await, param.tgid)
+        .iter()
+        .map(|(stack, us)| format!("{stack} {us}\n"))
+        .collect();
+    Ok(folded)
+}
+
+pub async fn get_offcpu_flamegraph(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<OffCpuParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let folded = offcpu_folded(&*shared_state.read().await, param.tgid);
+    let svg = flamegraph::render(&folded, "Off-CPU Flame Graph", "us");
+    Ok(([(header::CONTENT_TYPE, "image/svg+xml")], svg))
+}
+
+pub async fn get_offcpu_rank(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<OffCpuParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let mut rank = offcpu_rank(&shared_state.read().await.offcpu);
+    rank.truncate(param.num.unwrap_or(10));
+    Ok(axum::Json(rank))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BioParam {
+    pub num: Option<usize>,
+    pub by: Option<BioSort>,
+    pub per: Option<BioGroup>,
+}
+
+pub async fn get_bio_rank(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<BioParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let mut rank = shared_state
+        .read()
+        .await
+        .bio
+        .rank(param.per.unwrap_or_default(), param.by.unwrap_or_default());
+    rank.truncate(param.num.unwrap_or(10));
+    Ok(axum::Json(rank))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BioHistParam {
+    pub tgid: Option<u32>,
+    pub device: Option<String>,
+}
+
+/// Device latency of all requests, or those of `tgid` or `device`.
+pub async fn get_bio_hist(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<BioHistParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let summary = shared_state
+        .read()
+        .await
+        .bio
+        .histogram(param.tgid, param.device.as_deref())
+        .map(Histogram::summary)
+        .ok_or("no block I/O samples".to_string())?;
+    Ok(axum::Json(summary))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NetHealthParam {
+    pub num: Option<usize>,
+    pub by: Option<TcpSort>,
+    pub per: Option<TcpGroup>,
+}
+
+pub async fn get_net_health(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<NetHealthParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let state = shared_state.read().await;
+    let mut rows = state.tcp_health.rank(
+        param.per.unwrap_or_default(),
+        param.by.unwrap_or_default(),
+        |addr| state.dns_cache.get(addr).cloned(),
+    );
+    rows.truncate(param.num.unwrap_or(10));
+    Ok(axum::Json(rows))
+}
+
+pub async fn get_oom_logs(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let logs = shared_state
+        .read()
+        .await
+        .oom_logs
+        .clone()
+        .into_iter()
+        .take(param.num.unwrap_or(100))
+        .collect::<Vec<_>>();
+    Ok(axum::Json(logs))
+}
+
+pub async fn get_memory_pressure(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let mut rows = pressure_rank(&shared_state.read().await.memory_pressure);
+    rows.truncate(param.num.unwrap_or(10));
+    Ok(axum::Json(rows))
+}
+
+pub async fn get_series(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Path(item): Path<String>,
+    Query(param): Query<SeriesParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let state = shared_state.read().await;
+    let now = now_ms() / 1000;
+    let window = param.window.unwrap_or_default();
+    let key = param.key.as_str();
+    let points = match item.as_str() {
+        "execve" => state
+            .execve_rank
+            .series
+            .points(&key.to_string(), now, window),
+        "exit" => state.exit_rank.series.points(&key.to_string(), now, window),
+        "read" => state.read_series.points(&parse_key(key)?, now, window),
+        "openat" => state
+            .openat_rank
+            .series
+            .points(&key.to_string(), now, window),
+        "net" => {
+            let addr: std::net::Ipv4Addr = parse_key(key)?;
+            state.net_rank.series.points(&addr.octets(), now, window)
+        }
+        "signal" => state
+            .signal_rank
+            .series
+            .points(&key.to_string(), now, window),
+        "fileops" => state
+            .fileops_rank
+            .series
+            .points(&key.to_string(), now, window),
+        "perms" => state
+            .perms_rank
+            .series
+            .points(&key.to_string(), now, window),
+        "bpf" => state.bpf_rank.series.points(&key.to_string(), now, window),
+        "namespaces" => state
+            .namespaces_rank
+            .series
+            .points(&key.to_string(), now, window),
+        "ptrace" => state
+            .ptrace_rank
+            .series
+            .points(&key.to_string(), now, window),
+        "dns" => state.dns_rank.series.points(&key.to_string(), now, window),
+        "tracepoints" => state
+            .tracepoint_rank
+            .series
+            .points(&key.to_string(), now, window),
+        "probes" => state
+            .probe_rank
+            .series
+            .points(&key.to_string(), now, window),
+        "retransmits" => state
+            .retransmit_rank
+            .series
+            .points(&key.to_string(), now, window),
+        "resets" => state
+            .reset_rank
+            .series
+            .points(&key.to_string(), now, window),
+        _ => return Err(format!("unknown item: {item}")),
+    };
+    Ok(axum::Json(points))
+}
+
+fn parse_key<T: std::str::FromStr>(key: &str) -> Result<T, String> {
+    key.parse().map_err(|_| format!("invalid key: {key}"))
+}
+
+pub async fn get_read_hist(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Path(pid): Path<u32>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let summary = shared_state
+        .read()
+        .await
+        .read_rank
+        .get(&pid)
+        .map(Histogram::summary)
+        .ok_or(format!("no read samples for pid {pid}"))?;
+    Ok(axum::Json(summary))
+}
+
+pub async fn get_probe_hist(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Path(probe): Path<String>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let summary = shared_state
+        .read()
+        .await
+        .probe_latency
+        .get(&probe)
+        .map(Histogram::summary)
+        .ok_or(format!("no latency samples for probe {probe}"))?;
+    Ok(axum::Json(summary))
+}
+
+pub async fn get_processes(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Query(param): Query<QueryParam>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let state = shared_state.read().await;
+    let processes = state
+        .processes
+        .list()
+        .into_iter()
+        .take(param.num.unwrap_or(usize::MAX))
+        .cloned()
+        .collect::<Vec<_>>();
+    Ok(axum::Json(processes))
+}
+
+pub async fn get_process(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Path(tgid): Path<u32>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let process = shared_state
+        .read()
+        .await
+        .processes
+        .get(tgid)
+        .cloned()
+        .ok_or(format!("unknown process {tgid}"))?;
+    Ok(axum::Json(process))
+}
+
+pub async fn get_process_tree(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+    Path(tgid): Path<u32>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let tree = shared_state
+        .read()
+        .await
+        .processes
+        .tree(tgid)
+        .ok_or(format!("unknown process {tgid}"))?;
+    Ok(axum::Json(tree))
+}
+
+pub async fn get_listeners(
+    State(shared_state): State<Arc<RwLock<TuiState>>>,
+) -> anyhow::Result<impl IntoResponse, String> {
+    let bound = tokio::task::spawn_blocking(udp_inodes)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut state = shared_state.write().await;
+    state.listeners.prune_udp(&bound);
+    let listeners: Vec<_> = state.listeners.list().into_iter().cloned().collect();
+    Ok(axum::Json(listeners))
 }
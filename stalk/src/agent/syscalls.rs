@@ -0,0 +1,1272 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use stalk_common::{SyscallErrorKey, SyscallKey, SyscallStats};
+
+/// Column `/rank/syscalls` is sorted by.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyscallSort {
+    #[default]
+    Count,
+    Errors,
+    Time,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyscallSummary {
+    pub syscall: String,
+    pub count: u64,
+    pub errors: u64,
+    pub total_us: u64,
+    pub avg_us: u64,
+    /// Errno name -> failed calls
+    pub errnos: BTreeMap<String, u64>,
+}
+
+/// Totals of exited processes are moved to tgid 0, which makes no syscalls, so that the table
+/// doesn't grow with every process that ever ran while the totals over all of them stay whole.
+const EXITED_TGID: u32 = 0;
+
+/// Syscall totals since tracing started, summed from what the kernel counted in between reads.
+#[derive(Debug, Default)]
+pub struct SyscallTable {
+    stats: HashMap<SyscallKey, SyscallStats>,
+    errors: HashMap<SyscallErrorKey, u64>,
+}
+
+impl SyscallTable {
+    /// Adds what the kernel counted since the last read. Counts of processes for which
+    /// `exited` holds, read after their exit was seen, go straight to [`EXITED_TGID`].
+    pub fn update(
+        &mut self,
+        stats: Vec<(SyscallKey, SyscallStats)>,
+        errors: Vec<(SyscallErrorKey, u64)>,
+        exited: impl Fn(u32) -> bool,
+    ) {
+        for (mut key, added) in stats {
+            if exited(key.tgid) {
+                key.tgid = EXITED_TGID;
+            }
+            self.add_stats(key, added);
+        }
+        for (mut key, added) in errors {
+            if exited(key.tgid) {
+                key.tgid = EXITED_TGID;
+            }
+            *self.errors.entry(key).or_default() += added;
+        }
+    }
+
+    /// Moves the totals of `tgid` to [`EXITED_TGID`].
+    pub fn exit(&mut self, tgid: u32) {
+        if tgid == EXITED_TGID {
+            return;
+        }
+        let keys: Vec<_> = self
+            .stats
+            .keys()
+            .filter(|k| k.tgid == tgid)
+            .copied()
+            .collect();
+        for key in keys {
+            if let Some(stats) = self.stats.remove(&key) {
+                self.add_stats(
+                    SyscallKey {
+                        tgid: EXITED_TGID,
+                        ..key
+                    },
+                    stats,
+                );
+            }
+        }
+        let keys: Vec<_> = self
+            .errors
+            .keys()
+            .filter(|k| k.tgid == tgid)
+            .copied()
+            .collect();
+        for key in keys {
+            if let Some(count) = self.errors.remove(&key) {
+                let key = SyscallErrorKey {
+                    tgid: EXITED_TGID,
+                    ..key
+                };
+                *self.errors.entry(key).or_default() += count;
+            }
+        }
+    }
+
+    fn add_stats(&mut self, key: SyscallKey, added: SyscallStats) {
+        let stats = self.stats.entry(key).or_default();
+        stats.count += added.count;
+        stats.errors += added.errors;
+        stats.total_ns += added.total_ns;
+    }
+
+    /// Per-syscall totals over every process, or over `tgid` only.
+    pub fn summary(&self, tgid: Option<u32>, sort: SyscallSort) -> Vec<SyscallSummary> {
+        let mut by_nr: BTreeMap<u32, SyscallSummary> = BTreeMap::new();
+        let wanted = |key_tgid: u32| tgid.is_none_or(|tgid| tgid == key_tgid);
+        for (key, stats) in self.stats.iter().filter(|(key, _)| wanted(key.tgid)) {
+            let summary = by_nr.entry(key.nr).or_insert_with(|| SyscallSummary {
+                syscall: syscall_name(key.nr),
+                count: 0,
+                errors: 0,
+                total_us: 0,
+                avg_us: 0,
+                errnos: BTreeMap::new(),
+            });
+            summary.count += stats.count;
+            summary.errors += stats.errors;
+            summary.total_us += stats.total_ns / 1000;
+        }
+        for (key, count) in self.errors.iter().filter(|(key, _)| wanted(key.tgid)) {
+            if let Some(summary) = by_nr.get_mut(&key.nr) {
+                *summary.errnos.entry(errno_name(key.errno)).or_insert(0) += count;
+            }
+        }
+        let mut summaries: Vec<_> = by_nr.into_values().collect();
+        for summary in &mut summaries {
+            summary.avg_us = summary.total_us / summary.count.max(1);
+        }
+        summaries.sort_by_key(|s| {
+            std::cmp::Reverse(match sort {
+                SyscallSort::Count => s.count,
+                SyscallSort::Errors => s.errors,
+                SyscallSort::Time => s.total_us,
+            })
+        });
+        summaries
+    }
+}
+
+pub fn syscall_name(nr: u32) -> String {
+    SYSCALL_NAMES
+        .get(nr as usize)
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| format!("syscall_{nr}"))
+}
+
+pub fn errno_name(errno: u32) -> String {
+    ERRNO_NAMES
+        .get(errno as usize)
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| format!("E{errno}"))
+}
+
+/// From `arch/x86/entry/syscalls/syscall_64.tbl`.
+#[cfg(target_arch = "x86_64")]
+const SYSCALL_NAMES: [&str; 451] = [
+    "read",
+    "write",
+    "open",
+    "close",
+    "stat",
+    "fstat",
+    "lstat",
+    "poll",
+    "lseek",
+    "mmap",
+    "mprotect",
+    "munmap",
+    "brk",
+    "rt_sigaction",
+    "rt_sigprocmask",
+    "rt_sigreturn",
+    "ioctl",
+    "pread64",
+    "pwrite64",
+    "readv",
+    "writev",
+    "access",
+    "pipe",
+    "select",
+    "sched_yield",
+    "mremap",
+    "msync",
+    "mincore",
+    "madvise",
+    "shmget",
+    "shmat",
+    "shmctl",
+    "dup",
+    "dup2",
+    "pause",
+    "nanosleep",
+    "getitimer",
+    "alarm",
+    "setitimer",
+    "getpid",
+    "sendfile",
+    "socket",
+    "connect",
+    "accept",
+    "sendto",
+    "recvfrom",
+    "sendmsg",
+    "recvmsg",
+    "shutdown",
+    "bind",
+    "listen",
+    "getsockname",
+    "getpeername",
+    "socketpair",
+    "setsockopt",
+    "getsockopt",
+    "clone",
+    "fork",
+    "vfork",
+    "execve",
+    "exit",
+    "wait4",
+    "kill",
+    "uname",
+    "semget",
+    "semop",
+    "semctl",
+    "shmdt",
+    "msgget",
+    "msgsnd",
+    "msgrcv",
+    "msgctl",
+    "fcntl",
+    "flock",
+    "fsync",
+    "fdatasync",
+    "truncate",
+    "ftruncate",
+    "getdents",
+    "getcwd",
+    "chdir",
+    "fchdir",
+    "rename",
+    "mkdir",
+    "rmdir",
+    "creat",
+    "link",
+    "unlink",
+    "symlink",
+    "readlink",
+    "chmod",
+    "fchmod",
+    "chown",
+    "fchown",
+    "lchown",
+    "umask",
+    "gettimeofday",
+    "getrlimit",
+    "getrusage",
+    "sysinfo",
+    "times",
+    "ptrace",
+    "getuid",
+    "syslog",
+    "getgid",
+    "setuid",
+    "setgid",
+    "geteuid",
+    "getegid",
+    "setpgid",
+    "getppid",
+    "getpgrp",
+    "setsid",
+    "setreuid",
+    "setregid",
+    "getgroups",
+    "setgroups",
+    "setresuid",
+    "getresuid",
+    "setresgid",
+    "getresgid",
+    "getpgid",
+    "setfsuid",
+    "setfsgid",
+    "getsid",
+    "capget",
+    "capset",
+    "rt_sigpending",
+    "rt_sigtimedwait",
+    "rt_sigqueueinfo",
+    "rt_sigsuspend",
+    "sigaltstack",
+    "utime",
+    "mknod",
+    "uselib",
+    "personality",
+    "ustat",
+    "statfs",
+    "fstatfs",
+    "sysfs",
+    "getpriority",
+    "setpriority",
+    "sched_setparam",
+    "sched_getparam",
+    "sched_setscheduler",
+    "sched_getscheduler",
+    "sched_get_priority_max",
+    "sched_get_priority_min",
+    "sched_rr_get_interval",
+    "mlock",
+    "munlock",
+    "mlockall",
+    "munlockall",
+    "vhangup",
+    "modify_ldt",
+    "pivot_root",
+    "_sysctl",
+    "prctl",
+    "arch_prctl",
+    "adjtimex",
+    "setrlimit",
+    "chroot",
+    "sync",
+    "acct",
+    "settimeofday",
+    "mount",
+    "umount2",
+    "swapon",
+    "swapoff",
+    "reboot",
+    "sethostname",
+    "setdomainname",
+    "iopl",
+    "ioperm",
+    "create_module",
+    "init_module",
+    "delete_module",
+    "get_kernel_syms",
+    "query_module",
+    "quotactl",
+    "nfsservctl",
+    "getpmsg",
+    "putpmsg",
+    "afs_syscall",
+    "tuxcall",
+    "security",
+    "gettid",
+    "readahead",
+    "setxattr",
+    "lsetxattr",
+    "fsetxattr",
+    "getxattr",
+    "lgetxattr",
+    "fgetxattr",
+    "listxattr",
+    "llistxattr",
+    "flistxattr",
+    "removexattr",
+    "lremovexattr",
+    "fremovexattr",
+    "tkill",
+    "time",
+    "futex",
+    "sched_setaffinity",
+    "sched_getaffinity",
+    "set_thread_area",
+    "io_setup",
+    "io_destroy",
+    "io_getevents",
+    "io_submit",
+    "io_cancel",
+    "get_thread_area",
+    "lookup_dcookie",
+    "epoll_create",
+    "epoll_ctl_old",
+    "epoll_wait_old",
+    "remap_file_pages",
+    "getdents64",
+    "set_tid_address",
+    "restart_syscall",
+    "semtimedop",
+    "fadvise64",
+    "timer_create",
+    "timer_settime",
+    "timer_gettime",
+    "timer_getoverrun",
+    "timer_delete",
+    "clock_settime",
+    "clock_gettime",
+    "clock_getres",
+    "clock_nanosleep",
+    "exit_group",
+    "epoll_wait",
+    "epoll_ctl",
+    "tgkill",
+    "utimes",
+    "vserver",
+    "mbind",
+    "set_mempolicy",
+    "get_mempolicy",
+    "mq_open",
+    "mq_unlink",
+    "mq_timedsend",
+    "mq_timedreceive",
+    "mq_notify",
+    "mq_getsetattr",
+    "kexec_load",
+    "waitid",
+    "add_key",
+    "request_key",
+    "keyctl",
+    "ioprio_set",
+    "ioprio_get",
+    "inotify_init",
+    "inotify_add_watch",
+    "inotify_rm_watch",
+    "migrate_pages",
+    "openat",
+    "mkdirat",
+    "mknodat",
+    "fchownat",
+    "futimesat",
+    "newfstatat",
+    "unlinkat",
+    "renameat",
+    "linkat",
+    "symlinkat",
+    "readlinkat",
+    "fchmodat",
+    "faccessat",
+    "pselect6",
+    "ppoll",
+    "unshare",
+    "set_robust_list",
+    "get_robust_list",
+    "splice",
+    "tee",
+    "sync_file_range",
+    "vmsplice",
+    "move_pages",
+    "utimensat",
+    "epoll_pwait",
+    "signalfd",
+    "timerfd_create",
+    "eventfd",
+    "fallocate",
+    "timerfd_settime",
+    "timerfd_gettime",
+    "accept4",
+    "signalfd4",
+    "eventfd2",
+    "epoll_create1",
+    "dup3",
+    "pipe2",
+    "inotify_init1",
+    "preadv",
+    "pwritev",
+    "rt_tgsigqueueinfo",
+    "perf_event_open",
+    "recvmmsg",
+    "fanotify_init",
+    "fanotify_mark",
+    "prlimit64",
+    "name_to_handle_at",
+    "open_by_handle_at",
+    "clock_adjtime",
+    "syncfs",
+    "sendmmsg",
+    "setns",
+    "getcpu",
+    "process_vm_readv",
+    "process_vm_writev",
+    "kcmp",
+    "finit_module",
+    "sched_setattr",
+    "sched_getattr",
+    "renameat2",
+    "seccomp",
+    "getrandom",
+    "memfd_create",
+    "kexec_file_load",
+    "bpf",
+    "execveat",
+    "userfaultfd",
+    "membarrier",
+    "mlock2",
+    "copy_file_range",
+    "preadv2",
+    "pwritev2",
+    "pkey_mprotect",
+    "pkey_alloc",
+    "pkey_free",
+    "statx",
+    "io_pgetevents",
+    "rseq",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "pidfd_send_signal",
+    "io_uring_setup",
+    "io_uring_enter",
+    "io_uring_register",
+    "open_tree",
+    "move_mount",
+    "fsopen",
+    "fsconfig",
+    "fsmount",
+    "fspick",
+    "pidfd_open",
+    "clone3",
+    "close_range",
+    "openat2",
+    "pidfd_getfd",
+    "faccessat2",
+    "process_madvise",
+    "epoll_pwait2",
+    "mount_setattr",
+    "quotactl_fd",
+    "landlock_create_ruleset",
+    "landlock_add_rule",
+    "landlock_restrict_self",
+    "memfd_secret",
+    "process_mrelease",
+    "futex_waitv",
+    "set_mempolicy_home_node",
+];
+
+/// From `include/uapi/asm-generic/unistd.h`, as used by arm64.
+#[cfg(target_arch = "aarch64")]
+const SYSCALL_NAMES: [&str; 451] = [
+    "io_setup",
+    "io_destroy",
+    "io_submit",
+    "io_cancel",
+    "io_getevents",
+    "setxattr",
+    "lsetxattr",
+    "fsetxattr",
+    "getxattr",
+    "lgetxattr",
+    "fgetxattr",
+    "listxattr",
+    "llistxattr",
+    "flistxattr",
+    "removexattr",
+    "lremovexattr",
+    "fremovexattr",
+    "getcwd",
+    "lookup_dcookie",
+    "eventfd2",
+    "epoll_create1",
+    "epoll_ctl",
+    "epoll_pwait",
+    "dup",
+    "dup3",
+    "fcntl",
+    "inotify_init1",
+    "inotify_add_watch",
+    "inotify_rm_watch",
+    "ioctl",
+    "ioprio_set",
+    "ioprio_get",
+    "flock",
+    "mknodat",
+    "mkdirat",
+    "unlinkat",
+    "symlinkat",
+    "linkat",
+    "renameat",
+    "umount2",
+    "mount",
+    "pivot_root",
+    "nfsservctl",
+    "statfs",
+    "fstatfs",
+    "truncate",
+    "ftruncate",
+    "fallocate",
+    "faccessat",
+    "chdir",
+    "fchdir",
+    "chroot",
+    "fchmod",
+    "fchmodat",
+    "fchownat",
+    "fchown",
+    "openat",
+    "close",
+    "vhangup",
+    "pipe2",
+    "quotactl",
+    "getdents64",
+    "lseek",
+    "read",
+    "write",
+    "readv",
+    "writev",
+    "pread64",
+    "pwrite64",
+    "preadv",
+    "pwritev",
+    "sendfile",
+    "pselect6",
+    "ppoll",
+    "signalfd4",
+    "vmsplice",
+    "splice",
+    "tee",
+    "readlinkat",
+    "newfstatat",
+    "fstat",
+    "sync",
+    "fsync",
+    "fdatasync",
+    "sync_file_range",
+    "timerfd_create",
+    "timerfd_settime",
+    "timerfd_gettime",
+    "utimensat",
+    "acct",
+    "capget",
+    "capset",
+    "personality",
+    "exit",
+    "exit_group",
+    "waitid",
+    "set_tid_address",
+    "unshare",
+    "futex",
+    "set_robust_list",
+    "get_robust_list",
+    "nanosleep",
+    "getitimer",
+    "setitimer",
+    "kexec_load",
+    "init_module",
+    "delete_module",
+    "timer_create",
+    "timer_gettime",
+    "timer_getoverrun",
+    "timer_settime",
+    "timer_delete",
+    "clock_settime",
+    "clock_gettime",
+    "clock_getres",
+    "clock_nanosleep",
+    "syslog",
+    "ptrace",
+    "sched_setparam",
+    "sched_setscheduler",
+    "sched_getscheduler",
+    "sched_getparam",
+    "sched_setaffinity",
+    "sched_getaffinity",
+    "sched_yield",
+    "sched_get_priority_max",
+    "sched_get_priority_min",
+    "sched_rr_get_interval",
+    "restart_syscall",
+    "kill",
+    "tkill",
+    "tgkill",
+    "sigaltstack",
+    "rt_sigsuspend",
+    "rt_sigaction",
+    "rt_sigprocmask",
+    "rt_sigpending",
+    "rt_sigtimedwait",
+    "rt_sigqueueinfo",
+    "rt_sigreturn",
+    "setpriority",
+    "getpriority",
+    "reboot",
+    "setregid",
+    "setgid",
+    "setreuid",
+    "setuid",
+    "setresuid",
+    "getresuid",
+    "setresgid",
+    "getresgid",
+    "setfsuid",
+    "setfsgid",
+    "times",
+    "setpgid",
+    "getpgid",
+    "getsid",
+    "setsid",
+    "getgroups",
+    "setgroups",
+    "uname",
+    "sethostname",
+    "setdomainname",
+    "getrlimit",
+    "setrlimit",
+    "getrusage",
+    "umask",
+    "prctl",
+    "getcpu",
+    "gettimeofday",
+    "settimeofday",
+    "adjtimex",
+    "getpid",
+    "getppid",
+    "getuid",
+    "geteuid",
+    "getgid",
+    "getegid",
+    "gettid",
+    "sysinfo",
+    "mq_open",
+    "mq_unlink",
+    "mq_timedsend",
+    "mq_timedreceive",
+    "mq_notify",
+    "mq_getsetattr",
+    "msgget",
+    "msgctl",
+    "msgrcv",
+    "msgsnd",
+    "semget",
+    "semctl",
+    "semtimedop",
+    "semop",
+    "shmget",
+    "shmctl",
+    "shmat",
+    "shmdt",
+    "socket",
+    "socketpair",
+    "bind",
+    "listen",
+    "accept",
+    "connect",
+    "getsockname",
+    "getpeername",
+    "sendto",
+    "recvfrom",
+    "setsockopt",
+    "getsockopt",
+    "shutdown",
+    "sendmsg",
+    "recvmsg",
+    "readahead",
+    "brk",
+    "munmap",
+    "mremap",
+    "add_key",
+    "request_key",
+    "keyctl",
+    "clone",
+    "execve",
+    "mmap",
+    "fadvise64",
+    "swapon",
+    "swapoff",
+    "mprotect",
+    "msync",
+    "mlock",
+    "munlock",
+    "mlockall",
+    "munlockall",
+    "mincore",
+    "madvise",
+    "remap_file_pages",
+    "mbind",
+    "get_mempolicy",
+    "set_mempolicy",
+    "migrate_pages",
+    "move_pages",
+    "rt_tgsigqueueinfo",
+    "perf_event_open",
+    "accept4",
+    "recvmmsg",
+    "arch_specific_syscall",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "wait4",
+    "prlimit64",
+    "fanotify_init",
+    "fanotify_mark",
+    "",
+    "",
+    "clock_adjtime",
+    "syncfs",
+    "setns",
+    "sendmmsg",
+    "process_vm_readv",
+    "process_vm_writev",
+    "kcmp",
+    "finit_module",
+    "sched_setattr",
+    "sched_getattr",
+    "renameat2",
+    "seccomp",
+    "getrandom",
+    "memfd_create",
+    "bpf",
+    "execveat",
+    "userfaultfd",
+    "membarrier",
+    "mlock2",
+    "copy_file_range",
+    "preadv2",
+    "pwritev2",
+    "pkey_mprotect",
+    "pkey_alloc",
+    "pkey_free",
+    "statx",
+    "io_pgetevents",
+    "rseq",
+    "kexec_file_load",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "pidfd_send_signal",
+    "io_uring_setup",
+    "io_uring_enter",
+    "io_uring_register",
+    "open_tree",
+    "move_mount",
+    "fsopen",
+    "fsconfig",
+    "fsmount",
+    "fspick",
+    "pidfd_open",
+    "clone3",
+    "close_range",
+    "openat2",
+    "pidfd_getfd",
+    "faccessat2",
+    "process_madvise",
+    "epoll_pwait2",
+    "mount_setattr",
+    "quotactl_fd",
+    "landlock_create_ruleset",
+    "landlock_add_rule",
+    "landlock_restrict_self",
+    "memfd_secret",
+    "process_mrelease",
+    "futex_waitv",
+    "set_mempolicy_home_node",
+];
+
+/// From `include/uapi/asm-generic/errno*.h`, shared by x86_64 and arm64.
+const ERRNO_NAMES: [&str; 134] = [
+    "",
+    "EPERM",
+    "ENOENT",
+    "ESRCH",
+    "EINTR",
+    "EIO",
+    "ENXIO",
+    "E2BIG",
+    "ENOEXEC",
+    "EBADF",
+    "ECHILD",
+    "EAGAIN",
+    "ENOMEM",
+    "EACCES",
+    "EFAULT",
+    "ENOTBLK",
+    "EBUSY",
+    "EEXIST",
+    "EXDEV",
+    "ENODEV",
+    "ENOTDIR",
+    "EISDIR",
+    "EINVAL",
+    "ENFILE",
+    "EMFILE",
+    "ENOTTY",
+    "ETXTBSY",
+    "EFBIG",
+    "ENOSPC",
+    "ESPIPE",
+    "EROFS",
+    "EMLINK",
+    "EPIPE",
+    "EDOM",
+    "ERANGE",
+    "EDEADLK",
+    "ENAMETOOLONG",
+    "ENOLCK",
+    "ENOSYS",
+    "ENOTEMPTY",
+    "ELOOP",
+    "",
+    "ENOMSG",
+    "EIDRM",
+    "ECHRNG",
+    "EL2NSYNC",
+    "EL3HLT",
+    "EL3RST",
+    "ELNRNG",
+    "EUNATCH",
+    "ENOCSI",
+    "EL2HLT",
+    "EBADE",
+    "EBADR",
+    "EXFULL",
+    "ENOANO",
+    "EBADRQC",
+    "EBADSLT",
+    "",
+    "EBFONT",
+    "ENOSTR",
+    "ENODATA",
+    "ETIME",
+    "ENOSR",
+    "ENONET",
+    "ENOPKG",
+    "EREMOTE",
+    "ENOLINK",
+    "EADV",
+    "ESRMNT",
+    "ECOMM",
+    "EPROTO",
+    "EMULTIHOP",
+    "EDOTDOT",
+    "EBADMSG",
+    "EOVERFLOW",
+    "ENOTUNIQ",
+    "EBADFD",
+    "EREMCHG",
+    "ELIBACC",
+    "ELIBBAD",
+    "ELIBSCN",
+    "ELIBMAX",
+    "ELIBEXEC",
+    "EILSEQ",
+    "ERESTART",
+    "ESTRPIPE",
+    "EUSERS",
+    "ENOTSOCK",
+    "EDESTADDRREQ",
+    "EMSGSIZE",
+    "EPROTOTYPE",
+    "ENOPROTOOPT",
+    "EPROTONOSUPPORT",
+    "ESOCKTNOSUPPORT",
+    "EOPNOTSUPP",
+    "EPFNOSUPPORT",
+    "EAFNOSUPPORT",
+    "EADDRINUSE",
+    "EADDRNOTAVAIL",
+    "ENETDOWN",
+    "ENETUNREACH",
+    "ENETRESET",
+    "ECONNABORTED",
+    "ECONNRESET",
+    "ENOBUFS",
+    "EISCONN",
+    "ENOTCONN",
+    "ESHUTDOWN",
+    "ETOOMANYREFS",
+    "ETIMEDOUT",
+    "ECONNREFUSED",
+    "EHOSTDOWN",
+    "EHOSTUNREACH",
+    "EALREADY",
+    "EINPROGRESS",
+    "ESTALE",
+    "EUCLEAN",
+    "ENOTNAM",
+    "ENAVAIL",
+    "EISNAM",
+    "EREMOTEIO",
+    "EDQUOT",
+    "ENOMEDIUM",
+    "EMEDIUMTYPE",
+    "ECANCELED",
+    "ENOKEY",
+    "EKEYEXPIRED",
+    "EKEYREVOKED",
+    "EKEYREJECTED",
+    "EOWNERDEAD",
+    "ENOTRECOVERABLE",
+    "ERFKILL",
+    "EHWPOISON",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let mut table = SyscallTable::default();
+        let stats = |count, errors, total_ns| SyscallStats {
+            count,
+            errors,
+            total_ns,
+        };
+        table.update(
+            vec![
+                (SyscallKey { tgid: 1, nr: 0 }, stats(10, 0, 20_000)),
+                (SyscallKey { tgid: 2, nr: 0 }, stats(5, 0, 10_000)),
+                (SyscallKey { tgid: 2, nr: 1 }, stats(20, 4, 4_000)),
+            ],
+            vec![(
+                SyscallErrorKey {
+                    tgid: 2,
+                    nr: 1,
+                    errno: 2,
+                    padding: 0,
+                },
+                4,
+            )],
+            |_| false,
+        );
+        let all = table.summary(None, SyscallSort::Count);
+        assert_eq!(all[0].count, 20);
+        assert_eq!(all[0].errnos.get("ENOENT"), Some(&4));
+        assert_eq!((all[1].count, all[1].total_us, all[1].avg_us), (15, 30, 2));
+        let one = table.summary(Some(1), SyscallSort::Time);
+        assert_eq!(one.len(), 1);
+        assert_eq!(one[0].count, 10);
+
+        // Later reads add to the totals.
+        table.update(
+            vec![(SyscallKey { tgid: 1, nr: 0 }, stats(2, 1, 4_000))],
+            vec![],
+            |_| false,
+        );
+        let one = table.summary(Some(1), SyscallSort::Count);
+        assert_eq!((one[0].count, one[0].errors, one[0].total_us), (12, 1, 24));
+
+        // Exited processes no longer have rows of their own but still count in the totals,
+        // including what is read after their exit.
+        table.exit(2);
+        table.update(
+            vec![(SyscallKey { tgid: 2, nr: 1 }, stats(1, 0, 1_000))],
+            vec![],
+            |tgid| tgid == 2,
+        );
+        assert!(table.summary(Some(2), SyscallSort::Count).is_empty());
+        assert_eq!(table.stats.len(), 3);
+        let all = table.summary(None, SyscallSort::Count);
+        assert_eq!((all[0].count, all[1].count), (21, 17));
+        assert_eq!(all[0].errnos.get("ENOENT"), Some(&4));
+        assert_eq!(errno_name(11), "EAGAIN");
+        assert_eq!(errno_name(9999), "E9999");
+    }
+}
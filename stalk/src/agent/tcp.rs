@@ -0,0 +1,191 @@
+use std::{collections::HashMap, net::IpAddr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{agent::histogram::Histogram, event::TcpEvent};
+
+/// What `/net/health` is sorted by.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TcpSort {
+    #[default]
+    Retransmits,
+    /// Resets sent and received.
+    Resets,
+    /// 99th percentile of the smoothed RTT.
+    Rtt,
+}
+
+/// Whether `/net/health` lists remote addresses or processes.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TcpGroup {
+    #[default]
+    Remote,
+    Process,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TcpHealth {
+    /// Last command name of the owning process, empty for remotes.
+    pub comm: String,
+    pub retransmits: u64,
+    pub resets_sent: u64,
+    pub resets_received: u64,
+    /// Smoothed RTT samples in us.
+    pub rtt: Histogram,
+    pub last_srtt_us: Option<u32>,
+}
+
+impl TcpHealth {
+    fn record(&mut self, ev: &TcpEvent) {
+        match ev.op {
+            "retransmit" => self.retransmits += 1,
+            "send_reset" => self.resets_sent += 1,
+            "receive_reset" => self.resets_received += 1,
+            _ => {}
+        }
+        if let Some(srtt) = ev.srtt_us {
+            self.rtt.record(srtt as u64);
+            self.last_srtt_us = Some(srtt);
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TcpHealthRow {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<IpAddr>,
+    /// Name the remote address was resolved from, when a DNS answer for it was seen.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tgid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comm: Option<String>,
+    pub retransmits: u64,
+    pub resets_sent: u64,
+    pub resets_received: u64,
+    pub rtt_samples: u64,
+    pub srtt_us: Option<u32>,
+    pub rtt_p50_us: u64,
+    pub rtt_p99_us: u64,
+}
+
+impl TcpHealthRow {
+    fn new(health: &TcpHealth) -> Self {
+        TcpHealthRow {
+            remote: None,
+            name: None,
+            tgid: None,
+            comm: None,
+            retransmits: health.retransmits,
+            resets_sent: health.resets_sent,
+            resets_received: health.resets_received,
+            rtt_samples: health.rtt.count,
+            srtt_us: health.last_srtt_us,
+            rtt_p50_us: health.rtt.percentile(0.5),
+            rtt_p99_us: health.rtt.percentile(0.99),
+        }
+    }
+}
+
+/// Retransmits, resets and RTT by remote address and by owning process.
+#[derive(Debug, Default)]
+pub struct TcpHealthTable {
+    pub remotes: HashMap<IpAddr, TcpHealth>,
+    pub processes: HashMap<u32, TcpHealth>,
+}
+
+impl TcpHealthTable {
+    pub fn record(&mut self, ev: &TcpEvent) {
+        self.remotes.entry(ev.remote.ip()).or_default().record(ev);
+        // Sockets that were never seen sending have no known owner.
+        if ev.pid != 0 {
+            let process = self.processes.entry(ev.pid).or_default();
+            process.comm.clone_from(&ev.comm);
+            process.record(ev);
+        }
+    }
+
+    /// Rows sorted by `sort`, with `name` looking up remote addresses.
+    pub fn rank(
+        &self,
+        group: TcpGroup,
+        sort: TcpSort,
+        name: impl Fn(&IpAddr) -> Option<String>,
+    ) -> Vec<TcpHealthRow> {
+        let mut rows: Vec<_> = match group {
+            TcpGroup::Remote => self
+                .remotes
+                .iter()
+                .map(|(addr, health)| TcpHealthRow {
+                    remote: Some(*addr),
+                    name: name(addr),
+                    ..TcpHealthRow::new(health)
+                })
+                .collect(),
+            TcpGroup::Process => self
+                .processes
+                .iter()
+                .map(|(tgid, health)| TcpHealthRow {
+                    tgid: Some(*tgid),
+                    comm: Some(health.comm.clone()),
+                    ..TcpHealthRow::new(health)
+                })
+                .collect(),
+        };
+        rows.sort_by_key(|row| {
+            std::cmp::Reverse(match sort {
+                TcpSort::Retransmits => row.retransmits,
+                TcpSort::Resets => row.resets_sent + row.resets_received,
+                TcpSort::Rtt => row.rtt_p99_us,
+            })
+        });
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::Instant;
+
+    use super::*;
+
+    fn event(op: &'static str, pid: u32, remote: &str, srtt_us: Option<u32>) -> TcpEvent {
+        TcpEvent {
+            op,
+            pid,
+            comm: "curl".to_string(),
+            local: "10.0.0.1:40000".parse().unwrap(),
+            remote: remote.parse().unwrap(),
+            state: None,
+            srtt_us,
+            start_time: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_rank() {
+        let mut table = TcpHealthTable::default();
+        table.record(&event("retransmit", 7, "1.1.1.1:443", None));
+        table.record(&event("retransmit", 0, "1.1.1.1:443", None));
+        table.record(&event("receive_reset", 7, "8.8.8.8:53", None));
+        table.record(&event("rtt", 7, "8.8.8.8:53", Some(1500)));
+
+        let remotes = table.rank(TcpGroup::Remote, TcpSort::Retransmits, |addr| {
+            addr.is_ipv4().then(|| "one.one.one.one".to_string())
+        });
+        assert_eq!(remotes[0].remote, Some("1.1.1.1".parse().unwrap()));
+        assert_eq!(remotes[0].retransmits, 2);
+        assert_eq!(remotes[0].name.as_deref(), Some("one.one.one.one"));
+        assert_eq!(remotes[1].resets_received, 1);
+        assert_eq!(remotes[1].srtt_us, Some(1500));
+
+        let processes = table.rank(TcpGroup::Process, TcpSort::Resets, |_| None);
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].tgid, Some(7));
+        assert_eq!(processes[0].retransmits, 1);
+        assert_eq!(processes[0].rtt_samples, 1);
+    }
+}
@@ -0,0 +1,229 @@
+use anyhow::{Context, anyhow, bail};
+
+use crate::agent::layouts::FieldLayout;
+
+/// Mount points of tracefs, the second being the legacy one under debugfs.
+const TRACEFS: [&str; 2] = ["/sys/kernel/tracing", "/sys/kernel/debug/tracing"];
+
+/// A field of a tracepoint record as described by its `format` file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceField {
+    pub name: String,
+    /// C type without the name, e.g. `const char *` or `char[16]`.
+    pub ty: String,
+    pub offset: usize,
+    pub size: usize,
+    pub signed: bool,
+    /// `__data_loc` fields hold `len << 16 | offset` of data stored after the fixed part.
+    pub data_loc: bool,
+}
+
+/// Layout of a tracepoint record.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TraceFormat {
+    pub fields: Vec<TraceField>,
+}
+
+impl TraceFormat {
+    pub fn field(&self, name: &str) -> Option<&TraceField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// Fields other than the `common_*` header.
+    pub fn event_fields(&self) -> impl Iterator<Item = &TraceField> {
+        self.fields
+            .iter()
+            .filter(|field| !field.name.starts_with("common_"))
+    }
+
+    /// Size of the fixed part of the record.
+    pub fn size(&self) -> usize {
+        self.fields
+            .iter()
+            .map(|field| field.offset + field.size)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn has_data_loc(&self) -> bool {
+        self.fields.iter().any(|field| field.data_loc)
+    }
+}
+
+/// Whether tracefs has `events/<category>/<name>`. Assumed when tracefs isn't mounted, leaving
+/// the attach to fail.
+pub fn has_tracepoint(category: &str, name: &str) -> bool {
+    let mounted: Vec<_> = TRACEFS
+        .iter()
+        .filter(|root| std::path::Path::new(&format!("{root}/events")).is_dir())
+        .collect();
+    mounted.is_empty()
+        || mounted
+            .iter()
+            .any(|root| std::path::Path::new(&format!("{root}/events/{category}/{name}")).exists())
+}
+
+/// Reads `events/<category>/<name>/format` from tracefs.
+pub fn read_format(category: &str, name: &str) -> anyhow::Result<TraceFormat> {
+    let contents = TRACEFS
+        .iter()
+        .find_map(|root| {
+            std::fs::read_to_string(format!("{root}/events/{category}/{name}/format")).ok()
+        })
+        .ok_or(anyhow!("no tracefs format for {category}:{name}"))?;
+    parse_format(&contents).with_context(|| format!("bad tracefs format for {category}:{name}"))
+}
+
+pub fn parse_format(contents: &str) -> anyhow::Result<TraceFormat> {
+    let mut format = TraceFormat::default();
+    for line in contents.lines() {
+        let Some(line) = line.trim().strip_prefix("field:") else {
+            continue;
+        };
+        let mut parts = line.split(';').map(str::trim);
+        let decl = parts.next().unwrap_or_default();
+        let mut attr = |key: &str| -> anyhow::Result<usize> {
+            parts
+                .next()
+                .and_then(|part| part.strip_prefix(key))
+                .and_then(|value| value.parse().ok())
+                .ok_or(anyhow!("missing {key} in `{line}`"))
+        };
+        let offset = attr("offset:")?;
+        let size = attr("size:")?;
+        // Old kernels omit `signed:`.
+        let signed = attr("signed:").unwrap_or(0) != 0;
+        let (ty, name) = decl
+            .rsplit_once(' ')
+            .ok_or(anyhow!("bad declaration `{decl}`"))?;
+        let (name, array) = match name.split_once('[') {
+            Some((name, len)) => (name, format!("[{len}")),
+            None => (name, String::new()),
+        };
+        let data_loc = ty.starts_with("__data_loc ");
+        let ty = ty.strip_prefix("__data_loc ").unwrap_or(ty);
+        format.fields.push(TraceField {
+            name: name.to_string(),
+            ty: format!("{ty}{array}"),
+            offset,
+            size,
+            signed,
+            data_loc,
+        });
+    }
+    if format.fields.is_empty() {
+        return Err(anyhow!("no fields"));
+    }
+    Ok(format)
+}
+
+/// Checks that every field the compiled layout relies on sits at the same offset, with the
+/// same size, in the kernel's record.
+pub fn check_layout(format: &TraceFormat, expected: &[FieldLayout]) -> anyhow::Result<()> {
+    let mismatches: Vec<_> = expected
+        .iter()
+        .filter_map(|layout| match format.field(layout.name) {
+            None => Some(format!("{} is missing", layout.name)),
+            Some(field) if (field.offset, field.size) != (layout.offset, layout.size) => {
+                Some(format!(
+                    "{} is at offset {} size {}, compiled for offset {} size {}",
+                    layout.name, field.offset, field.size, layout.offset, layout.size
+                ))
+            }
+            Some(_) => None,
+        })
+        .collect();
+    if !mismatches.is_empty() {
+        bail!("{}", mismatches.join(", "));
+    }
+    Ok(())
+}
+
+/// Renders `field` of `record`: strings for `char` arrays and `__data_loc` data, hex for
+/// pointers and other arrays, decimal for integers.
+pub fn decode_field(record: &[u8], field: &TraceField) -> Option<String> {
+    let bytes = record.get(field.offset..field.offset + field.size)?;
+    if field.data_loc {
+        let loc = u32::from_ne_bytes(bytes.try_into().ok()?);
+        let start = (loc & 0xFFFF) as usize;
+        let len = (loc >> 16) as usize;
+        let data = record.get(start..start + len)?;
+        return Some(if field.ty.starts_with("char") {
+            c_string(data)
+        } else {
+            hex(data)
+        });
+    }
+    if field.ty.ends_with(']') {
+        return Some(if field.ty.starts_with("char") {
+            c_string(bytes)
+        } else {
+            hex(bytes)
+        });
+    }
+    let value = match field.size {
+        1 => bytes[0] as u64,
+        2 => u16::from_ne_bytes(bytes.try_into().ok()?) as u64,
+        4 => u32::from_ne_bytes(bytes.try_into().ok()?) as u64,
+        8 => u64::from_ne_bytes(bytes.try_into().ok()?),
+        _ => return Some(hex(bytes)),
+    };
+    if field.ty.ends_with('*') {
+        return Some(format!("{value:#x}"));
+    }
+    if field.signed {
+        // Sign-extend from the field's width.
+        let shift = 64 - field.size * 8;
+        return Some((((value << shift) as i64) >> shift).to_string());
+    }
+    Some(value.to_string())
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let len = bytes.iter().position(|&c| c == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..len]).to_string()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHED_PROCESS_EXEC: &str = "name: sched_process_exec
+ID: 311
+format:
+\tfield:unsigned short common_type;\toffset:0;\tsize:2;\tsigned:0;
+\tfield:unsigned char common_flags;\toffset:2;\tsize:1;\tsigned:0;
+\tfield:unsigned char common_preempt_count;\toffset:3;\tsize:1;\tsigned:0;
+\tfield:int common_pid;\toffset:4;\tsize:4;\tsigned:1;
+
+\tfield:__data_loc char[] filename;\toffset:8;\tsize:4;\tsigned:0;
+\tfield:pid_t pid;\toffset:12;\tsize:4;\tsigned:1;
+\tfield:pid_t old_pid;\toffset:16;\tsize:4;\tsigned:1;
+
+print fmt: \"filename=%s pid=%d old_pid=%d\", __get_str(filename), REC->pid, REC->old_pid
+";
+
+    #[test]
+    fn test_parse_and_decode() {
+        let format = parse_format(SCHED_PROCESS_EXEC).unwrap();
+        assert_eq!(format.fields.len(), 7);
+        assert_eq!(format.size(), 20);
+        let filename = format.field("filename").unwrap();
+        assert!(filename.data_loc);
+        assert_eq!(filename.ty, "char[]");
+
+        let mut record = vec![0u8; 20];
+        record[8..12].copy_from_slice(&((8u32 << 16) | 20).to_ne_bytes());
+        record[12..16].copy_from_slice(&(-1i32).to_ne_bytes());
+        record.extend_from_slice(b"/bin/ls\0");
+        assert_eq!(decode_field(&record, filename).unwrap(), "/bin/ls");
+        assert_eq!(
+            decode_field(&record, format.field("pid").unwrap()).unwrap(),
+            "-1"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use std::path::Path;
+
+use anyhow::{anyhow, bail};
+use stalk_common::{UsdtArgKind, UsdtArgSpec};
+
+use crate::agent::elf::{
+    SHT_NOTE, check_header, file_offset, find_section, load_segments, read_u32, read_u64,
+    section_data, sections,
+};
+
+const NT_STAPSDT: u32 = 3;
+
+/// x86-64 registers by their offset in `struct pt_regs`, each with its narrower names.
+const PT_REGS: [(u32, &[&str]); 17] = [
+    (0, &["r15", "r15d", "r15w", "r15b"]),
+    (8, &["r14", "r14d", "r14w", "r14b"]),
+    (16, &["r13", "r13d", "r13w", "r13b"]),
+    (24, &["r12", "r12d", "r12w", "r12b"]),
+    (32, &["rbp", "ebp", "bp", "bpl"]),
+    (40, &["rbx", "ebx", "bx", "bl"]),
+    (48, &["r11", "r11d", "r11w", "r11b"]),
+    (56, &["r10", "r10d", "r10w", "r10b"]),
+    (64, &["r9", "r9d", "r9w", "r9b"]),
+    (72, &["r8", "r8d", "r8w", "r8b"]),
+    (80, &["rax", "eax", "ax", "al"]),
+    (88, &["rcx", "ecx", "cx", "cl"]),
+    (96, &["rdx", "edx", "dx", "dl"]),
+    (104, &["rsi", "esi", "si", "sil"]),
+    (112, &["rdi", "edi", "di", "dil"]),
+    (128, &["rip", "eip", "ip"]),
+    (152, &["rsp", "esp", "sp", "spl"]),
+];
+
+/// A USDT probe site from a binary's `.note.stapsdt` section.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsdtProbe {
+    pub provider: String,
+    pub name: String,
+    /// Address of the probe's `nop`, as linked.
+    pub pc: u64,
+    /// Link-time address of `.stapsdt.base`, used to correct `pc` in prelinked binaries.
+    pub base: u64,
+    /// Address of the semaphore guarding the probe, or 0 if it is always enabled.
+    pub semaphore: u64,
+    /// Argument spec, e.g. `-4@%edi 8@%rsi`.
+    pub args: String,
+}
+
+/// Finds `provider:name` in the 64-bit little-endian ELF at `binary` and returns it with the
+/// file offset to place a uprobe at.
+pub fn find_probe(binary: &Path, provider: &str, name: &str) -> anyhow::Result<(UsdtProbe, u64)> {
+    let data = std::fs::read(binary)?;
+    check_header(&data, binary)?;
+    let sections = sections(&data).ok_or(anyhow!("malformed section headers"))?;
+    let base_addr = find_section(&data, &sections, ".stapsdt.base").map(|s| s.addr);
+    let notes = find_section(&data, &sections, ".note.stapsdt")
+        .filter(|s| s.kind == SHT_NOTE)
+        .ok_or(anyhow!("{} has no USDT probes", binary.display()))?;
+    let notes = section_data(&data, notes).ok_or(anyhow!("truncated .note.stapsdt"))?;
+    let probe = parse_notes(notes)
+        .into_iter()
+        .find(|p| p.provider == provider && p.name == name)
+        .ok_or(anyhow!(
+            "no USDT probe {provider}:{name} in {}",
+            binary.display()
+        ))?;
+    let pc = match base_addr {
+        Some(addr) if probe.base != 0 => probe.pc.wrapping_add(addr).wrapping_sub(probe.base),
+        _ => probe.pc,
+    };
+    let segments = load_segments(&data).ok_or(anyhow!("malformed program headers"))?;
+    let offset =
+        file_offset(&segments, pc).ok_or(anyhow!("probe address {pc:#x} is not loaded"))?;
+    Ok((probe, offset))
+}
+
+/// Parses the `stapsdt` notes in the contents of a `.note.stapsdt` section.
+pub fn parse_notes(notes: &[u8]) -> Vec<UsdtProbe> {
+    let mut probes = Vec::new();
+    let mut pos = 0;
+    while let (Some(namesz), Some(descsz), Some(kind)) = (
+        read_u32(notes, pos),
+        read_u32(notes, pos + 4),
+        read_u32(notes, pos + 8),
+    ) {
+        let name_start = pos + 12;
+        let desc_start = name_start + align4(namesz as usize);
+        let next = desc_start + align4(descsz as usize);
+        let owner = notes.get(name_start..name_start + namesz as usize);
+        let desc = notes.get(desc_start..desc_start + descsz as usize);
+        if let (NT_STAPSDT, Some(b"stapsdt\0"), Some(desc)) = (kind, owner, desc) {
+            probes.extend(parse_desc(desc));
+        }
+        pos = next;
+    }
+    probes
+}
+
+fn parse_desc(desc: &[u8]) -> Option<UsdtProbe> {
+    let pc = read_u64(desc, 0)?;
+    let base = read_u64(desc, 8)?;
+    let semaphore = read_u64(desc, 16)?;
+    let mut strings = desc.get(24..)?.split(|&c| c == 0);
+    let mut next = || {
+        strings
+            .next()
+            .map(|s| String::from_utf8_lossy(s).to_string())
+    };
+    Some(UsdtProbe {
+        provider: next()?,
+        name: next()?,
+        pc,
+        base,
+        semaphore,
+        args: next().unwrap_or_default(),
+    })
+}
+
+/// Decodes an x86-64 argument spec such as `-4@%eax 8@-16(%rbp) 4@$5`: each argument is its
+/// size in bytes, negative when signed, and an operand in AT&T syntax.
+pub fn parse_args(args: &str) -> anyhow::Result<Vec<UsdtArgSpec>> {
+    args.split_whitespace().map(parse_arg).collect()
+}
+
+fn parse_arg(arg: &str) -> anyhow::Result<UsdtArgSpec> {
+    let (size, operand) = arg
+        .split_once('@')
+        .ok_or(anyhow!("no size in argument `{arg}`"))?;
+    let size: i32 = size
+        .parse()
+        .map_err(|_| anyhow!("bad size in argument `{arg}`"))?;
+    if ![1, 2, 4, 8].contains(&size.unsigned_abs()) {
+        bail!("bad size in argument `{arg}`");
+    }
+    let mut spec = UsdtArgSpec {
+        size: size.unsigned_abs(),
+        signed: (size < 0) as u32,
+        ..UsdtArgSpec::UNUSED
+    };
+    if let Some(value) = operand.strip_prefix('$') {
+        spec.value = parse_int(value).ok_or(anyhow!("bad constant in argument `{arg}`"))?;
+    } else if let Some(reg) = operand.strip_prefix('%') {
+        spec.kind = UsdtArgKind::Reg;
+        spec.reg_offset = reg_offset(reg).ok_or(anyhow!("unknown register in `{arg}`"))?;
+    } else if let Some((offset, reg)) = operand
+        .strip_suffix(')')
+        .and_then(|operand| operand.split_once("(%"))
+    {
+        spec.kind = UsdtArgKind::RegDeref;
+        spec.reg_offset = reg_offset(reg).ok_or(anyhow!("unknown register in `{arg}`"))?;
+        // `sym(%rip)` would need the symbol's address.
+        spec.value = if offset.is_empty() {
+            0
+        } else {
+            parse_int(offset).ok_or(anyhow!("unsupported displacement in `{arg}`"))?
+        };
+    } else {
+        bail!("unsupported operand in argument `{arg}`");
+    }
+    Ok(spec)
+}
+
+fn reg_offset(name: &str) -> Option<u32> {
+    PT_REGS
+        .iter()
+        .find(|(_, names)| names.contains(&name))
+        .map(|(offset, _)| *offset)
+}
+
+fn parse_int(value: &str) -> Option<i64> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value),
+    };
+    let value = match value.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+        None => value.parse().ok()?,
+    };
+    Some(if negative { -value } else { value })
+}
+
+fn align4(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_notes() {
+        let mut notes = Vec::new();
+        let mut note = |kind: u32, owner: &[u8], desc: &[u8]| {
+            notes.extend_from_slice(&(owner.len() as u32).to_le_bytes());
+            notes.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+            notes.extend_from_slice(&kind.to_le_bytes());
+            notes.extend_from_slice(owner);
+            notes.resize(align4(notes.len()), 0);
+            notes.extend_from_slice(desc);
+            notes.resize(align4(notes.len()), 0);
+        };
+        let mut desc = Vec::new();
+        desc.extend_from_slice(&0x1234u64.to_le_bytes());
+        desc.extend_from_slice(&0x4000u64.to_le_bytes());
+        desc.extend_from_slice(&0u64.to_le_bytes());
+        desc.extend_from_slice(b"myapp\0request__start\0-4@%edi 8@%rsi\0");
+        note(1, b"GNU\0", &[0; 20]);
+        note(NT_STAPSDT, b"stapsdt\0", &desc);
+        assert_eq!(
+            parse_notes(&notes),
+            vec![UsdtProbe {
+                provider: "myapp".to_string(),
+                name: "request__start".to_string(),
+                pc: 0x1234,
+                base: 0x4000,
+                semaphore: 0,
+                args: "-4@%edi 8@%rsi".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = parse_args("-4@%eax 8@-16(%rbp) 2@(%r8) 1@$0x10 -8@$-3").unwrap();
+        assert_eq!(
+            args,
+            vec![
+                UsdtArgSpec {
+                    kind: UsdtArgKind::Reg,
+                    reg_offset: 80,
+                    value: 0,
+                    size: 4,
+                    signed: 1,
+                },
+                UsdtArgSpec {
+                    kind: UsdtArgKind::RegDeref,
+                    reg_offset: 32,
+                    value: -16,
+                    size: 8,
+                    signed: 0,
+                },
+                UsdtArgSpec {
+                    kind: UsdtArgKind::RegDeref,
+                    reg_offset: 72,
+                    value: 0,
+                    size: 2,
+                    signed: 0,
+                },
+                UsdtArgSpec {
+                    kind: UsdtArgKind::Const,
+                    reg_offset: 0,
+                    value: 16,
+                    size: 1,
+                    signed: 0,
+                },
+                UsdtArgSpec {
+                    kind: UsdtArgKind::Const,
+                    reg_offset: 0,
+                    value: -3,
+                    size: 8,
+                    signed: 1,
+                },
+            ]
+        );
+        assert!(parse_args("").unwrap().is_empty());
+        assert!(parse_args("8@counter(%rip)").is_err());
+        assert!(parse_args("3@%eax").is_err());
+        assert!(parse_args("4@%xmm0").is_err());
+        assert!(parse_args("%eax").is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{net::IpAddr, num::NonZeroU64, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 
@@ -18,6 +18,14 @@ pub struct Cli {
 pub struct StalkConfig {
     pub items: Vec<StalkItem>,
     pub port: u16,
+    /// Address the web server listens on. It serves captured plaintext, so only loopback by
+    /// default.
+    #[serde(default = "default_address")]
+    pub address: IpAddr,
+}
+
+fn default_address() -> IpAddr {
+    IpAddr::from([127, 0, 0, 1])
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -27,6 +35,56 @@ pub enum StalkItem {
     Openat,
     Read,
     Net(String),
+    Process,
+    Signal,
+    FileOps,
+    Perms,
+    Creds,
+    Modules,
+    Bpf,
+    Namespaces,
+    Ptrace,
+    ExecMem,
+    Sockets,
+    Dns,
+    /// OpenSSL plaintext, capturing up to this many bytes per call.
+    Tls(u32),
+    /// Lines read by `readline` in this bash binary.
+    Shell(String),
+    /// Entry of `symbol` in `binary`, paired with its return for latency when `ret` is set.
+    Uprobe {
+        binary: String,
+        symbol: String,
+        ret: bool,
+    },
+    /// A USDT (`.note.stapsdt`) probe.
+    Usdt {
+        binary: String,
+        provider: String,
+        name: String,
+    },
+    /// OOM kills with their victim, trigger and the cgroup that hit its limit, plus memory
+    /// pressure of every cgroup sampled every few seconds.
+    Oom,
+    /// TCP retransmits, resets and smoothed RTT, per remote address and per process.
+    TcpHealth,
+    /// Block I/O device latency and bytes, per process and per device.
+    Bio,
+    /// Time threads spend blocked or waiting for a CPU, by stack.
+    OffCpu,
+    /// Per-process syscall counts, errors and latency, aggregated in the kernel.
+    Syscalls,
+    /// On-CPU stacks of every process, sampled this many times per second on each CPU.
+    Profile {
+        hz: NonZeroU64,
+    },
+    /// Any tracepoint, decoding `fields` (all when empty) from its tracefs format.
+    Tracepoint {
+        category: String,
+        name: String,
+        #[serde(default)]
+        fields: Vec<String>,
+    },
 }
 
 #[cfg(test)]
@@ -43,11 +101,25 @@ mod tests {
                 StalkItem::Net("eth2".to_string()),
             ],
             port: 3000,
+            address: default_address(),
         };
 
         let toml_str = toml::to_string(&config).unwrap();
         let deserialized_config: StalkConfig = toml::from_str(&toml_str).unwrap();
 
         assert_eq!(config.items.len(), deserialized_config.items.len());
+        assert_eq!(deserialized_config.address, default_address());
+
+        let config: StalkConfig =
+            toml::from_str("items = []\nport = 80\naddress = \"::\"").unwrap();
+        assert_eq!(config.address, IpAddr::from([0u16; 8]));
+
+        let profile = |hz| {
+            toml::from_str::<StalkConfig>(&format!(
+                "items = [{{ Profile = {{ hz = {hz} }} }}]\nport = 80"
+            ))
+        };
+        assert!(profile(99).is_ok());
+        assert!(profile(0).is_err());
     }
 }
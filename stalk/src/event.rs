@@ -3,10 +3,21 @@ use core::fmt::Display;
 
 use serde::Serialize;
 use stalk_common::{
-    RawExecveEvent, RawExitEvent, RawOpenatEvent, RawReadEvent, RawReadEventExit, RawXdpEvent,
+    Creds, ExecMemBacking, ExecMemKind, FileOpKind, ModuleEventKind, NamespaceOpKind, PermOpKind,
+    ProcessEventKind, PtraceEventKind, RawBioEvent, RawBpfEvent, RawCredsEvent, RawDnsEvent,
+    RawExecMemEvent, RawExecveEvent, RawExitEvent, RawFileOpEvent, RawModuleEvent,
+    RawNamespaceEvent, RawOomEvent, RawOpenatEvent, RawPermEvent, RawProcessEvent, RawPtraceEvent,
+    RawReadEvent, RawReadEventExit, RawShellCommandEvent, RawSignalEvent, RawSocketEvent,
+    RawTcpEvent, RawTlsDataEvent, RawTracepointEvent, RawUprobeEvent, RawXdpEvent, SignalEventKind,
+    SocketEventKind, TcpEventKind, TlsDirection,
 };
 use tokio::time::Instant;
 
+use crate::agent::{
+    dns::{DnsData, DnsMessage, rcode_name},
+    oom::Pressure,
+};
+
 pub trait Event: Display {
     fn pid(&self) -> u32;
     fn start_time(&self) -> Instant;
@@ -17,6 +28,21 @@ pub trait Event: Display {
 
 pub trait RawEvent {}
 
+/// Decodes a NUL-terminated (or full-length) C string buffer.
+fn bytes_to_string(bytes: &[u8]) -> String {
+    let len = bytes.iter().position(|&c| c == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..len]).to_string()
+}
+
+/// Syscall return value as `ok` or the errno description.
+fn syscall_result(ret: i64) -> String {
+    if ret < 0 {
+        std::io::Error::from_raw_os_error(-ret as i32).to_string()
+    } else {
+        "ok".to_string()
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct ExecveEvent {
     pub pid: u32,
@@ -78,10 +104,90 @@ impl From<RawExecveEvent> for ExecveEvent {
 
 impl RawEvent for RawExecveEvent {}
 
+/// Decoded wait status of an exiting process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ExitStatus {
+    Exited { code: u8 },
+    Signaled { signal: u8, core_dumped: bool },
+}
+
+impl From<u64> for ExitStatus {
+    fn from(status: u64) -> Self {
+        match status & 0x7f {
+            0 => ExitStatus::Exited {
+                code: ((status >> 8) & 0xff) as u8,
+            },
+            signal => ExitStatus::Signaled {
+                signal: signal as u8,
+                core_dumped: status & 0x80 != 0,
+            },
+        }
+    }
+}
+
+impl Display for ExitStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ExitStatus::Exited { code } => write!(f, "exit({code})"),
+            ExitStatus::Signaled {
+                signal,
+                core_dumped: false,
+            } => write!(f, "{}", signal_name(*signal as u32)),
+            ExitStatus::Signaled {
+                signal,
+                core_dumped: true,
+            } => write!(f, "{} (core dumped)", signal_name(*signal as u32)),
+        }
+    }
+}
+
+pub fn signal_name(signal: u32) -> String {
+    const NAMES: [&str; 32] = [
+        "0",
+        "SIGHUP",
+        "SIGINT",
+        "SIGQUIT",
+        "SIGILL",
+        "SIGTRAP",
+        "SIGABRT",
+        "SIGBUS",
+        "SIGFPE",
+        "SIGKILL",
+        "SIGUSR1",
+        "SIGSEGV",
+        "SIGUSR2",
+        "SIGPIPE",
+        "SIGALRM",
+        "SIGTERM",
+        "SIGSTKFLT",
+        "SIGCHLD",
+        "SIGCONT",
+        "SIGSTOP",
+        "SIGTSTP",
+        "SIGTTIN",
+        "SIGTTOU",
+        "SIGURG",
+        "SIGXCPU",
+        "SIGXFSZ",
+        "SIGVTALRM",
+        "SIGPROF",
+        "SIGWINCH",
+        "SIGIO",
+        "SIGPWR",
+        "SIGSYS",
+    ];
+    match NAMES.get(signal as usize) {
+        Some(name) => name.to_string(),
+        None => format!("SIGRT{}", signal.saturating_sub(32)),
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct ExitEvent {
     pub pid: u32,
-    pub exit_code: u64,
+    pub comm: String,
+    pub status: ExitStatus,
     #[serde(skip)]
     pub start_time: Instant,
 }
@@ -90,8 +196,8 @@ impl Display for ExitEvent {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
-            "ExitEvent {{ pid: {}, exit_code: {} }}",
-            self.pid, self.exit_code
+            "ExitEvent {{ pid: {}, comm: {}, status: {} }}",
+            self.pid, self.comm, self.status
         )
     }
 }
@@ -109,7 +215,8 @@ impl From<RawExitEvent> for ExitEvent {
     fn from(value: RawExitEvent) -> Self {
         ExitEvent {
             pid: value.pid,
-            exit_code: value.exit_code,
+            comm: bytes_to_string(&value.comm),
+            status: value.exit_code.into(),
             start_time: Instant::now(),
         }
     }
@@ -225,6 +332,8 @@ pub struct XdpEvent {
     pub dest_addr: [u8; 4],
     pub source_port: u16,
     pub dest_port: u16,
+    /// Name the source address was resolved from, if a DNS answer for it was seen.
+    pub source_name: Option<String>,
     #[serde(skip)]
     pub start_time: Instant,
 }
@@ -244,7 +353,11 @@ impl Display for XdpEvent {
             self.dest_addr[3],
             self.source_port,
             self.dest_port
-        )
+        )?;
+        if let Some(name) = &self.source_name {
+            write!(f, " ({name})")?;
+        }
+        Ok(())
     }
 }
 
@@ -266,9 +379,1873 @@ impl From<RawXdpEvent> for XdpEvent {
             dest_addr: value.dest_addr.to_be_bytes(),
             source_port: value.source_port,
             dest_port: value.dest_port,
+            source_name: None,
             start_time,
         }
     }
 }
 
 impl RawEvent for RawXdpEvent {}
+
+#[derive(Debug)]
+pub struct ProcessEvent {
+    pub kind: ProcessEventKind,
+    pub pid: u32,
+    pub tgid: u32,
+    pub ppid: u32,
+    pub comm: String,
+    pub filename: String,
+    pub start_time: Instant,
+}
+
+impl Display for ProcessEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "ProcessEvent {{ kind: {:?}, pid: {}, tgid: {}, ppid: {}, comm: {}, filename: {} }}",
+            self.kind, self.pid, self.tgid, self.ppid, self.comm, self.filename
+        )
+    }
+}
+
+impl Event for ProcessEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawProcessEvent> for ProcessEvent {
+    fn from(value: RawProcessEvent) -> Self {
+        ProcessEvent {
+            kind: value.kind,
+            pid: value.pid,
+            tgid: value.tgid,
+            ppid: value.ppid,
+            comm: bytes_to_string(&value.comm),
+            filename: bytes_to_string(&value.filename),
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawProcessEvent {}
+
+#[derive(Debug)]
+pub struct SignalEvent {
+    pub kind: SignalEventKind,
+    pub tgid: u32,
+    pub comm: String,
+    /// Thread the signal was sent to, and its process.
+    pub target_pid: u32,
+    pub target_tgid: u32,
+    pub target_comm: String,
+    pub signal: u32,
+    pub errno: i32,
+    pub code: i32,
+    pub result: &'static str,
+    pub start_time: Instant,
+}
+
+impl Display for SignalEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.kind {
+            SignalEventKind::Generate => write!(
+                f,
+                "SignalEvent {{ generate, sender: {}({}), target: {}({}), thread: {}, signal: {}, code: {}, result: {} }}",
+                self.comm,
+                self.tgid,
+                self.target_comm,
+                self.target_tgid,
+                self.target_pid,
+                signal_name(self.signal),
+                self.code,
+                self.result
+            ),
+            SignalEventKind::Deliver => write!(
+                f,
+                "SignalEvent {{ deliver, target: {}({}), signal: {}, code: {} }}",
+                self.comm,
+                self.tgid,
+                signal_name(self.signal),
+                self.code
+            ),
+        }
+    }
+}
+
+impl Event for SignalEvent {
+    fn pid(&self) -> u32 {
+        self.tgid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawSignalEvent> for SignalEvent {
+    fn from(value: RawSignalEvent) -> Self {
+        // enum trace_signal_result in include/trace/events/signal.h
+        let result = match value.result {
+            0 => "delivered",
+            1 => "ignored",
+            2 => "already_pending",
+            3 => "overflow_fail",
+            4 => "lose_info",
+            _ => "unknown",
+        };
+        SignalEvent {
+            kind: value.kind,
+            tgid: value.tgid,
+            comm: bytes_to_string(&value.comm),
+            target_pid: value.target_pid,
+            target_tgid: value.target_tgid,
+            target_comm: bytes_to_string(&value.target_comm),
+            signal: value.sig as u32,
+            errno: value.errno,
+            code: value.code,
+            result,
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawSignalEvent {}
+
+#[derive(Debug, Serialize)]
+pub struct FileOpEvent {
+    pub op: &'static str,
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: String,
+    /// Directory fds `path` and `target` are relative to, `path` being the file itself for
+    /// ftruncate.
+    #[serde(skip)]
+    pub dfd: i32,
+    #[serde(skip)]
+    pub target_dfd: i32,
+    pub path: String,
+    /// Destination of rename/link/symlink.
+    pub target: Option<String>,
+    /// Mode of a new directory.
+    pub mode: Option<u32>,
+    /// Size a file is truncated to.
+    pub length: Option<u64>,
+    /// `RENAME_*` flags of renameat2, `AT_*` flags of linkat.
+    pub flags: Option<u64>,
+    /// Syscall return value, a negated errno on failure.
+    pub ret: i64,
+    #[serde(skip)]
+    pub start_time: Instant,
+}
+
+impl FileOpEvent {
+    pub fn result(&self) -> String {
+        syscall_result(self.ret)
+    }
+}
+
+impl Display for FileOpEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "FileOpEvent {{ {}, comm: {}({}), uid: {}, path: {}",
+            self.op, self.comm, self.tgid, self.uid, self.path
+        )?;
+        if let Some(target) = &self.target {
+            write!(f, " -> {target}")?;
+        }
+        if let Some(mode) = self.mode {
+            write!(f, ", mode: {mode:o}")?;
+        }
+        if let Some(length) = self.length {
+            write!(f, ", length: {length}")?;
+        }
+        if let Some(flags) = self.flags {
+            write!(f, ", flags: {flags:#x}")?;
+        }
+        write!(f, ", result: {} }}", self.result())
+    }
+}
+
+impl Event for FileOpEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawFileOpEvent> for FileOpEvent {
+    fn from(value: RawFileOpEvent) -> Self {
+        let op = match value.kind {
+            FileOpKind::Unlink => "unlink",
+            FileOpKind::Rmdir => "rmdir",
+            FileOpKind::Rename => "rename",
+            FileOpKind::Link => "link",
+            FileOpKind::Symlink => "symlink",
+            FileOpKind::Mkdir => "mkdir",
+            FileOpKind::Truncate => "truncate",
+        };
+        let target = matches!(
+            value.kind,
+            FileOpKind::Rename | FileOpKind::Link | FileOpKind::Symlink
+        )
+        .then(|| bytes_to_string(&value.path2));
+        let flags = matches!(value.kind, FileOpKind::Rename | FileOpKind::Link)
+            .then_some(value.arg)
+            .filter(|&flags| flags != 0);
+        FileOpEvent {
+            op,
+            pid: value.pid,
+            tgid: value.tgid,
+            uid: value.uid,
+            comm: bytes_to_string(&value.comm),
+            dfd: value.dfd,
+            target_dfd: value.dfd2,
+            path: bytes_to_string(&value.path),
+            target,
+            mode: (value.kind == FileOpKind::Mkdir).then_some(value.arg as u32 & 0o7777),
+            length: (value.kind == FileOpKind::Truncate).then_some(value.arg),
+            flags,
+            ret: value.ret,
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawFileOpEvent {}
+
+const SETID_BITS: u32 = 0o6000;
+
+#[derive(Debug, Serialize)]
+pub struct PermEvent {
+    pub op: &'static str,
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: String,
+    /// Directory fd `path` is relative to, or the target fd when `path` is empty.
+    #[serde(skip)]
+    pub dfd: i32,
+    pub path: String,
+    pub xattr: Option<String>,
+    pub mode: Option<u32>,
+    /// Mode `path` had before a chmod, when read from the inode.
+    pub old_mode: Option<u32>,
+    pub owner: Option<u32>,
+    pub group: Option<u32>,
+    /// Syscall return value, a negated errno on failure.
+    pub ret: i64,
+    #[serde(skip)]
+    pub start_time: Instant,
+}
+
+impl PermEvent {
+    pub fn result(&self) -> String {
+        syscall_result(self.ret)
+    }
+
+    /// Whether a successful chmod turned on setuid or setgid. An unknown old mode counts as
+    /// not having them.
+    pub fn setid_added(&self) -> bool {
+        let Some(mode) = self.mode.filter(|_| self.ret == 0) else {
+            return false;
+        };
+        mode & SETID_BITS & !self.old_mode.unwrap_or(0) != 0
+    }
+}
+
+impl Display for PermEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "PermEvent {{ {}, comm: {}({}), uid: {}, path: {}",
+            self.op, self.comm, self.tgid, self.uid, self.path
+        )?;
+        if let Some(mode) = self.mode {
+            match self.old_mode {
+                Some(old) => write!(f, ", mode: {old:o} -> {mode:o}")?,
+                None => write!(f, ", mode: ? -> {mode:o}")?,
+            }
+        }
+        if let Some(owner) = self.owner {
+            write!(f, ", owner: {owner}")?;
+        }
+        if let Some(group) = self.group {
+            write!(f, ", group: {group}")?;
+        }
+        if let Some(xattr) = &self.xattr {
+            write!(f, ", xattr: {xattr}")?;
+        }
+        write!(f, ", result: {} }}", self.result())
+    }
+}
+
+impl Event for PermEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawPermEvent> for PermEvent {
+    fn from(value: RawPermEvent) -> Self {
+        let (op, mode, xattr) = match value.kind {
+            PermOpKind::Chmod => ("chmod", Some(value.mode & 0o7777), None),
+            PermOpKind::Chown => ("chown", None, None),
+            PermOpKind::SetXattr => ("setxattr", None, Some(bytes_to_string(&value.name))),
+            PermOpKind::RemoveXattr => ("removexattr", None, Some(bytes_to_string(&value.name))),
+        };
+        // -1 leaves the owner or group unchanged.
+        let id = |id: u32| (value.kind == PermOpKind::Chown && id != u32::MAX).then_some(id);
+        PermEvent {
+            op,
+            pid: value.pid,
+            tgid: value.tgid,
+            uid: value.uid,
+            comm: bytes_to_string(&value.comm),
+            dfd: value.dfd,
+            path: bytes_to_string(&value.path),
+            xattr,
+            mode,
+            old_mode: (value.kind == PermOpKind::Chmod && value.old_mode != u32::MAX)
+                .then_some(value.old_mode & 0o7777),
+            owner: id(value.owner),
+            group: id(value.group),
+            ret: value.ret,
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawPermEvent {}
+
+#[derive(Debug, Serialize)]
+pub struct CredsEvent {
+    pub pid: u32,
+    pub tgid: u32,
+    pub comm: String,
+    #[serde(serialize_with = "serialize_creds")]
+    pub old: Creds,
+    #[serde(serialize_with = "serialize_creds")]
+    pub new: Creds,
+    #[serde(skip)]
+    pub start_time: Instant,
+}
+
+impl CredsEvent {
+    /// `(field, old, new)` for every id or capability set that changed.
+    pub fn changes(&self) -> Vec<(&'static str, String, String)> {
+        let (old, new) = (&self.old, &self.new);
+        let ids = [
+            ("uid", old.uid, new.uid),
+            ("gid", old.gid, new.gid),
+            ("suid", old.suid, new.suid),
+            ("sgid", old.sgid, new.sgid),
+            ("euid", old.euid, new.euid),
+            ("egid", old.egid, new.egid),
+            ("fsuid", old.fsuid, new.fsuid),
+            ("fsgid", old.fsgid, new.fsgid),
+        ];
+        let caps = [
+            ("cap_inheritable", old.cap_inheritable, new.cap_inheritable),
+            ("cap_permitted", old.cap_permitted, new.cap_permitted),
+            ("cap_effective", old.cap_effective, new.cap_effective),
+            ("cap_bset", old.cap_bset, new.cap_bset),
+            ("cap_ambient", old.cap_ambient, new.cap_ambient),
+        ];
+        ids.into_iter()
+            .filter(|(_, old, new)| old != new)
+            .map(|(name, old, new)| (name, old.to_string(), new.to_string()))
+            .chain(
+                caps.into_iter()
+                    .filter(|(_, old, new)| old != new)
+                    .map(|(name, old, new)| (name, format!("{old:#x}"), format!("{new:#x}"))),
+            )
+            .collect()
+    }
+}
+
+fn serialize_creds<S: serde::Serializer>(creds: &Creds, serializer: S) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeStruct;
+
+    let mut state = serializer.serialize_struct("Creds", 13)?;
+    state.serialize_field("uid", &creds.uid)?;
+    state.serialize_field("gid", &creds.gid)?;
+    state.serialize_field("suid", &creds.suid)?;
+    state.serialize_field("sgid", &creds.sgid)?;
+    state.serialize_field("euid", &creds.euid)?;
+    state.serialize_field("egid", &creds.egid)?;
+    state.serialize_field("fsuid", &creds.fsuid)?;
+    state.serialize_field("fsgid", &creds.fsgid)?;
+    state.serialize_field("cap_inheritable", &creds.cap_inheritable)?;
+    state.serialize_field("cap_permitted", &creds.cap_permitted)?;
+    state.serialize_field("cap_effective", &creds.cap_effective)?;
+    state.serialize_field("cap_bset", &creds.cap_bset)?;
+    state.serialize_field("cap_ambient", &creds.cap_ambient)?;
+    state.end()
+}
+
+impl Display for CredsEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "CredsEvent {{ comm: {}({})", self.comm, self.tgid)?;
+        for (name, old, new) in self.changes() {
+            write!(f, ", {name}: {old} -> {new}")?;
+        }
+        write!(f, " }}")
+    }
+}
+
+impl Event for CredsEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawCredsEvent> for CredsEvent {
+    fn from(value: RawCredsEvent) -> Self {
+        CredsEvent {
+            pid: value.pid,
+            tgid: value.tgid,
+            comm: bytes_to_string(&value.comm),
+            old: value.old,
+            new: value.new,
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawCredsEvent {}
+
+#[derive(Debug, Serialize)]
+pub struct ModuleEvent {
+    pub op: &'static str,
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: String,
+    /// Empty when a load fails before the image is parsed.
+    pub name: String,
+    /// Image fd for `finit_module`.
+    #[serde(skip)]
+    pub fd: i32,
+    /// Image file for `finit_module`, if the fd could still be resolved.
+    pub path: Option<String>,
+    pub taints: u32,
+    pub ret: i64,
+    #[serde(skip)]
+    pub start_time: Instant,
+}
+
+impl Display for ModuleEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "ModuleEvent {{ {}, comm: {}({}), uid: {}, name: {}",
+            self.op, self.comm, self.tgid, self.uid, self.name
+        )?;
+        if let Some(path) = &self.path {
+            write!(f, ", path: {path}")?;
+        }
+        if self.taints != 0 {
+            write!(f, ", taints: {:#x}", self.taints)?;
+        }
+        write!(f, ", result: {} }}", syscall_result(self.ret))
+    }
+}
+
+impl Event for ModuleEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawModuleEvent> for ModuleEvent {
+    fn from(value: RawModuleEvent) -> Self {
+        let op = match value.kind {
+            ModuleEventKind::Init => "init_module",
+            ModuleEventKind::Finit => "finit_module",
+            ModuleEventKind::Delete => "delete_module",
+        };
+        ModuleEvent {
+            op,
+            pid: value.pid,
+            tgid: value.tgid,
+            uid: value.uid,
+            comm: bytes_to_string(&value.comm),
+            name: bytes_to_string(&value.name),
+            fd: value.fd,
+            path: None,
+            taints: value.taints,
+            ret: value.ret,
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawModuleEvent {}
+
+/// `enum bpf_prog_type` in include/uapi/linux/bpf.h
+const BPF_PROG_TYPES: [&str; 33] = [
+    "unspec",
+    "socket_filter",
+    "kprobe",
+    "sched_cls",
+    "sched_act",
+    "tracepoint",
+    "xdp",
+    "perf_event",
+    "cgroup_skb",
+    "cgroup_sock",
+    "lwt_in",
+    "lwt_out",
+    "lwt_xmit",
+    "sock_ops",
+    "sk_skb",
+    "cgroup_device",
+    "sk_msg",
+    "raw_tracepoint",
+    "cgroup_sock_addr",
+    "lwt_seg6local",
+    "lirc_mode2",
+    "sk_reuseport",
+    "flow_dissector",
+    "cgroup_sysctl",
+    "raw_tracepoint_writable",
+    "cgroup_sockopt",
+    "tracing",
+    "struct_ops",
+    "ext",
+    "lsm",
+    "sk_lookup",
+    "syscall",
+    "netfilter",
+];
+
+/// `enum bpf_map_type` in include/uapi/linux/bpf.h
+const BPF_MAP_TYPES: [&str; 34] = [
+    "unspec",
+    "hash",
+    "array",
+    "prog_array",
+    "perf_event_array",
+    "percpu_hash",
+    "percpu_array",
+    "stack_trace",
+    "cgroup_array",
+    "lru_hash",
+    "lru_percpu_hash",
+    "lpm_trie",
+    "array_of_maps",
+    "hash_of_maps",
+    "devmap",
+    "sockmap",
+    "cpumap",
+    "xskmap",
+    "sockhash",
+    "cgroup_storage",
+    "reuseport_sockarray",
+    "percpu_cgroup_storage",
+    "queue",
+    "stack",
+    "sk_storage",
+    "devmap_hash",
+    "struct_ops",
+    "ringbuf",
+    "inode_storage",
+    "task_storage",
+    "bloom_filter",
+    "user_ringbuf",
+    "cgrp_storage",
+    "arena",
+];
+
+#[derive(Debug, Serialize)]
+pub struct BpfEvent {
+    pub cmd: String,
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: String,
+    /// Program type for `BPF_PROG_LOAD`, map type for `BPF_MAP_CREATE`.
+    pub attr_type: Option<String>,
+    pub name: String,
+    /// New fd on success.
+    pub ret: i64,
+    #[serde(skip)]
+    pub start_time: Instant,
+}
+
+impl Display for BpfEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "BpfEvent {{ {}, comm: {}({}), uid: {}",
+            self.cmd, self.comm, self.tgid, self.uid
+        )?;
+        if let Some(attr_type) = &self.attr_type {
+            write!(f, ", type: {attr_type}, name: {}", self.name)?;
+        }
+        write!(f, ", result: {} }}", syscall_result(self.ret))
+    }
+}
+
+impl Event for BpfEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawBpfEvent> for BpfEvent {
+    fn from(value: RawBpfEvent) -> Self {
+        let type_name = |names: &[&str]| {
+            names
+                .get(value.attr_type as usize)
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| value.attr_type.to_string())
+        };
+        let (cmd, attr_type) = match value.cmd {
+            0 => (
+                "BPF_MAP_CREATE".to_string(),
+                Some(type_name(&BPF_MAP_TYPES)),
+            ),
+            5 => (
+                "BPF_PROG_LOAD".to_string(),
+                Some(type_name(&BPF_PROG_TYPES)),
+            ),
+            6 => ("BPF_OBJ_PIN".to_string(), None),
+            8 => ("BPF_PROG_ATTACH".to_string(), None),
+            17 => ("BPF_RAW_TRACEPOINT_OPEN".to_string(), None),
+            18 => ("BPF_BTF_LOAD".to_string(), None),
+            28 => ("BPF_LINK_CREATE".to_string(), None),
+            cmd => (format!("BPF_CMD_{cmd}"), None),
+        };
+        BpfEvent {
+            cmd,
+            pid: value.pid,
+            tgid: value.tgid,
+            uid: value.uid,
+            comm: bytes_to_string(&value.comm),
+            attr_type,
+            name: bytes_to_string(&value.name),
+            ret: value.ret,
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawBpfEvent {}
+
+/// `MS_*` in include/uapi/linux/mount.h
+const MOUNT_FLAGS: [(u64, &str); 24] = [
+    (1, "MS_RDONLY"),
+    (1 << 1, "MS_NOSUID"),
+    (1 << 2, "MS_NODEV"),
+    (1 << 3, "MS_NOEXEC"),
+    (1 << 4, "MS_SYNCHRONOUS"),
+    (1 << 5, "MS_REMOUNT"),
+    (1 << 6, "MS_MANDLOCK"),
+    (1 << 7, "MS_DIRSYNC"),
+    (1 << 8, "MS_NOSYMFOLLOW"),
+    (1 << 10, "MS_NOATIME"),
+    (1 << 11, "MS_NODIRATIME"),
+    (1 << 12, "MS_BIND"),
+    (1 << 13, "MS_MOVE"),
+    (1 << 14, "MS_REC"),
+    (1 << 15, "MS_SILENT"),
+    (1 << 16, "MS_POSIXACL"),
+    (1 << 17, "MS_UNBINDABLE"),
+    (1 << 18, "MS_PRIVATE"),
+    (1 << 19, "MS_SLAVE"),
+    (1 << 20, "MS_SHARED"),
+    (1 << 21, "MS_RELATIME"),
+    (1 << 23, "MS_I_VERSION"),
+    (1 << 24, "MS_STRICTATIME"),
+    (1 << 25, "MS_LAZYTIME"),
+];
+
+/// `umount2` flags in include/linux/fs.h
+const UMOUNT_FLAGS: [(u64, &str); 4] = [
+    (1, "MNT_FORCE"),
+    (2, "MNT_DETACH"),
+    (4, "MNT_EXPIRE"),
+    (8, "UMOUNT_NOFOLLOW"),
+];
+
+/// `CLONE_*` in include/uapi/linux/sched.h, namespace flags first.
+const CLONE_FLAGS: [(u64, &str); 16] = [
+    (0x0002_0000, "CLONE_NEWNS"),
+    (0x0200_0000, "CLONE_NEWCGROUP"),
+    (0x0400_0000, "CLONE_NEWUTS"),
+    (0x0800_0000, "CLONE_NEWIPC"),
+    (0x1000_0000, "CLONE_NEWUSER"),
+    (0x2000_0000, "CLONE_NEWPID"),
+    (0x4000_0000, "CLONE_NEWNET"),
+    (0x0000_0080, "CLONE_NEWTIME"),
+    (0x0000_0100, "CLONE_VM"),
+    (0x0000_0200, "CLONE_FS"),
+    (0x0000_0400, "CLONE_FILES"),
+    (0x0000_0800, "CLONE_SIGHAND"),
+    (0x0000_1000, "CLONE_PIDFD"),
+    (0x0000_4000, "CLONE_VFORK"),
+    (0x0001_0000, "CLONE_THREAD"),
+    (0x0004_0000, "CLONE_SYSVSEM"),
+];
+
+/// Names the bits of `flags` found in `table`, with any rest in hex.
+fn decode_flags(flags: u64, table: &[(u64, &str)]) -> String {
+    let mut names = Vec::new();
+    let mut rest = flags;
+    for (bit, name) in table {
+        if flags & bit != 0 {
+            names.push(name.to_string());
+            rest &= !bit;
+        }
+    }
+    if rest != 0 || names.is_empty() {
+        names.push(format!("{rest:#x}"));
+    }
+    names.join("|")
+}
+
+#[derive(Debug, Serialize)]
+pub struct NamespaceEvent {
+    pub op: &'static str,
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: String,
+    pub flags: Option<String>,
+    pub fstype: Option<String>,
+    pub source: Option<String>,
+    pub target: Option<String>,
+    /// Namespace fd for setns.
+    #[serde(skip)]
+    pub fd: i32,
+    /// Syscall return value, the child pid for clone.
+    pub ret: i64,
+    #[serde(skip)]
+    pub start_time: Instant,
+}
+
+impl Display for NamespaceEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "NamespaceEvent {{ {}, comm: {}({}), uid: {}",
+            self.op, self.comm, self.tgid, self.uid
+        )?;
+        if let Some(source) = &self.source {
+            write!(f, ", source: {source}")?;
+        }
+        if let Some(target) = &self.target {
+            write!(f, ", target: {target}")?;
+        }
+        if let Some(fstype) = &self.fstype {
+            write!(f, ", fstype: {fstype}")?;
+        }
+        if let Some(flags) = &self.flags {
+            write!(f, ", flags: {flags}")?;
+        }
+        write!(f, ", result: {} }}", syscall_result(self.ret))
+    }
+}
+
+impl Event for NamespaceEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawNamespaceEvent> for NamespaceEvent {
+    fn from(value: RawNamespaceEvent) -> Self {
+        let path = bytes_to_string(&value.path);
+        let path2 = bytes_to_string(&value.path2);
+        let (op, flags, source, target) = match value.kind {
+            NamespaceOpKind::Mount => (
+                "mount",
+                Some(decode_flags(value.flags, &MOUNT_FLAGS)),
+                Some(path),
+                Some(path2),
+            ),
+            NamespaceOpKind::Umount => (
+                "umount2",
+                Some(decode_flags(value.flags, &UMOUNT_FLAGS)),
+                None,
+                Some(path),
+            ),
+            // An nstype of 0 joins whatever namespace the fd refers to.
+            NamespaceOpKind::Setns => (
+                "setns",
+                Some(decode_flags(value.flags, &CLONE_FLAGS)),
+                None,
+                None,
+            ),
+            NamespaceOpKind::Unshare => (
+                "unshare",
+                Some(decode_flags(value.flags, &CLONE_FLAGS)),
+                None,
+                None,
+            ),
+            NamespaceOpKind::PivotRoot => ("pivot_root", None, Some(path), Some(path2)),
+            NamespaceOpKind::Chroot => ("chroot", None, None, Some(path)),
+            NamespaceOpKind::Clone => (
+                "clone",
+                Some(decode_flags(value.flags, &CLONE_FLAGS)),
+                None,
+                None,
+            ),
+        };
+        let fstype = Some(bytes_to_string(&value.fstype)).filter(|fstype| !fstype.is_empty());
+        NamespaceEvent {
+            op,
+            pid: value.pid,
+            tgid: value.tgid,
+            uid: value.uid,
+            comm: bytes_to_string(&value.comm),
+            flags,
+            fstype,
+            source,
+            target,
+            fd: value.fd,
+            ret: value.ret,
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawNamespaceEvent {}
+
+/// `PTRACE_*` requests in include/uapi/linux/ptrace.h and the x86 register requests.
+fn ptrace_request_name(request: u64) -> String {
+    let name = match request {
+        0 => "PTRACE_TRACEME",
+        1 => "PTRACE_PEEKTEXT",
+        2 => "PTRACE_PEEKDATA",
+        3 => "PTRACE_PEEKUSR",
+        4 => "PTRACE_POKETEXT",
+        5 => "PTRACE_POKEDATA",
+        6 => "PTRACE_POKEUSR",
+        7 => "PTRACE_CONT",
+        8 => "PTRACE_KILL",
+        9 => "PTRACE_SINGLESTEP",
+        12 => "PTRACE_GETREGS",
+        13 => "PTRACE_SETREGS",
+        14 => "PTRACE_GETFPREGS",
+        15 => "PTRACE_SETFPREGS",
+        16 => "PTRACE_ATTACH",
+        17 => "PTRACE_DETACH",
+        19 => "PTRACE_SETFPXREGS",
+        24 => "PTRACE_SYSCALL",
+        0x4200 => "PTRACE_SETOPTIONS",
+        0x4201 => "PTRACE_GETEVENTMSG",
+        0x4202 => "PTRACE_GETSIGINFO",
+        0x4203 => "PTRACE_SETSIGINFO",
+        0x4204 => "PTRACE_GETREGSET",
+        0x4205 => "PTRACE_SETREGSET",
+        0x4206 => "PTRACE_SEIZE",
+        0x4207 => "PTRACE_INTERRUPT",
+        0x4208 => "PTRACE_LISTEN",
+        _ => return format!("PTRACE_{request:#x}"),
+    };
+    name.to_string()
+}
+
+#[derive(Debug, Serialize)]
+pub struct PtraceEvent {
+    pub op: &'static str,
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: String,
+    /// Tracee pid, `None` for `PTRACE_TRACEME`, where the caller is traced by its parent.
+    pub target: Option<u32>,
+    /// Filled in from `/proc` while the target is still around.
+    pub target_comm: Option<String>,
+    pub request: Option<String>,
+    /// Bytes asked for by `process_vm_readv`/`process_vm_writev`.
+    pub requested: Option<u64>,
+    /// Syscall return value, the bytes transferred for `process_vm_*`.
+    pub ret: i64,
+    #[serde(skip)]
+    pub start_time: Instant,
+}
+
+impl PtraceEvent {
+    /// `tracer(tgid) -> tracee(pid)`, or `tracee(tgid) -> parent` for `PTRACE_TRACEME`.
+    pub fn pair(&self) -> String {
+        match self.target {
+            Some(target) => format!(
+                "{}({}) -> {}({})",
+                self.comm,
+                self.tgid,
+                self.target_comm.as_deref().unwrap_or("?"),
+                target
+            ),
+            None => format!("{}({}) -> parent", self.comm, self.tgid),
+        }
+    }
+}
+
+impl Display for PtraceEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "PtraceEvent {{ {}, {}, uid: {}",
+            self.op,
+            self.pair(),
+            self.uid
+        )?;
+        if let Some(request) = &self.request {
+            write!(f, ", request: {request}")?;
+        }
+        match self.requested {
+            Some(requested) if self.ret >= 0 => {
+                write!(f, ", bytes: {}/{requested} }}", self.ret)
+            }
+            _ => write!(f, ", result: {} }}", syscall_result(self.ret)),
+        }
+    }
+}
+
+impl Event for PtraceEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawPtraceEvent> for PtraceEvent {
+    fn from(value: RawPtraceEvent) -> Self {
+        let (op, request, requested) = match value.kind {
+            PtraceEventKind::Ptrace => ("ptrace", Some(ptrace_request_name(value.request)), None),
+            PtraceEventKind::VmReadv => ("process_vm_readv", None, Some(value.requested)),
+            PtraceEventKind::VmWritev => ("process_vm_writev", None, Some(value.requested)),
+        };
+        PtraceEvent {
+            op,
+            pid: value.pid,
+            tgid: value.tgid,
+            uid: value.uid,
+            comm: bytes_to_string(&value.comm),
+            target: (value.target != 0).then_some(value.target),
+            target_comm: None,
+            request,
+            requested,
+            ret: value.ret,
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawPtraceEvent {}
+
+const PROT_FLAGS: [(u64, &str); 3] = [(1, "PROT_READ"), (2, "PROT_WRITE"), (4, "PROT_EXEC")];
+
+/// Architecture independent `MAP_*` flags.
+const MAP_FLAGS: [(u64, &str); 5] = [
+    (0x01, "MAP_SHARED"),
+    (0x02, "MAP_PRIVATE"),
+    (0x10, "MAP_FIXED"),
+    (0x20, "MAP_ANONYMOUS"),
+    (0x10_0000, "MAP_FIXED_NOREPLACE"),
+];
+
+/// `MFD_*` in include/uapi/linux/memfd.h
+const MEMFD_FLAGS: [(u64, &str); 5] = [
+    (0x01, "MFD_CLOEXEC"),
+    (0x02, "MFD_ALLOW_SEALING"),
+    (0x04, "MFD_HUGETLB"),
+    (0x08, "MFD_NOEXEC_SEAL"),
+    (0x10, "MFD_EXEC"),
+];
+
+const EXECVEAT_FLAGS: [(u64, &str); 2] =
+    [(0x100, "AT_SYMLINK_NOFOLLOW"), (0x1000, "AT_EMPTY_PATH")];
+
+#[derive(Debug, Serialize)]
+pub struct ExecMemEvent {
+    pub op: &'static str,
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: String,
+    pub addr: Option<u64>,
+    pub len: Option<u64>,
+    pub prot: Option<String>,
+    pub writable: bool,
+    pub flags: Option<String>,
+    /// What the mprotect'ed region maps, read from its VMA: `[anon]` or the file's name.
+    pub mapping: Option<String>,
+    /// Memfd name, or the memfd execveat ran.
+    pub name: Option<String>,
+    /// Descriptor passed to mmap or execveat.
+    #[serde(skip)]
+    pub fd: i32,
+    pub ret: i64,
+    #[serde(skip)]
+    pub start_time: Instant,
+}
+
+impl Display for ExecMemEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "ExecMemEvent {{ {}, comm: {}({}), uid: {}",
+            self.op, self.comm, self.tgid, self.uid
+        )?;
+        if let (Some(addr), Some(len)) = (self.addr, self.len) {
+            write!(f, ", addr: {addr:#x}, len: {len}")?;
+        }
+        if let Some(prot) = &self.prot {
+            write!(f, ", prot: {prot}")?;
+        }
+        if let Some(mapping) = &self.mapping {
+            write!(f, ", mapping: {mapping}")?;
+        }
+        if let Some(name) = &self.name {
+            write!(f, ", name: {name}")?;
+        }
+        if let Some(flags) = &self.flags {
+            write!(f, ", flags: {flags}")?;
+        }
+        write!(f, ", result: {} }}", syscall_result(self.ret))
+    }
+}
+
+impl Event for ExecMemEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawExecMemEvent> for ExecMemEvent {
+    fn from(value: RawExecMemEvent) -> Self {
+        let flags = value.flags as u64;
+        let (op, flags) = match value.kind {
+            ExecMemKind::Mmap => ("mmap", Some(decode_flags(flags, &MAP_FLAGS))),
+            ExecMemKind::Mprotect => ("mprotect", None),
+            ExecMemKind::MemfdCreate => ("memfd_create", Some(decode_flags(flags, &MEMFD_FLAGS))),
+            ExecMemKind::Execveat => ("execveat", Some(decode_flags(flags, &EXECVEAT_FLAGS))),
+        };
+        let is_mapping = matches!(value.kind, ExecMemKind::Mmap | ExecMemKind::Mprotect);
+        let name = bytes_to_string(&value.name);
+        ExecMemEvent {
+            op,
+            pid: value.pid,
+            tgid: value.tgid,
+            uid: value.uid,
+            comm: bytes_to_string(&value.comm),
+            addr: is_mapping.then_some(value.addr),
+            len: is_mapping.then_some(value.len),
+            prot: is_mapping.then(|| decode_flags(value.prot as u64, &PROT_FLAGS)),
+            writable: value.prot & 2 != 0,
+            flags,
+            mapping: match value.backing {
+                ExecMemBacking::Unknown => None,
+                ExecMemBacking::Anonymous => Some("[anon]".to_string()),
+                ExecMemBacking::File | ExecMemBacking::Memfd => Some(name.clone()),
+            }
+            .filter(|_| value.kind == ExecMemKind::Mprotect),
+            name: (!is_mapping).then_some(name),
+            fd: value.fd,
+            ret: value.ret,
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawExecMemEvent {}
+
+const AF_INET6: u16 = 10;
+const TCP_LISTEN: i32 = 10;
+
+#[derive(Debug, Serialize)]
+pub struct SocketEvent {
+    pub op: &'static str,
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: String,
+    /// `tcp`, `tcp6`, `udp` or `udp6`, when known.
+    pub protocol: Option<String>,
+    pub addr: Option<std::net::IpAddr>,
+    pub port: Option<u16>,
+    pub backlog: Option<i32>,
+    #[serde(skip)]
+    pub fd: i32,
+    /// Socket inode, found from the fd after a bind and read by the kernel on state changes.
+    #[serde(skip)]
+    pub inode: Option<u64>,
+    pub ret: i64,
+    #[serde(skip)]
+    pub start_time: Instant,
+}
+
+impl Display for SocketEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "SocketEvent {{ {}, comm: {}({}), uid: {}",
+            self.op, self.comm, self.tgid, self.uid
+        )?;
+        if let Some(protocol) = &self.protocol {
+            write!(f, ", protocol: {protocol}")?;
+        }
+        if let (Some(addr), Some(port)) = (self.addr, self.port) {
+            write!(f, ", addr: {}", std::net::SocketAddr::new(addr, port))?;
+        }
+        if let Some(backlog) = self.backlog {
+            write!(f, ", fd: {}, backlog: {backlog}", self.fd)?;
+        }
+        write!(f, ", result: {} }}", syscall_result(self.ret))
+    }
+}
+
+impl Event for SocketEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawSocketEvent> for SocketEvent {
+    fn from(value: RawSocketEvent) -> Self {
+        let addr = if value.family == AF_INET6 {
+            std::net::IpAddr::from(value.addr)
+        } else {
+            let [a, b, c, d, ..] = value.addr;
+            std::net::IpAddr::from([a, b, c, d])
+        };
+        let v6 = if value.family == AF_INET6 { "6" } else { "" };
+        let (op, protocol, addr) = match value.kind {
+            SocketEventKind::Bind => ("bind", None, Some(addr)),
+            SocketEventKind::Listen => ("listen", None, None),
+            SocketEventKind::StateChange if value.newstate == TCP_LISTEN => {
+                ("tcp_listen", Some(format!("tcp{v6}")), Some(addr))
+            }
+            SocketEventKind::StateChange => ("tcp_unlisten", Some(format!("tcp{v6}")), Some(addr)),
+        };
+        SocketEvent {
+            op,
+            pid: value.pid,
+            tgid: value.tgid,
+            uid: value.uid,
+            comm: bytes_to_string(&value.comm),
+            protocol,
+            port: addr.map(|_| value.port),
+            addr,
+            backlog: (value.kind == SocketEventKind::Listen).then_some(value.backlog),
+            fd: value.fd,
+            inode: (value.inode != 0).then_some(value.inode),
+            ret: value.ret,
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawSocketEvent {}
+
+#[derive(Debug, Serialize)]
+pub struct DnsEvent {
+    pub pid: u32,
+    pub tgid: u32,
+    pub comm: String,
+    #[serde(flatten)]
+    pub message: DnsMessage,
+    #[serde(skip)]
+    pub start_time: Instant,
+}
+
+impl DnsEvent {
+    /// The first question's name, which is what the process looked up.
+    pub fn query(&self) -> Option<&str> {
+        self.message.questions.first().map(|q| q.name.as_str())
+    }
+}
+
+impl Display for DnsEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "DnsEvent {{ comm: {}({})", self.comm, self.tgid)?;
+        for question in &self.message.questions {
+            write!(f, ", query: {} {}", question.qtype, question.name)?;
+        }
+        for answer in &self.message.answers {
+            match &answer.data {
+                DnsData::A(addr) | DnsData::Aaaa(addr) => write!(f, ", answer: {addr}")?,
+                DnsData::Cname(name) => write!(f, ", cname: {name}")?,
+            }
+        }
+        write!(f, ", rcode: {} }}", rcode_name(self.message.rcode))
+    }
+}
+
+impl Event for DnsEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawDnsEvent> for DnsEvent {
+    fn from(value: RawDnsEvent) -> Self {
+        let len = (value.len as usize).min(value.data.len());
+        DnsEvent {
+            pid: value.pid,
+            tgid: value.tgid,
+            comm: bytes_to_string(&value.comm),
+            message: crate::agent::dns::parse(&value.data[..len]).unwrap_or_default(),
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawDnsEvent {}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TlsDataEvent {
+    /// `read` or `write`, as seen by the process.
+    pub direction: &'static str,
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: String,
+    /// Plaintext bytes transferred by the call.
+    pub len: u32,
+    /// The first bytes transferred, as hex.
+    #[serde(serialize_with = "serialize_hex")]
+    pub data: Vec<u8>,
+    /// `data` as text, when it is UTF-8 but for a character cut at the end.
+    pub text: Option<String>,
+    pub truncated: bool,
+    #[serde(skip)]
+    pub start_time: Instant,
+}
+
+impl Display for TlsDataEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "TlsDataEvent {{ {}, comm: {}({}), uid: {}, len: {}, ",
+            self.direction, self.comm, self.tgid, self.uid, self.len
+        )?;
+        match &self.text {
+            Some(text) => write!(f, "data: {text:?}")?,
+            None => write!(f, "hex: {}", hex(&self.data))?,
+        }
+        write!(f, "{} }}", if self.truncated { "..." } else { "" })
+    }
+}
+
+impl TlsDataEvent {
+    fn text(data: &[u8]) -> Option<String> {
+        match std::str::from_utf8(data) {
+            Ok(text) => Some(text.to_string()),
+            Err(e) if e.error_len().is_none() => {
+                String::from_utf8(data[..e.valid_up_to()].to_vec()).ok()
+            }
+            Err(_) => None,
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn serialize_hex<S: serde::Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex(bytes))
+}
+
+impl Event for TlsDataEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawTlsDataEvent> for TlsDataEvent {
+    fn from(value: RawTlsDataEvent) -> Self {
+        let captured = (value.captured as usize).min(value.data.len());
+        TlsDataEvent {
+            direction: match value.direction {
+                TlsDirection::Read => "read",
+                TlsDirection::Write => "write",
+            },
+            pid: value.pid,
+            tgid: value.tgid,
+            uid: value.uid,
+            comm: bytes_to_string(&value.comm),
+            len: value.len,
+            data: value.data[..captured].to_vec(),
+            text: TlsDataEvent::text(&value.data[..captured]),
+            truncated: captured < value.len as usize,
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawTlsDataEvent {}
+
+#[derive(Debug, Serialize)]
+pub struct ShellCommandEvent {
+    pub pid: u32,
+    pub tgid: u32,
+    pub uid: u32,
+    pub comm: String,
+    pub tty: Option<String>,
+    pub line: String,
+    /// The line was longer than the 255 bytes captured.
+    pub truncated: bool,
+    #[serde(skip)]
+    pub start_time: Instant,
+}
+
+impl Display for ShellCommandEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "ShellCommandEvent {{ comm: {}({}), uid: {}",
+            self.comm, self.tgid, self.uid
+        )?;
+        if let Some(tty) = &self.tty {
+            write!(f, ", tty: {tty}")?;
+        }
+        write!(f, ", line: {:?}", self.line)?;
+        write!(f, "{} }}", if self.truncated { "..." } else { "" })
+    }
+}
+
+impl Event for ShellCommandEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawShellCommandEvent> for ShellCommandEvent {
+    fn from(value: RawShellCommandEvent) -> Self {
+        ShellCommandEvent {
+            pid: value.pid,
+            tgid: value.tgid,
+            uid: value.uid,
+            comm: bytes_to_string(&value.comm),
+            tty: None,
+            line: bytes_to_string(&value.line),
+            truncated: value.truncated != 0,
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawShellCommandEvent {}
+
+#[derive(Debug, Serialize)]
+pub struct UprobeEvent {
+    /// Label of the probe, `binary:symbol` or `provider:name`.
+    pub probe: String,
+    pub pid: u32,
+    pub tgid: u32,
+    pub comm: String,
+    /// Argument registers at entry, or the decoded arguments of a USDT probe.
+    pub args: Vec<u64>,
+    /// Return value, on the second event of a call when the return is probed too.
+    pub ret: Option<u64>,
+    pub latency_us: Option<u64>,
+    #[serde(skip)]
+    pub start_time: Instant,
+}
+
+impl Display for UprobeEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "UprobeEvent {{ {}, comm: {}({}), args: {:x?}",
+            self.probe, self.comm, self.tgid, self.args
+        )?;
+        if let (Some(ret), Some(latency)) = (self.ret, self.latency_us) {
+            write!(f, ", ret: {ret:#x}, latency: {latency}us")?;
+        }
+        write!(f, " }}")
+    }
+}
+
+impl Event for UprobeEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawUprobeEvent> for UprobeEvent {
+    fn from(value: RawUprobeEvent) -> Self {
+        let returned = value.returned != 0;
+        UprobeEvent {
+            probe: String::new(),
+            pid: value.pid,
+            tgid: value.tgid,
+            comm: bytes_to_string(&value.comm),
+            args: value.args.to_vec(),
+            ret: returned.then_some(value.ret),
+            latency_us: returned.then_some(value.duration_ns / 1000),
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawUprobeEvent {}
+
+#[derive(Debug, Serialize)]
+pub struct TracepointEvent {
+    /// `category:name`
+    pub tracepoint: String,
+    pub pid: u32,
+    pub tgid: u32,
+    pub comm: String,
+    /// Requested fields, in order, with their decoded values.
+    pub fields: Vec<(String, String)>,
+    /// The raw record, as far as it was copied.
+    #[serde(skip)]
+    pub record: Vec<u8>,
+    #[serde(skip)]
+    pub start_time: Instant,
+}
+
+impl Display for TracepointEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "TracepointEvent {{ {}, comm: {}({})",
+            self.tracepoint, self.comm, self.tgid
+        )?;
+        for (name, value) in &self.fields {
+            write!(f, ", {name}: {value}")?;
+        }
+        write!(f, " }}")
+    }
+}
+
+impl Event for TracepointEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawTracepointEvent> for TracepointEvent {
+    fn from(value: RawTracepointEvent) -> Self {
+        let len = (value.len as usize).min(value.data.len());
+        TracepointEvent {
+            tracepoint: String::new(),
+            pid: value.pid,
+            tgid: value.tgid,
+            comm: bytes_to_string(&value.comm),
+            fields: Vec::new(),
+            record: value.data[..len].to_vec(),
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawTracepointEvent {}
+
+#[derive(Debug, Serialize)]
+pub struct BioEvent {
+    pub pid: u32,
+    pub comm: String,
+    /// Kernel `dev_t` of the disk or partition.
+    pub dev: u32,
+    pub sector: u64,
+    pub bytes: u32,
+    /// Operation flags, e.g. `R`, `WS` or `FWS`.
+    pub rwbs: String,
+    pub error: i32,
+    pub latency_us: u64,
+    #[serde(skip)]
+    pub start_time: Instant,
+}
+
+impl BioEvent {
+    pub fn is_read(&self) -> bool {
+        self.rwbs.contains('R')
+    }
+
+    pub fn is_write(&self) -> bool {
+        self.rwbs.contains('W')
+    }
+}
+
+impl Display for BioEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "BioEvent {{ comm: {}({}), dev: {}:{}, {} {} bytes at {}, latency: {}us, error: {} }}",
+            self.comm,
+            self.pid,
+            self.dev >> 20,
+            self.dev & 0xFFFFF,
+            self.rwbs,
+            self.bytes,
+            self.sector,
+            self.latency_us,
+            self.error
+        )
+    }
+}
+
+impl Event for BioEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawBioEvent> for BioEvent {
+    fn from(value: RawBioEvent) -> Self {
+        BioEvent {
+            pid: value.tgid,
+            comm: bytes_to_string(&value.comm),
+            dev: value.dev,
+            sector: value.sector,
+            bytes: value.bytes,
+            rwbs: bytes_to_string(&value.rwbs),
+            error: value.error,
+            latency_us: value.latency_ns / 1000,
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawBioEvent {}
+
+/// Names of TCP states, indexed by `TCP_*` from `include/net/tcp_states.h`.
+const TCP_STATES: [&str; 13] = [
+    "UNKNOWN",
+    "ESTABLISHED",
+    "SYN_SENT",
+    "SYN_RECV",
+    "FIN_WAIT1",
+    "FIN_WAIT2",
+    "TIME_WAIT",
+    "CLOSE",
+    "CLOSE_WAIT",
+    "LAST_ACK",
+    "LISTEN",
+    "CLOSING",
+    "NEW_SYN_RECV",
+];
+
+#[derive(Debug, Serialize)]
+pub struct TcpEvent {
+    /// `retransmit`, `send_reset`, `receive_reset` or `rtt`.
+    pub op: &'static str,
+    /// Owner of the socket, 0 when it wasn't seen sending.
+    pub pid: u32,
+    pub comm: String,
+    pub local: std::net::SocketAddr,
+    pub remote: std::net::SocketAddr,
+    /// Socket state when a segment is retransmitted.
+    pub state: Option<&'static str>,
+    pub srtt_us: Option<u32>,
+    #[serde(skip)]
+    pub start_time: Instant,
+}
+
+impl Display for TcpEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "TcpEvent {{ {}, comm: {}({}), local: {}, remote: {}",
+            self.op, self.comm, self.pid, self.local, self.remote
+        )?;
+        if let Some(state) = self.state {
+            write!(f, ", state: {state}")?;
+        }
+        if let Some(srtt) = self.srtt_us {
+            write!(f, ", srtt: {srtt}us")?;
+        }
+        write!(f, " }}")
+    }
+}
+
+impl Event for TcpEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawTcpEvent> for TcpEvent {
+    fn from(value: RawTcpEvent) -> Self {
+        let addr = |bytes: [u8; 16]| {
+            if value.family == AF_INET6 {
+                std::net::IpAddr::from(bytes)
+            } else {
+                let [a, b, c, d, ..] = bytes;
+                std::net::IpAddr::from([a, b, c, d])
+            }
+        };
+        let op = match value.kind {
+            TcpEventKind::Retransmit => "retransmit",
+            TcpEventKind::SendReset => "send_reset",
+            TcpEventKind::ReceiveReset => "receive_reset",
+            TcpEventKind::Rtt => "rtt",
+        };
+        TcpEvent {
+            op,
+            pid: value.tgid,
+            comm: bytes_to_string(&value.comm),
+            local: std::net::SocketAddr::new(addr(value.saddr), value.sport),
+            remote: std::net::SocketAddr::new(addr(value.daddr), value.dport),
+            state: (value.kind == TcpEventKind::Retransmit).then(|| {
+                TCP_STATES
+                    .get(value.state as usize)
+                    .copied()
+                    .unwrap_or("UNKNOWN")
+            }),
+            srtt_us: (value.kind == TcpEventKind::Rtt).then_some(value.srtt_us),
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawTcpEvent {}
+
+#[derive(Debug, Serialize)]
+pub struct OomEvent {
+    /// Victim of the OOM killer.
+    pub pid: u32,
+    pub comm: String,
+    /// Anonymous, file and shmem resident memory of the victim.
+    pub rss_kb: Option<u64>,
+    pub oom_score_adj: Option<i64>,
+    /// `cgroup` when a cgroup hit its memory limit, `system` otherwise.
+    pub constraint: Option<&'static str>,
+    /// The cgroup that hit its limit.
+    pub cgroup: Option<String>,
+    /// Process whose allocation failed and invoked the OOM killer.
+    pub trigger_pid: u32,
+    pub trigger_comm: String,
+    /// Latest memory pressure sample of `cgroup`, or of the system.
+    pub pressure: Option<Pressure>,
+    /// Thread that invoked the OOM killer.
+    #[serde(skip)]
+    pub trigger_thread: u32,
+    /// Id of `cgroup`, 0 when the whole system ran out or it's unknown.
+    #[serde(skip)]
+    pub memcg_id: u64,
+    /// The `oom:mark_victim` record, as far as it was copied.
+    #[serde(skip)]
+    pub record: Vec<u8>,
+    #[serde(skip)]
+    pub start_time: Instant,
+}
+
+impl Display for OomEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "OomEvent {{ comm: {}({})", self.comm, self.pid)?;
+        if let Some(rss) = self.rss_kb {
+            write!(f, ", rss: {rss}kB")?;
+        }
+        if let Some(adj) = self.oom_score_adj {
+            write!(f, ", oom_score_adj: {adj}")?;
+        }
+        if let Some(constraint) = self.constraint {
+            write!(f, ", constraint: {constraint}")?;
+        }
+        if let Some(cgroup) = &self.cgroup {
+            write!(f, ", cgroup: {cgroup}")?;
+        }
+        write!(f, ", trigger: {}({})", self.trigger_comm, self.trigger_pid)?;
+        if let Some(pressure) = &self.pressure {
+            write!(
+                f,
+                ", pressure: some {:.2}% full {:.2}%",
+                pressure.some.avg10, pressure.full.avg10
+            )?;
+        }
+        write!(f, " }}")
+    }
+}
+
+impl Event for OomEvent {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+    fn start_time(&self) -> Instant {
+        self.start_time
+    }
+}
+
+impl From<RawOomEvent> for OomEvent {
+    fn from(value: RawOomEvent) -> Self {
+        let len = (value.len as usize).min(value.data.len());
+        let message = bytes_to_string(&value.message);
+        // "Memory cgroup out of memory" or "Out of memory", the latter also covering
+        // cpuset and mempolicy constraints.
+        let constraint = if message.is_empty() {
+            None
+        } else if message.contains("cgroup") {
+            Some("cgroup")
+        } else {
+            Some("system")
+        };
+        OomEvent {
+            pid: 0,
+            comm: String::new(),
+            rss_kb: None,
+            oom_score_adj: None,
+            constraint,
+            cgroup: None,
+            trigger_pid: value.trigger_tgid,
+            trigger_comm: bytes_to_string(&value.trigger_comm),
+            pressure: None,
+            trigger_thread: value.trigger_pid,
+            memcg_id: value.memcg_id,
+            record: value.data[..len].to_vec(),
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl RawEvent for RawOomEvent {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_exit_status() {
+        assert_eq!(ExitStatus::from(0), ExitStatus::Exited { code: 0 });
+        assert_eq!(ExitStatus::from(3 << 8), ExitStatus::Exited { code: 3 });
+        assert_eq!(
+            ExitStatus::from(9),
+            ExitStatus::Signaled {
+                signal: 9,
+                core_dumped: false
+            }
+        );
+        assert_eq!(
+            ExitStatus::from(0x80 | 11).to_string(),
+            "SIGSEGV (core dumped)"
+        );
+        assert_eq!(ExitStatus::from(1 << 8).to_string(), "exit(1)");
+        assert_eq!(signal_name(34), "SIGRT2");
+    }
+
+    #[test]
+    fn test_decode_flags() {
+        assert_eq!(
+            decode_flags(0x1000 | 0x4000, &MOUNT_FLAGS),
+            "MS_BIND|MS_REC"
+        );
+        assert_eq!(
+            decode_flags(0x1000_0000 | 0x1, &CLONE_FLAGS),
+            "CLONE_NEWUSER|0x1"
+        );
+        assert_eq!(decode_flags(0, &UMOUNT_FLAGS), "0x0");
+    }
+
+    #[test]
+    fn test_setid_added() {
+        let mut event = PermEvent {
+            op: "chmod",
+            pid: 1,
+            tgid: 1,
+            uid: 0,
+            comm: "chmod".to_string(),
+            dfd: -100,
+            path: "/tmp/sh".to_string(),
+            xattr: None,
+            mode: Some(0o4755),
+            old_mode: None,
+            owner: None,
+            group: None,
+            ret: 0,
+            start_time: Instant::now(),
+        };
+        assert!(event.setid_added());
+        event.old_mode = Some(0o4755);
+        assert!(!event.setid_added());
+        event.mode = Some(0o6755);
+        assert!(event.setid_added());
+        event.ret = -1;
+        assert!(!event.setid_added());
+    }
+
+    #[test]
+    fn test_creds_changes() {
+        let old = Creds {
+            uid: 1000,
+            euid: 1000,
+            cap_bset: 0x1ff_ffff_ffff,
+            ..Default::default()
+        };
+        let mut event = CredsEvent {
+            pid: 1,
+            tgid: 1,
+            comm: "sudo".to_string(),
+            old,
+            new: old,
+            start_time: Instant::now(),
+        };
+        assert!(event.changes().is_empty());
+        event.new.euid = 0;
+        event.new.cap_effective = 0x1ff_ffff_ffff;
+        assert_eq!(
+            event.changes(),
+            vec![
+                ("euid", "1000".to_string(), "0".to_string()),
+                (
+                    "cap_effective",
+                    "0x0".to_string(),
+                    "0x1ffffffffff".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            event.to_string(),
+            "CredsEvent { comm: sudo(1), euid: 1000 -> 0, cap_effective: 0x0 -> 0x1ffffffffff }"
+        );
+    }
+}
@@ -1,12 +1,23 @@
 use std::{collections::HashMap, sync::Arc};
 
+use anyhow::Context;
 use aya::{
     maps::RingBuf,
-    programs::{TracePoint, Xdp, XdpFlags},
+    programs::{
+        KProbe, PerfEvent, RawTracePoint, TracePoint, UProbe, Xdp, XdpFlags,
+        perf_event::{PerfEventScope, PerfTypeId, SamplePolicy, perf_sw_ids},
+        uprobe::UProbeAttachLocation,
+    },
 };
-use log::warn;
+use log::{error, warn};
 use stalk_common::{
-    RawExecveEvent, RawExitEvent, RawOpenatEvent, RawReadEvent, RawReadEventExit, RawXdpEvent,
+    OOM_MAX_RECORD, OffCpuKey, OffCpuStart, ProfileKey, RawBioEvent, RawBpfEvent, RawCredsEvent,
+    RawDnsEvent, RawExecMemEvent, RawExecveEvent, RawExitEvent, RawFileOpEvent, RawModuleEvent,
+    RawNamespaceEvent, RawOomEvent, RawOpenatEvent, RawPermEvent, RawProcessEvent, RawPtraceEvent,
+    RawReadEvent, RawReadEventExit, RawShellCommandEvent, RawSignalEvent, RawSocketEvent,
+    RawTcpEvent, RawTlsDataEvent, RawTracepointEvent, RawUprobeEvent, RawXdpEvent, SocketEventKind,
+    SyscallErrorKey, SyscallKey, SyscallStats, TLS_MAX_CAPTURE, TRACEPOINT_MAX_RECORD, UPROBE_ARGS,
+    UsdtArgSpec,
 };
 use tokio::{
     io::unix::AsyncFd,
@@ -15,11 +26,24 @@ use tokio::{
 
 use crate::{
     agent::{
+        btf::Btf,
+        layouts::expected_layout,
+        listeners::{self, socket_protocol},
+        oom::{cgroup_path, decode_victim, is_kill, sample_pressure},
+        process::{comm, find_libraries, resolve_path, scan, tty},
+        profile::{OffCpuStack, Symbolizer},
         server::Server,
         state::{StalkEvent, TuiState},
+        tracefs::{check_layout, decode_field, has_tracepoint, read_format},
+        usdt::{find_probe, parse_args},
     },
     config::{StalkConfig, StalkItem},
-    event::{ExecveEvent, ExitEvent, OpenatEvent, ReadEvent, XdpEvent},
+    event::{
+        BioEvent, BpfEvent, CredsEvent, DnsEvent, ExecMemEvent, ExecveEvent, ExitEvent,
+        FileOpEvent, ModuleEvent, NamespaceEvent, OomEvent, OpenatEvent, PermEvent, ProcessEvent,
+        PtraceEvent, ReadEvent, ShellCommandEvent, SignalEvent, SocketEvent, TcpEvent,
+        TlsDataEvent, TracepointEvent, UprobeEvent, XdpEvent,
+    },
 };
 pub type EventSender = mpsc::Sender<StalkEvent>;
 
@@ -47,9 +71,90 @@ pub async fn stalk(config: StalkConfig) -> anyhow::Result<Server> {
             StalkItem::Net(interface) => {
                 stalk_net(tx.clone(), interface);
             }
+            StalkItem::Process => {
+                stalk_process(tx.clone());
+            }
+            StalkItem::Signal => {
+                stalk_signal(tx.clone());
+            }
+            StalkItem::FileOps => {
+                stalk_fileops(tx.clone());
+            }
+            StalkItem::Perms => {
+                stalk_perms(tx.clone());
+            }
+            StalkItem::Creds => {
+                stalk_creds(tx.clone());
+            }
+            StalkItem::Modules => {
+                stalk_modules(tx.clone());
+            }
+            StalkItem::Bpf => {
+                stalk_bpf(tx.clone());
+            }
+            StalkItem::Namespaces => {
+                stalk_namespaces(tx.clone());
+            }
+            StalkItem::Ptrace => {
+                stalk_ptrace(tx.clone());
+            }
+            StalkItem::ExecMem => {
+                stalk_execmem(tx.clone());
+            }
+            StalkItem::Sockets => {
+                stalk_sockets(tx.clone());
+            }
+            StalkItem::Dns => {
+                stalk_dns(tx.clone());
+            }
+            StalkItem::Tls(capture_size) => {
+                stalk_tls(tx.clone(), capture_size);
+            }
+            StalkItem::Shell(binary) => {
+                stalk_shell(tx.clone(), binary);
+            }
+            StalkItem::Uprobe {
+                binary,
+                symbol,
+                ret,
+            } => {
+                stalk_uprobe(tx.clone(), binary, symbol, ret);
+            }
+            StalkItem::Usdt {
+                binary,
+                provider,
+                name,
+            } => {
+                stalk_usdt(tx.clone(), binary, provider, name);
+            }
+            StalkItem::Oom => {
+                stalk_oom(tx.clone());
+            }
+            StalkItem::TcpHealth => {
+                stalk_tcp_health(tx.clone());
+            }
+            StalkItem::Bio => {
+                stalk_bio(tx.clone());
+            }
+            StalkItem::OffCpu => {
+                stalk_offcpu(tx.clone());
+            }
+            StalkItem::Profile { hz } => {
+                stalk_profile(tx.clone(), hz.get());
+            }
+            StalkItem::Syscalls => {
+                stalk_syscalls(tx.clone());
+            }
+            StalkItem::Tracepoint {
+                category,
+                name,
+                fields,
+            } => {
+                stalk_tracepoint(tx.clone(), category, name, fields);
+            }
         }
     }
-    crate::agent::server::web_server(shared_state, config.port).await
+    crate::agent::server::web_server(shared_state, config.address, config.port).await
 }
 
 pub fn stalk_execve(tx: EventSender) {
@@ -70,9 +175,9 @@ pub fn stalk_execve(tx: EventSender) {
 
 pub fn stalk_exit(tx: EventSender) {
     tokio::task::spawn(async move {
-        let _ = handle_tracepoint(
-            "stalk_exit_group",
-            ("syscalls", "sys_enter_exit_group"),
+        let _ = handle_kprobe(
+            "stalk_exit",
+            "do_exit",
             "EXIT_EVENTS",
             async move |raw_event: RawExitEvent| {
                 let event: ExitEvent = raw_event.into();
@@ -155,51 +260,1429 @@ pub fn stalk_net(tx: EventSender, interface: String) {
     });
 }
 
+/// Forks, execs and exits, on top of the processes found in `/proc` once attached so that none
+/// started in between are missed.
+pub fn stalk_process(tx: EventSender) {
+    tokio::task::spawn(async move {
+        if let Err(e) = poll_process(tx).await {
+            error!("process tracing stopped: {e:#}");
+        }
+    });
+}
+
+async fn poll_process(tx: EventSender) -> anyhow::Result<()> {
+    let mut ebpf = load_tracepoints(
+        &[
+            ("stalk_task_newtask", ("task", "task_newtask")),
+            ("stalk_process_fork", ("sched", "sched_process_fork")),
+            ("stalk_process_exec", ("sched", "sched_process_exec")),
+            ("stalk_process_exit", ("sched", "sched_process_exit")),
+        ],
+        &[],
+    )?;
+    let processes = tokio::task::spawn_blocking(scan).await?;
+    tx.send(StalkEvent::ProcessSeed(processes)).await?;
+    poll_events(
+        &mut ebpf,
+        "PROCESS_EVENTS",
+        async move |raw_event: RawProcessEvent| {
+            let event: ProcessEvent = raw_event.into();
+            tx.send(StalkEvent::Process(event)).await.unwrap();
+            Ok(())
+        },
+    )
+    .await
+}
+
+pub fn stalk_signal(tx: EventSender) {
+    tokio::task::spawn(async move {
+        if let Err(e) = poll_signal(tx).await {
+            error!("signal tracing stopped: {e:#}");
+        }
+    });
+}
+
+async fn poll_signal(tx: EventSender) -> anyhow::Result<()> {
+    let globals = task_offsets(&Btf::kernel()?)?;
+    let mut ebpf = load_tracepoints(
+        &[("stalk_signal_deliver", ("signal", "signal_deliver"))],
+        &globals,
+    )?;
+    let program: &mut RawTracePoint = ebpf
+        .program_mut("stalk_signal_generate")
+        .unwrap()
+        .try_into()?;
+    program.load()?;
+    program.attach("signal_generate")?;
+    poll_events(
+        &mut ebpf,
+        "SIGNAL_EVENTS",
+        async move |raw_event: RawSignalEvent| {
+            let event: SignalEvent = raw_event.into();
+            tx.send(StalkEvent::Signal(event)).await.unwrap();
+            Ok(())
+        },
+    )
+    .await
+}
+
+/// Syscalls traced by `StalkItem::FileOps`, keyed by their enter program.
+const FILEOPS_SYSCALLS: [(&str, &str); 8] = [
+    ("stalk_unlinkat", "unlinkat"),
+    ("stalk_renameat2", "renameat2"),
+    ("stalk_renameat", "renameat"),
+    ("stalk_linkat", "linkat"),
+    ("stalk_symlinkat", "symlinkat"),
+    ("stalk_mkdirat", "mkdirat"),
+    ("stalk_truncate", "truncate"),
+    ("stalk_ftruncate", "ftruncate"),
+];
+
+/// Forms without a directory fd, which architectures newer than x86_64 only have as `*at`.
+#[cfg(target_arch = "x86_64")]
+const FILEOPS_LEGACY_SYSCALLS: [(&str, &str); 6] = [
+    ("stalk_unlink", "unlink"),
+    ("stalk_rename", "rename"),
+    ("stalk_link", "link"),
+    ("stalk_symlink", "symlink"),
+    ("stalk_mkdir", "mkdir"),
+    ("stalk_rmdir", "rmdir"),
+];
+#[cfg(not(target_arch = "x86_64"))]
+const FILEOPS_LEGACY_SYSCALLS: [(&str, &str); 0] = [];
+
+pub fn stalk_fileops(tx: EventSender) {
+    tokio::task::spawn(async move {
+        let syscalls = [FILEOPS_SYSCALLS.as_slice(), &FILEOPS_LEGACY_SYSCALLS].concat();
+        let result = handle_syscalls(
+            &syscalls,
+            "stalk_fileops_exit",
+            &[],
+            "FILEOPS_EVENTS",
+            async move |raw_event: RawFileOpEvent| {
+                let mut event: FileOpEvent = raw_event.into();
+                event.path = resolve_path(event.tgid, event.dfd, &event.path);
+                if let Some(target) = &event.target {
+                    event.target = Some(resolve_path(event.tgid, event.target_dfd, target));
+                }
+                tx.send(StalkEvent::FileOps(event)).await.unwrap();
+                Ok(())
+            },
+        )
+        .await;
+        if let Err(e) = result {
+            error!("file operation tracing stopped: {e:#}");
+        }
+    });
+}
+
+/// Syscalls traced by `StalkItem::Perms`, keyed by their enter program.
+const PERMS_SYSCALLS: [(&str, &str); 10] = [
+    ("stalk_fchmodat", "fchmodat"),
+    ("stalk_fchmod", "fchmod"),
+    ("stalk_fchownat", "fchownat"),
+    ("stalk_fchown", "fchown"),
+    ("stalk_setxattr", "setxattr"),
+    ("stalk_setxattr", "lsetxattr"),
+    ("stalk_fsetxattr", "fsetxattr"),
+    ("stalk_removexattr", "removexattr"),
+    ("stalk_removexattr", "lremovexattr"),
+    ("stalk_fremovexattr", "fremovexattr"),
+];
+
+/// Forms without a directory fd, which architectures newer than x86_64 only have as `*at`.
+#[cfg(target_arch = "x86_64")]
+const PERMS_LEGACY_SYSCALLS: [(&str, &str); 3] = [
+    ("stalk_chmod", "chmod"),
+    ("stalk_chown", "chown"),
+    ("stalk_chown", "lchown"),
+];
+#[cfg(not(target_arch = "x86_64"))]
+const PERMS_LEGACY_SYSCALLS: [(&str, &str); 0] = [];
+
+pub fn stalk_perms(tx: EventSender) {
+    tokio::task::spawn(async move {
+        if let Err(e) = poll_perms(tx).await {
+            error!("permission tracing stopped: {e:#}");
+        }
+    });
+}
+
+/// Without BTF the modes replaced by chmod are left unknown.
+async fn poll_perms(tx: EventSender) -> anyhow::Result<()> {
+    let globals = match Btf::kernel().and_then(|btf| notify_change_offsets(&btf)) {
+        Ok(globals) => Some(globals),
+        Err(e) => {
+            warn!("not reading modes replaced by chmod: {e:#}");
+            None
+        }
+    };
+    let syscalls = [PERMS_SYSCALLS.as_slice(), &PERMS_LEGACY_SYSCALLS].concat();
+    let mut ebpf = load_syscalls(
+        &syscalls,
+        "stalk_perms_exit",
+        &[],
+        globals.as_deref().unwrap_or_default(),
+    )?;
+    if globals.is_some() {
+        let program: &mut KProbe = ebpf
+            .program_mut("stalk_perms_notify_change")
+            .unwrap()
+            .try_into()?;
+        program.load()?;
+        program.attach("notify_change", 0)?;
+    }
+    poll_events(
+        &mut ebpf,
+        "PERMS_EVENTS",
+        async move |raw_event: RawPermEvent| {
+            let mut event: PermEvent = raw_event.into();
+            event.path = resolve_path(event.tgid, event.dfd, &event.path);
+            tx.send(StalkEvent::Perms(event)).await.unwrap();
+            Ok(())
+        },
+    )
+    .await
+}
+
+pub fn stalk_modules(tx: EventSender) {
+    tokio::task::spawn(async move {
+        if let Err(e) = handle_syscalls(
+            &[
+                ("stalk_init_module", "init_module"),
+                ("stalk_finit_module", "finit_module"),
+                ("stalk_delete_module", "delete_module"),
+            ],
+            "stalk_modules_exit",
+            &[("stalk_module_load", ("module", "module_load"))],
+            "MODULES_EVENTS",
+            async move |raw_event: RawModuleEvent| {
+                let mut event: ModuleEvent = raw_event.into();
+                if event.fd >= 0 {
+                    event.path = Some(resolve_path(event.tgid, event.fd, ""))
+                        .filter(|path| !path.is_empty());
+                }
+                tx.send(StalkEvent::Module(event)).await.unwrap();
+                Ok(())
+            },
+        )
+        .await
+        {
+            error!("module tracing stopped: {e:#}");
+        }
+    });
+}
+
+pub fn stalk_bpf(tx: EventSender) {
+    tokio::task::spawn(async move {
+        if let Err(e) = handle_syscalls(
+            &[("stalk_bpf", "bpf")],
+            "stalk_bpf_exit",
+            &[],
+            "BPF_EVENTS",
+            async move |raw_event: RawBpfEvent| {
+                let event: BpfEvent = raw_event.into();
+                tx.send(StalkEvent::Bpf(event)).await.unwrap();
+                Ok(())
+            },
+        )
+        .await
+        {
+            error!("BPF tracing stopped: {e:#}");
+        }
+    });
+}
+
+/// Syscalls traced by `StalkItem::Namespaces`, keyed by their enter program. `umount2` is
+/// traced as `umount`.
+const NAMESPACES_SYSCALLS: [(&str, &str); 8] = [
+    ("stalk_mount", "mount"),
+    ("stalk_umount", "umount"),
+    ("stalk_setns", "setns"),
+    ("stalk_unshare", "unshare"),
+    ("stalk_pivot_root", "pivot_root"),
+    ("stalk_chroot", "chroot"),
+    ("stalk_clone", "clone"),
+    ("stalk_clone3", "clone3"),
+];
+
+pub fn stalk_namespaces(tx: EventSender) {
+    tokio::task::spawn(async move {
+        if let Err(e) = handle_syscalls(
+            &NAMESPACES_SYSCALLS,
+            "stalk_namespaces_exit",
+            &[],
+            "NAMESPACES_EVENTS",
+            async move |raw_event: RawNamespaceEvent| {
+                let mut event: NamespaceEvent = raw_event.into();
+                if event.fd >= 0 {
+                    event.target = Some(resolve_path(event.tgid, event.fd, ""));
+                } else if let Some(target) = &event.target {
+                    event.target = Some(resolve_path(event.tgid, libc::AT_FDCWD, target));
+                }
+                tx.send(StalkEvent::Namespace(event)).await.unwrap();
+                Ok(())
+            },
+        )
+        .await
+        {
+            error!("namespace tracing stopped: {e:#}");
+        }
+    });
+}
+
+pub fn stalk_ptrace(tx: EventSender) {
+    tokio::task::spawn(async move {
+        if let Err(e) = handle_syscalls(
+            &[
+                ("stalk_ptrace", "ptrace"),
+                ("stalk_process_vm_readv", "process_vm_readv"),
+                ("stalk_process_vm_writev", "process_vm_writev"),
+            ],
+            "stalk_ptrace_exit",
+            &[],
+            "PTRACE_EVENTS",
+            async move |raw_event: RawPtraceEvent| {
+                let mut event: PtraceEvent = raw_event.into();
+                event.target_comm = event.target.and_then(comm);
+                tx.send(StalkEvent::Ptrace(event)).await.unwrap();
+                Ok(())
+            },
+        )
+        .await
+        {
+            error!("ptrace tracing stopped: {e:#}");
+        }
+    });
+}
+
+pub fn stalk_execmem(tx: EventSender) {
+    tokio::task::spawn(async move {
+        if let Err(e) = poll_execmem(tx).await {
+            error!("executable memory tracing stopped: {e:#}");
+        }
+    });
+}
+
+async fn poll_execmem(tx: EventSender) -> anyhow::Result<()> {
+    let btf = Btf::kernel()?;
+    let mut globals = task_offsets(&btf)?;
+    globals.extend(execmem_offsets(&btf)?);
+    let mut ebpf = load_syscalls(
+        &[
+            ("stalk_mmap", "mmap"),
+            ("stalk_mprotect", "mprotect"),
+            ("stalk_memfd_create", "memfd_create"),
+        ],
+        "stalk_execmem_exit",
+        &[
+            ("stalk_execveat", ("syscalls", "sys_enter_execveat")),
+            ("stalk_execveat_exit", ("syscalls", "sys_exit_execveat")),
+        ],
+        &globals,
+    )?;
+    let program: &mut KProbe = ebpf
+        .program_mut("stalk_mprotect_fixup")
+        .unwrap()
+        .try_into()?;
+    program.load()?;
+    program.attach("mprotect_fixup", 0)?;
+    poll_events(
+        &mut ebpf,
+        "EXECMEM_EVENTS",
+        async move |raw_event: RawExecMemEvent| {
+            let event: ExecMemEvent = raw_event.into();
+            tx.send(StalkEvent::ExecMem(event)).await.unwrap();
+            Ok(())
+        },
+    )
+    .await
+}
+
+pub fn stalk_sockets(tx: EventSender) {
+    tokio::task::spawn(async move {
+        if let Err(e) = poll_sockets(tx).await {
+            error!("socket tracing stopped: {e:#}");
+        }
+    });
+}
+
+async fn poll_sockets(tx: EventSender) -> anyhow::Result<()> {
+    let btf = Btf::kernel()?;
+    let socket = btf.offset("socket_alloc", "socket")?;
+    let globals = [
+        ("SOCK_SOCKET_OFFSET", btf.offset("sock", "sk_socket")?),
+        (
+            "SOCKET_INODE_OFFSET",
+            btf.offset("socket_alloc", "vfs_inode")? - socket,
+        ),
+        ("INODE_INO_OFFSET", btf.offset("inode", "i_ino")?),
+    ];
+    let mut ebpf = load_syscalls(
+        &[("stalk_bind", "bind"), ("stalk_listen", "listen")],
+        "stalk_socket_exit",
+        &[("stalk_inet_sock_set_state", ("sock", "inet_sock_set_state"))],
+        &globals,
+    )?;
+    let listeners = tokio::task::spawn_blocking(listeners::scan).await?;
+    tx.send(StalkEvent::ListenerSeed(listeners)).await?;
+    poll_events(
+        &mut ebpf,
+        "SOCKET_EVENTS",
+        async move |raw_event: RawSocketEvent| {
+            let kind = raw_event.kind;
+            let mut event: SocketEvent = raw_event.into();
+            if kind == SocketEventKind::Bind && event.ret == 0 {
+                let (tgid, fd) = (event.tgid, event.fd);
+                let (protocol, inode) =
+                    tokio::task::spawn_blocking(move || socket_protocol(tgid, fd))
+                        .await?
+                        .unzip();
+                event.protocol = protocol;
+                event.inode = inode;
+            }
+            tx.send(StalkEvent::Socket(event)).await.unwrap();
+            Ok(())
+        },
+    )
+    .await
+}
+
+/// DNS responses read by processes from port 53 with `recvfrom`, `recvmsg` or `recvmmsg`, or
+/// with any receive call on a UDP socket connected to port 53.
+pub fn stalk_dns(tx: EventSender) {
+    tokio::task::spawn(async move {
+        if let Err(e) = poll_dns(tx).await {
+            error!("DNS tracing stopped: {e:#}");
+        }
+    });
+}
+
+async fn poll_dns(tx: EventSender) -> anyhow::Result<()> {
+    let btf = Btf::kernel()?;
+    let mut globals = task_offsets(&btf)?;
+    globals.push((
+        "FILE_PRIVATE_DATA_OFFSET",
+        btf.offset("file", "private_data")?,
+    ));
+    globals.push(("SOCKET_TYPE_OFFSET", btf.offset("socket", "type")?));
+    let mut ebpf = load_syscalls(
+        &[
+            ("stalk_dns_read", "read"),
+            ("stalk_dns_recvfrom", "recvfrom"),
+            ("stalk_dns_recvmsg", "recvmsg"),
+            ("stalk_dns_recvmmsg", "recvmmsg"),
+        ],
+        "stalk_dns_exit",
+        &[
+            ("stalk_dns_connect", ("syscalls", "sys_enter_connect")),
+            ("stalk_dns_connect_exit", ("syscalls", "sys_exit_connect")),
+        ],
+        &globals,
+    )?;
+    poll_events(
+        &mut ebpf,
+        "DNS_EVENTS",
+        async move |raw_event: RawDnsEvent| {
+            let event: DnsEvent = raw_event.into();
+            if event.message.response {
+                tx.send(StalkEvent::Dns(event)).await.unwrap();
+            }
+            Ok(())
+        },
+    )
+    .await
+}
+
+/// Probes on OpenSSL's plaintext calls, each program being attached to every symbol listed.
+const TLS_PROBES: [(&str, &[&str]); 5] = [
+    ("stalk_ssl_read", &["SSL_read"]),
+    ("stalk_ssl_write", &["SSL_write"]),
+    ("stalk_ssl_read_ex", &["SSL_read_ex"]),
+    ("stalk_ssl_write_ex", &["SSL_write_ex"]),
+    (
+        "stalk_ssl_ret",
+        &["SSL_read", "SSL_write", "SSL_read_ex", "SSL_write_ex"],
+    ),
+];
+
+/// How often processes are scanned for newly mapped `libssl` files.
+const TLS_RESCAN_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Plaintext passed to `SSL_read`/`SSL_write` and their `_ex` variants of every `libssl`
+/// mapped by a process. The probes are placed on the library files, so processes started
+/// later that map the same file are covered too, and files mapped for the first time are
+/// found by rescanning.
+pub fn stalk_tls(tx: EventSender, capture_size: u32) {
+    tokio::task::spawn(async move {
+        if let Err(e) = poll_tls(tx, capture_size).await {
+            error!("TLS capture stopped: {e:#}");
+        }
+    });
+}
+
+async fn poll_tls(tx: EventSender, capture_size: u32) -> anyhow::Result<()> {
+    let capture_size = capture_size.min(TLS_MAX_CAPTURE as u32);
+    let mut loader = aya::EbpfLoader::new();
+    loader.set_global("TLS_CAPTURE_SIZE", &capture_size, true);
+    let programs: Vec<_> = TLS_PROBES.iter().map(|(name, _)| *name).collect();
+    let mut ebpf = load_ebpf(&mut loader, &programs)?;
+    for (name, _) in TLS_PROBES {
+        let program: &mut UProbe = ebpf.program_mut(name).unwrap().try_into()?;
+        program.load()?;
+    }
+    let ring_buf = RingBuf::try_from(
+        ebpf.take_map("TLS_EVENTS")
+            .ok_or(anyhow::anyhow!("Failed to find map TLS_EVENTS"))?,
+    )?;
+    let mut events = AsyncFd::with_interest(ring_buf, tokio::io::Interest::READABLE)?;
+    let mut seen = std::collections::HashSet::new();
+    let mut rescan = tokio::time::interval(TLS_RESCAN_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = rescan.tick() => {
+                let known = seen.clone();
+                let libraries =
+                    tokio::task::spawn_blocking(move || find_libraries("libssl.so", &known))
+                        .await?;
+                for (key, library) in libraries {
+                    // Retried on the next rescan when no probe could be placed.
+                    if attach_tls(&mut ebpf, &library)? {
+                        seen.insert(key);
+                    }
+                }
+            }
+            guard = events.readable_mut() => {
+                let mut guard = guard?;
+                let ring_buf = guard.get_inner_mut();
+                while let Some(item) = ring_buf.next() {
+                    let ptr = item.as_ptr() as *const RawTlsDataEvent;
+                    let event: TlsDataEvent = unsafe { ptr.read_unaligned() }.into();
+                    tx.send(StalkEvent::Tls(event)).await.unwrap();
+                }
+                guard.clear_ready();
+            }
+        }
+    }
+}
+
+/// Attaches [`TLS_PROBES`] to `library`, returning whether any of them was placed. Symbols
+/// it lacks, like the `_ex` variants before OpenSSL 1.1.1, are skipped.
+fn attach_tls(ebpf: &mut aya::Ebpf, library: &std::path::Path) -> anyhow::Result<bool> {
+    let mut attached = false;
+    for (name, symbols) in TLS_PROBES {
+        let program: &mut UProbe = ebpf.program_mut(name).unwrap().try_into()?;
+        for symbol in symbols {
+            match program.attach(*symbol, library, None, None) {
+                Ok(_) => attached = true,
+                Err(e) => warn!(
+                    "failed to attach {name} to {symbol} in {}: {e}",
+                    library.display()
+                ),
+            }
+        }
+    }
+    Ok(attached)
+}
+
+/// Lines typed at interactive prompts of `binary`, builtins included.
+pub fn stalk_shell(tx: EventSender, binary: String) {
+    tokio::task::spawn(async move {
+        let result = handle_uprobes(
+            &[("stalk_readline", "readline")],
+            &[binary.into()],
+            &[],
+            "SHELL_EVENTS",
+            async move |raw_event: RawShellCommandEvent| {
+                let mut event: ShellCommandEvent = raw_event.into();
+                if event.line.trim().is_empty() {
+                    return Ok(());
+                }
+                event.tty = tty(event.tgid);
+                tx.send(StalkEvent::Shell(event)).await.unwrap();
+                Ok(())
+            },
+        )
+        .await;
+        if let Err(e) = result {
+            error!("shell tracing stopped: {e:#}");
+        }
+    });
+}
+
+pub fn stalk_uprobe(tx: EventSender, binary: String, symbol: String, ret: bool) {
+    tokio::task::spawn(async move {
+        let file_name = std::path::Path::new(&binary)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let label = format!("{file_name}:{symbol}");
+        let mut programs = vec![("stalk_uprobe", symbol.as_str())];
+        if ret {
+            programs.push(("stalk_uretprobe", symbol.as_str()));
+        }
+        let result = handle_uprobes(
+            &programs,
+            &[binary.into()],
+            &[("UPROBE_PAIRED", ret as u32)],
+            "UPROBE_EVENTS",
+            async move |raw_event: RawUprobeEvent| {
+                let mut event: UprobeEvent = raw_event.into();
+                event.probe = label.clone();
+                tx.send(StalkEvent::Uprobe(event)).await.unwrap();
+                Ok(())
+            },
+        )
+        .await;
+        if let Err(e) = result {
+            error!("probe {file_name}:{symbol} stopped: {e:#}");
+        }
+    });
+}
+
+/// USDT probes are `nop`s at addresses listed in the binary's notes, probed like any other
+/// instruction. Probes guarded by a semaphore only fire while something enables them, which
+/// takes a uprobe reference counter that isn't set here, so they are refused.
+pub fn stalk_usdt(tx: EventSender, binary: String, provider: String, name: String) {
+    tokio::task::spawn(async move {
+        if let Err(e) = poll_usdt(tx, binary, &provider, &name).await {
+            error!("USDT probe {provider}:{name} stopped: {e:#}");
+        }
+    });
+}
+
+async fn poll_usdt(
+    tx: EventSender,
+    binary: String,
+    provider: &str,
+    name: &str,
+) -> anyhow::Result<()> {
+    let (probe, offset) = find_probe(binary.as_ref(), provider, name)?;
+    if probe.semaphore != 0 {
+        anyhow::bail!("the probe is guarded by a semaphore, which can't be enabled");
+    }
+    let mut args = parse_args(&probe.args).unwrap_or_else(|e| {
+        warn!("not reporting arguments of USDT probe {provider}:{name}: {e:#}");
+        Vec::new()
+    });
+    if args.len() > UPROBE_ARGS {
+        warn!(
+            "reporting the first {UPROBE_ARGS} of {} arguments of USDT probe {provider}:{name}",
+            args.len()
+        );
+        args.truncate(UPROBE_ARGS);
+    }
+    let count = args.len();
+    let mut specs = [UsdtArgSpec::UNUSED; UPROBE_ARGS];
+    specs[..count].copy_from_slice(&args);
+    let arg_count = count as u32;
+    let mut loader = aya::EbpfLoader::new();
+    loader.set_global("USDT_ARGS", &specs, true);
+    loader.set_global("USDT_ARG_COUNT", &arg_count, true);
+    let mut ebpf = load_ebpf(&mut loader, &["stalk_usdt"])?;
+    let program: &mut UProbe = ebpf.program_mut("stalk_usdt").unwrap().try_into()?;
+    program.load()?;
+    program.attach(offset, &binary, None, None)?;
+    let label = format!("{provider}:{name}");
+    poll_events(
+        &mut ebpf,
+        "UPROBE_EVENTS",
+        async move |raw_event: RawUprobeEvent| {
+            let mut event: UprobeEvent = raw_event.into();
+            event.probe = label.clone();
+            event.args.truncate(count as usize);
+            tx.send(StalkEvent::Uprobe(event)).await.unwrap();
+            Ok(())
+        },
+    )
+    .await
+}
+
+/// Stacks are counted in the kernel and symbolized here every few seconds, as long as the
+/// processes are still around to read their memory maps.
+pub fn stalk_profile(tx: EventSender, hz: u64) {
+    tokio::task::spawn(async move {
+        if let Err(e) = poll_profile(tx, hz).await {
+            error!("profiler stopped: {e:#}");
+        }
+    });
+}
+
+async fn poll_profile(tx: EventSender, hz: u64) -> anyhow::Result<()> {
+    let mut ebpf = load_ebpf(&mut aya::EbpfLoader::new(), &["stalk_profile"])?;
+    let program: &mut PerfEvent = ebpf.program_mut("stalk_profile").unwrap().try_into()?;
+    program.load()?;
+    let cpus = aya::util::online_cpus().map_err(|(_, e)| e)?;
+    for cpu in cpus {
+        program.attach(
+            PerfTypeId::Software,
+            perf_sw_ids::PERF_COUNT_SW_CPU_CLOCK as u64,
+            PerfEventScope::AllProcessesOneCpu { cpu },
+            SamplePolicy::Frequency(hz),
+            true,
+        )?;
+    }
+    let mut counts: aya::maps::HashMap<_, ProfileKey, u64> =
+        aya::maps::HashMap::try_from(take_map(&mut ebpf, "PROFILE_COUNTS")?)?;
+    let mut stacks = aya::maps::StackTraceMap::try_from(take_map(&mut ebpf, "PROFILE_STACKS")?)?;
+    let mut symbolizer = symbolizer();
+    // Totals since the profiler started, as the kernel's counts are drained on every read.
+    let mut folded: HashMap<String, u64> = HashMap::new();
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
+    loop {
+        interval.tick().await;
+        let samples = drain(&mut counts);
+        let frames = samples
+            .iter()
+            .map(|(key, _)| {
+                sample_frames(
+                    &stacks,
+                    key.tgid,
+                    key.comm,
+                    key.user_stack,
+                    key.kernel_stack,
+                )
+            })
+            .collect();
+        forget_stacks(
+            &mut stacks,
+            samples
+                .iter()
+                .flat_map(|(key, _)| [key.user_stack, key.kernel_stack]),
+        );
+        let (returned, stacks_folded) = fold_stacks(symbolizer, frames).await?;
+        symbolizer = returned;
+        for (stack, (_, count)) in stacks_folded.into_iter().zip(samples) {
+            *folded.entry(stack).or_insert(0) += count;
+        }
+        tx.send(StalkEvent::Profile(
+            folded
+                .iter()
+                .map(|(stack, count)| (stack.clone(), *count))
+                .collect(),
+        ))
+        .await?;
+    }
+}
+
+/// OOM kills from `oom:mark_victim`, which runs in the task that triggered them, with
+/// `oom_kill_process` telling which cgroup, if any, hit its limit. Memory pressure is sampled
+/// alongside so that kills can be put in context.
+pub fn stalk_oom(tx: EventSender) {
+    let pressure_tx = tx.clone();
+    tokio::task::spawn(async move {
+        if let Err(e) = poll_oom(tx).await {
+            error!("OOM tracing stopped: {e:#}");
+        }
+    });
+    tokio::task::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
+        loop {
+            interval.tick().await;
+            let samples = tokio::task::spawn_blocking(sample_pressure)
+                .await
+                .unwrap_or_default();
+            if pressure_tx
+                .send(StalkEvent::MemoryPressure(samples))
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+}
+
+async fn poll_oom(tx: EventSender) -> anyhow::Result<()> {
+    let format = read_format("oom", "mark_victim")?;
+    // The victim's `comm` follows the fixed part of the record on kernels that have it.
+    let mut record_size = format.size();
+    if format.has_data_loc() {
+        record_size += 16;
+    }
+    let mut globals = vec![("OOM_RECORD_SIZE", record_size.min(OOM_MAX_RECORD) as u32)];
+    match Btf::kernel().and_then(|btf| memcg_offsets(&btf)) {
+        Ok(offsets) => globals.extend(offsets),
+        Err(e) => warn!("cgroups that hit their memory limit unknown: {e:#}"),
+    }
+    let mut ebpf = load_tracepoints(
+        &[("stalk_oom_mark_victim", ("oom", "mark_victim"))],
+        &globals,
+    )?;
+    let program: &mut KProbe = ebpf
+        .program_mut("stalk_oom_kill_process")
+        .unwrap()
+        .try_into()?;
+    program.load()?;
+    // `oom_kill_process` is static and may be inlined.
+    let kprobe_attached = match program.attach("oom_kill_process", 0) {
+        Ok(_) => true,
+        Err(e) => {
+            warn!("OOM constraints unknown: {e:#}");
+            false
+        }
+    };
+    poll_events(
+        &mut ebpf,
+        "OOM_EVENTS",
+        async move |raw_event: RawOomEvent| {
+            let mut event: OomEvent = raw_event.into();
+            decode_victim(&mut event, &format);
+            if !is_kill(&event, kprobe_attached) {
+                return Ok(());
+            }
+            if event.memcg_id != 0 {
+                event.cgroup = cgroup_path(event.memcg_id);
+            }
+            tx.send(StalkEvent::Oom(event)).await.unwrap();
+            Ok(())
+        },
+    )
+    .await
+}
+
+/// Retransmits and resets, plus RTT samples when `tcp_probe`'s format can be read. Owners are
+/// learned from `tcp_connect`, `inet_csk_accept` and `tcp_sendmsg`, which run in the context of
+/// the process using the socket.
+pub fn stalk_tcp_health(tx: EventSender) {
+    tokio::task::spawn(async move {
+        if let Err(e) = poll_tcp_health(tx).await {
+            error!("TCP health tracing stopped: {e:#}");
+        }
+    });
+}
+
+async fn poll_tcp_health(tx: EventSender) -> anyhow::Result<()> {
+    let mut programs = vec![
+        ("stalk_tcp_retransmit", ("tcp", "tcp_retransmit_skb")),
+        ("stalk_tcp_send_reset", ("tcp", "tcp_send_reset")),
+        ("stalk_tcp_receive_reset", ("tcp", "tcp_receive_reset")),
+    ];
+    let mut globals = Vec::new();
+    match read_format("tcp", "tcp_probe") {
+        Ok(format) => match format.field("srtt") {
+            Some(srtt) => {
+                programs.push(("stalk_tcp_probe", ("tcp", "tcp_probe")));
+                globals.push(("TCP_PROBE_SRTT_OFFSET", srtt.offset as u32));
+                if let Some(skaddr) = format.field("skaddr") {
+                    globals.push(("TCP_PROBE_SKADDR_OFFSET", skaddr.offset as u32));
+                }
+            }
+            None => warn!("RTT samples disabled: tcp_probe has no srtt field"),
+        },
+        Err(e) => warn!("RTT samples disabled: {e:#}"),
+    }
+    let mut ebpf = load_tracepoints(&programs, &globals)?;
+    for (name, function) in [
+        ("stalk_tcp_sendmsg", "tcp_sendmsg"),
+        ("stalk_tcp_connect", "tcp_connect"),
+        ("stalk_inet_csk_accept", "inet_csk_accept"),
+    ] {
+        let program: &mut KProbe = ebpf.program_mut(name).unwrap().try_into()?;
+        program.load()?;
+        program.attach(function, 0)?;
+    }
+    poll_events(
+        &mut ebpf,
+        "TCP_EVENTS",
+        async move |raw_event: RawTcpEvent| {
+            let event: TcpEvent = raw_event.into();
+            tx.send(StalkEvent::Tcp(event)).await.unwrap();
+            Ok(())
+        },
+    )
+    .await
+}
+
+/// Block requests from issue to completion, attributed to the process that submitted them.
+pub fn stalk_bio(tx: EventSender) {
+    tokio::task::spawn(async move {
+        if let Err(e) = poll_bio(tx).await {
+            error!("block I/O tracing stopped: {e:#}");
+        }
+    });
+}
+
+async fn poll_bio(tx: EventSender) -> anyhow::Result<()> {
+    // `rwbs` moved when `ioprio` was added to the record.
+    let rwbs_offset = match read_format("block", "block_rq_issue") {
+        Ok(format) => format
+            .field("rwbs")
+            .map(|field| field.offset as u32)
+            .ok_or(anyhow::anyhow!("block_rq_issue has no rwbs field"))?,
+        Err(e) => {
+            warn!("assuming block_rq_issue has no ioprio field: {e:#}");
+            32
+        }
+    };
+    handle_tracepoints(
+        &[
+            ("stalk_bio_queue", ("block", "block_bio_queue")),
+            ("stalk_rq_issue", ("block", "block_rq_issue")),
+            ("stalk_rq_complete", ("block", "block_rq_complete")),
+        ],
+        &[("BIO_RWBS_OFFSET", rwbs_offset)],
+        "BIO_EVENTS",
+        async move |raw_event: RawBioEvent| {
+            let event: BioEvent = raw_event.into();
+            tx.send(StalkEvent::Bio(event)).await.unwrap();
+            Ok(())
+        },
+    )
+    .await
+}
+
+/// Off-CPU time is summed in the kernel and symbolized like [`stalk_profile`].
+pub fn stalk_offcpu(tx: EventSender) {
+    tokio::task::spawn(async move {
+        if let Err(e) = poll_offcpu(tx).await {
+            error!("off-CPU tracing stopped: {e:#}");
+        }
+    });
+}
+
+async fn poll_offcpu(tx: EventSender) -> anyhow::Result<()> {
+    let mut ebpf = load_tracepoints(
+        &[
+            ("stalk_sched_switch", ("sched", "sched_switch")),
+            ("stalk_sched_wakeup", ("sched", "sched_wakeup")),
+        ],
+        &[],
+    )?;
+    let mut times: aya::maps::HashMap<_, OffCpuKey, u64> =
+        aya::maps::HashMap::try_from(take_map(&mut ebpf, "OFFCPU_TIME")?)?;
+    let starts: aya::maps::HashMap<_, u32, OffCpuStart> =
+        aya::maps::HashMap::try_from(take_map(&mut ebpf, "OFFCPU_START")?)?;
+    let mut stacks = aya::maps::StackTraceMap::try_from(take_map(&mut ebpf, "OFFCPU_STACKS")?)?;
+    let mut symbolizer = symbolizer();
+    // Totals since tracing started, as the kernel's are drained on every read.
+    let mut folded: HashMap<(u32, bool, String), u64> = HashMap::new();
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
+    loop {
+        interval.tick().await;
+        let samples = drain(&mut times);
+        let frames = samples
+            .iter()
+            .map(|(key, _)| {
+                sample_frames(
+                    &stacks,
+                    key.tgid,
+                    key.comm,
+                    key.user_stack,
+                    key.kernel_stack,
+                )
+            })
+            .collect();
+        // Threads still off-CPU are charged to their stacks when they come back.
+        let pending: std::collections::HashSet<i32> = starts
+            .iter()
+            .flatten()
+            .flat_map(|(_, start)| [start.key.user_stack, start.key.kernel_stack])
+            .collect();
+        forget_stacks(
+            &mut stacks,
+            samples
+                .iter()
+                .flat_map(|(key, _)| [key.user_stack, key.kernel_stack])
+                .filter(|id| !pending.contains(id)),
+        );
+        let (returned, stacks_folded) = fold_stacks(symbolizer, frames).await?;
+        symbolizer = returned;
+        for (mut stack, (key, ns)) in stacks_folded.into_iter().zip(samples) {
+            let runqueue = key.runqueue != 0;
+            if runqueue {
+                stack.push_str(";[runqueue]");
+            }
+            *folded.entry((key.tgid, runqueue, stack)).or_insert(0) += ns;
+        }
+        let stacks = folded
+            .iter()
+            .filter(|(_, ns)| **ns >= 1000)
+            .map(|((tgid, runqueue, stack), ns)| OffCpuStack {
+                tgid: *tgid,
+                comm: stack.split(';').next().unwrap_or_default().to_string(),
+                runqueue: *runqueue,
+                stack: stack.clone(),
+                us: ns / 1000,
+            })
+            .collect();
+        tx.send(StalkEvent::OffCpu(stacks)).await?;
+    }
+}
+
+fn symbolizer() -> Symbolizer {
+    let kernel = aya::util::kernel_symbols().unwrap_or_else(|e| {
+        warn!("kernel stacks won't be symbolized: {e}");
+        Default::default()
+    });
+    Symbolizer::new(kernel)
+}
+
+/// Instruction pointers of a stack, innermost first, or none if it couldn't be collected.
+fn stack_frames<T: std::borrow::Borrow<aya::maps::MapData>>(
+    stacks: &aya::maps::StackTraceMap<T>,
+    id: i32,
+) -> Vec<u64> {
+    if id < 0 {
+        return Vec::new();
+    }
+    stacks
+        .get(&(id as u32), 0)
+        .map(|stack| stack.frames().iter().map(|frame| frame.ip).collect())
+        .unwrap_or_default()
+}
+
+/// A sample's process and stacks, copied out of the stack trace map.
+type SampleFrames = (u32, [u8; 16], Vec<u64>, Vec<u64>);
+
+fn sample_frames(
+    stacks: &aya::maps::StackTraceMap<aya::maps::MapData>,
+    tgid: u32,
+    comm: [u8; 16],
+    user_stack: i32,
+    kernel_stack: i32,
+) -> SampleFrames {
+    (
+        tgid,
+        comm,
+        stack_frames(stacks, user_stack),
+        stack_frames(stacks, kernel_stack),
+    )
+}
+
+/// Folds `samples` in order on a blocking thread, as symbolizing reads binaries and process
+/// maps, and hands `symbolizer` back with the processes forgotten.
+async fn fold_stacks(
+    mut symbolizer: Symbolizer,
+    samples: Vec<SampleFrames>,
+) -> anyhow::Result<(Symbolizer, Vec<String>)> {
+    Ok(tokio::task::spawn_blocking(move || {
+        let folded = samples
+            .iter()
+            .map(|(tgid, comm, user, kernel)| symbolizer.fold(*tgid, comm, user, kernel))
+            .collect();
+        symbolizer.forget_processes();
+        (symbolizer, folded)
+    })
+    .await?)
+}
+
+/// Frees the stacks of drained samples so the map doesn't fill up. A sample taken between the
+/// drain and this loses its frames.
+fn forget_stacks(
+    stacks: &mut aya::maps::StackTraceMap<aya::maps::MapData>,
+    ids: impl IntoIterator<Item = i32>,
+) {
+    for id in ids {
+        if id >= 0 {
+            let _ = stacks.remove(&(id as u32));
+        }
+    }
+}
+
+/// Syscall totals are kept in kernel maps and copied to the state every second.
+pub fn stalk_syscalls(tx: EventSender) {
+    tokio::task::spawn(async move {
+        if let Err(e) = poll_syscalls(tx).await {
+            error!("syscall counting stopped: {e:#}");
+        }
+    });
+}
+
+async fn poll_syscalls(tx: EventSender) -> anyhow::Result<()> {
+    let mut ebpf = load_tracepoints(
+        &[
+            ("stalk_sys_enter", ("raw_syscalls", "sys_enter")),
+            ("stalk_sys_exit", ("raw_syscalls", "sys_exit")),
+        ],
+        &[
+            ("SYS_EXIT_NR", libc::SYS_exit as u32),
+            ("SYS_EXIT_GROUP_NR", libc::SYS_exit_group as u32),
+        ],
+    )?;
+    let mut active: aya::maps::Array<_, u32> =
+        aya::maps::Array::try_from(take_map(&mut ebpf, "SYSCALL_ACTIVE")?)?;
+    let mut stats_maps = Vec::new();
+    let mut errors_maps = Vec::new();
+    for i in 0..2 {
+        let stats: aya::maps::HashMap<_, SyscallKey, SyscallStats> =
+            aya::maps::HashMap::try_from(take_map(&mut ebpf, &format!("SYSCALL_STATS_{i}"))?)?;
+        let errors: aya::maps::HashMap<_, SyscallErrorKey, u64> =
+            aya::maps::HashMap::try_from(take_map(&mut ebpf, &format!("SYSCALL_ERRORS_{i}"))?)?;
+        stats_maps.push(stats);
+        errors_maps.push(errors);
+    }
+    let mut filling = 0;
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+    loop {
+        interval.tick().await;
+        let drained = filling;
+        filling ^= 1;
+        active.set(0, filling as u32, 0)?;
+        // Let exits that looked up the pair before the flip finish counting.
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        let stats = drain(&mut stats_maps[drained]);
+        let errors = drain(&mut errors_maps[drained]);
+        tx.send(StalkEvent::Syscalls(stats, errors)).await?;
+    }
+}
+
+/// Bytes of `__data_loc` data (strings and dynamic arrays) captured after the fixed part of a
+/// tracepoint record, within [`TRACEPOINT_MAX_RECORD`] in total. Longer data is cut short.
+const TRACEPOINT_DATA_LOC_SIZE: usize = 256;
+
+/// Any tracepoint, with `fields` (all of them when empty) decoded by name using the layout
+/// from its tracefs `format` file.
+pub fn stalk_tracepoint(tx: EventSender, category: String, name: String, fields: Vec<String>) {
+    tokio::task::spawn(async move {
+        let format = match read_format(&category, &name) {
+            Ok(format) => format,
+            Err(e) => {
+                warn!("tracepoint {category}:{name} disabled: {e:#}");
+                return;
+            }
+        };
+        let selected: Vec<_> = if fields.is_empty() {
+            format.event_fields().cloned().collect()
+        } else {
+            fields
+                .iter()
+                .filter_map(|field| {
+                    let found = format.field(field).cloned();
+                    if found.is_none() {
+                        warn!("tracepoint {category}:{name} has no field {field}");
+                    }
+                    found
+                })
+                .collect()
+        };
+        let mut record_size = format.size();
+        if format.has_data_loc() {
+            record_size += TRACEPOINT_DATA_LOC_SIZE;
+        }
+        let label = format!("{category}:{name}");
+        let result = handle_tracepoints(
+            &[("stalk_tracepoint", (category.as_str(), name.as_str()))],
+            &[(
+                "TRACEPOINT_RECORD_SIZE",
+                record_size.min(TRACEPOINT_MAX_RECORD) as u32,
+            )],
+            "TRACEPOINT_EVENTS",
+            async move |raw_event: RawTracepointEvent| {
+                let mut event: TracepointEvent = raw_event.into();
+                event.tracepoint = label.clone();
+                event.fields = selected
+                    .iter()
+                    .map(|field| {
+                        let value = decode_field(&event.record, field).unwrap_or_default();
+                        (field.name.clone(), value)
+                    })
+                    .collect();
+                tx.send(StalkEvent::Tracepoint(event)).await.unwrap();
+                Ok(())
+            },
+        )
+        .await;
+        if let Err(e) = result {
+            error!("tracepoint {category}:{name} stopped: {e:#}");
+        }
+    });
+}
+
+pub fn stalk_creds(tx: EventSender) {
+    tokio::task::spawn(async move {
+        if let Err(e) = poll_creds(tx).await {
+            error!("credential tracing stopped: {e:#}");
+        }
+    });
+}
+
+async fn poll_creds(tx: EventSender) -> anyhow::Result<()> {
+    let btf = Btf::kernel()?;
+    let mut globals = task_offsets(&btf)?;
+    globals.push(("CRED_IDS_OFFSET", btf.offset("cred", "uid")?));
+    globals.push(("CRED_CAPS_OFFSET", btf.offset("cred", "cap_inheritable")?));
+    handle_kprobes(
+        &[("stalk_commit_creds", "commit_creds")],
+        &globals,
+        "CREDS_EVENTS",
+        async move |raw_event: RawCredsEvent| {
+            let event: CredsEvent = raw_event.into();
+            tx.send(StalkEvent::Creds(event)).await.unwrap();
+            Ok(())
+        },
+    )
+    .await
+}
+
+/// Offsets of the `task_struct` fields the eBPF object reads through its `task` module.
+fn task_offsets(btf: &Btf) -> anyhow::Result<Vec<(&'static str, u32)>> {
+    Ok(vec![
+        ("TASK_PID_OFFSET", btf.offset("task_struct", "pid")?),
+        ("TASK_TGID_OFFSET", btf.offset("task_struct", "tgid")?),
+        (
+            "TASK_GROUP_LEADER_OFFSET",
+            btf.offset("task_struct", "group_leader")?,
+        ),
+        ("TASK_COMM_OFFSET", btf.offset("task_struct", "comm")?),
+        (
+            "TASK_REAL_CRED_OFFSET",
+            btf.offset("task_struct", "real_cred")?,
+        ),
+        ("TASK_FILES_OFFSET", btf.offset("task_struct", "files")?),
+        ("FILES_FDT_OFFSET", btf.offset("files_struct", "fdt")?),
+        ("FDTABLE_MAX_FDS_OFFSET", btf.offset("fdtable", "max_fds")?),
+        ("FDTABLE_FD_OFFSET", btf.offset("fdtable", "fd")?),
+    ])
+}
+
+/// Where `stalk_mprotect_fixup` finds the file a VMA maps, and the name of that file.
+fn execmem_offsets(btf: &Btf) -> anyhow::Result<Vec<(&'static str, u32)>> {
+    Ok(vec![
+        (
+            "MPROTECT_FIXUP_VMA_ARG",
+            btf.param("mprotect_fixup", "vma")?,
+        ),
+        ("VMA_FILE_OFFSET", btf.offset("vm_area_struct", "vm_file")?),
+        ("FILE_DENTRY_OFFSET", btf.offset("file", "f_path.dentry")?),
+        ("DENTRY_NAME_OFFSET", btf.offset("dentry", "d_name.name")?),
+    ])
+}
+
+/// Where `stalk_oom_kill_process` finds the id of the memory cgroup that hit its limit.
+fn memcg_offsets(btf: &Btf) -> anyhow::Result<Vec<(&'static str, u32)>> {
+    Ok(vec![
+        (
+            "OOM_CONTROL_MEMCG_OFFSET",
+            btf.offset("oom_control", "memcg")?,
+        ),
+        (
+            "MEMCG_CGROUP_OFFSET",
+            btf.offset("mem_cgroup", "css.cgroup")?,
+        ),
+        ("CGROUP_KN_OFFSET", btf.offset("cgroup", "kn")?),
+        ("KERNFS_NODE_ID_OFFSET", btf.offset("kernfs_node", "id")?),
+    ])
+}
+
+/// Where `stalk_perms_notify_change` finds the mode of the inode being changed.
+fn notify_change_offsets(btf: &Btf) -> anyhow::Result<Vec<(&'static str, u32)>> {
+    Ok(vec![
+        (
+            "NOTIFY_CHANGE_DENTRY_ARG",
+            btf.param("notify_change", "dentry")?,
+        ),
+        ("DENTRY_INODE_OFFSET", btf.offset("dentry", "d_inode")?),
+        ("INODE_MODE_OFFSET", btf.offset("inode", "i_mode")?),
+    ])
+}
+
 async fn handle_tracepoint<F: crate::event::RawEvent>(
     program: &str,
     attach_point: (&str, &str),
     event_map: &str,
     func: impl AsyncFn(F) -> anyhow::Result<()>,
 ) -> anyhow::Result<()> {
-    let mut ebpf = aya::Ebpf::load(aya::include_bytes_aligned!(concat!(
-        env!("OUT_DIR"),
-        "/stalk"
-    )))?;
-    init_ebpf(&mut ebpf)?;
-    let program: &mut TracePoint = ebpf.program_mut(program).unwrap().try_into()?;
-    program.load()?;
-    program.attach(attach_point.0, attach_point.1)?;
-    let ring_buf = RingBuf::try_from(
-        ebpf.map_mut(event_map)
-            .ok_or(anyhow::anyhow!("Failed to find map {}", event_map))?,
-    )?;
-    let mut async_array_buf = AsyncFd::with_interest(ring_buf, tokio::io::Interest::READABLE)?;
-    loop {
-        let mut guard = async_array_buf.readable_mut().await?;
-        let events = guard.get_inner_mut();
-        while let Some(item) = events.next() {
-            let ptr = item.as_ptr() as *const F;
-            let raw_event = unsafe { ptr.read_unaligned() };
-            func(raw_event).await?;
+    handle_tracepoints(&[(program, attach_point)], &[], event_map, func).await
+}
+
+/// Attaches several programs sharing `event_map` from a single eBPF instance, after setting
+/// `globals` in the loaded object.
+async fn handle_tracepoints<F: crate::event::RawEvent>(
+    programs: &[(&str, (&str, &str))],
+    globals: &[(&str, u32)],
+    event_map: &str,
+    func: impl AsyncFn(F) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut ebpf = load_tracepoints(programs, globals)?;
+    poll_events(&mut ebpf, event_map, func).await
+}
+
+/// Loads the object and attaches `programs`, for callers that read its maps themselves.
+/// A program listed more than once is loaded once and attached to each tracepoint.
+fn load_tracepoints(
+    programs: &[(&str, (&str, &str))],
+    globals: &[(&str, u32)],
+) -> anyhow::Result<aya::Ebpf> {
+    let mut loader = aya::EbpfLoader::new();
+    for (name, value) in globals {
+        loader.set_global(name, value, true);
+    }
+    let names: Vec<_> = programs.iter().map(|(name, _)| *name).collect();
+    let mut ebpf = load_ebpf(&mut loader, &names)?;
+    let mut loaded = Vec::new();
+    for (name, attach_point) in programs {
+        if let Err(e) = validate_layout(name, *attach_point) {
+            error!("refusing to attach {name}: {e:#}");
+            return Err(e);
+        }
+        let program: &mut TracePoint = ebpf.program_mut(name).unwrap().try_into()?;
+        if !loaded.contains(name) {
+            program.load()?;
+            loaded.push(*name);
+        }
+        program.attach(attach_point.0, attach_point.1)?;
+    }
+    Ok(ebpf)
+}
+
+/// Compares the struct `program` reads its context through with the tracepoint's format file.
+/// When the format can't be read the layout is trusted as compiled.
+fn validate_layout(program: &str, (category, name): (&str, &str)) -> anyhow::Result<()> {
+    let Some(layout) = expected_layout(program) else {
+        return Ok(());
+    };
+    match read_format(category, name) {
+        Ok(format) => check_layout(&format, &layout.fields)
+            .with_context(|| format!("{} does not match {category}:{name}", layout.ty)),
+        Err(e) => {
+            warn!(
+                "cannot check {} against {category}:{name}: {e:#}",
+                layout.ty
+            );
+            Ok(())
         }
-        guard.clear_ready();
     }
 }
 
+/// Attaches each `(program, syscall)` to `sys_enter_<syscall>` and `exit_program` to the
+/// matching `sys_exit_<syscall>`, along with any `extra` tracepoints.
+async fn handle_syscalls<F: crate::event::RawEvent>(
+    syscalls: &[(&str, &str)],
+    exit_program: &str,
+    extra: &[(&str, (&str, &str))],
+    event_map: &str,
+    func: impl AsyncFn(F) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut ebpf = load_syscalls(syscalls, exit_program, extra, &[])?;
+    poll_events(&mut ebpf, event_map, func).await
+}
+
+/// Loads the object and attaches syscalls like [`handle_syscalls`], for callers that attach
+/// more programs. Syscalls the running kernel has no tracepoint for are skipped.
+fn load_syscalls(
+    syscalls: &[(&str, &str)],
+    exit_program: &str,
+    extra: &[(&str, (&str, &str))],
+    globals: &[(&str, u32)],
+) -> anyhow::Result<aya::Ebpf> {
+    let syscalls: Vec<_> = syscalls
+        .iter()
+        .filter(|(_, syscall)| {
+            let found = has_tracepoint("syscalls", &format!("sys_enter_{syscall}"));
+            if !found {
+                warn!("not tracing {syscall}: no such syscall tracepoint");
+            }
+            found
+        })
+        .collect();
+    let tracepoints: Vec<_> = syscalls
+        .iter()
+        .map(|(_, syscall)| {
+            (
+                format!("sys_enter_{syscall}"),
+                format!("sys_exit_{syscall}"),
+            )
+        })
+        .collect();
+    let mut programs = Vec::new();
+    for ((program, _), (enter, exit)) in syscalls.iter().zip(&tracepoints) {
+        programs.push((*program, ("syscalls", enter.as_str())));
+        programs.push((exit_program, ("syscalls", exit.as_str())));
+    }
+    programs.extend_from_slice(extra);
+    load_tracepoints(&programs, globals)
+}
+
+async fn handle_kprobe<F: crate::event::RawEvent>(
+    program: &str,
+    function: &str,
+    event_map: &str,
+    func: impl AsyncFn(F) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    handle_kprobes(&[(program, function)], &[], event_map, func).await
+}
+
+/// Attaches several kprobes or kretprobes sharing `event_map` from a single eBPF instance,
+/// after setting `globals` in the loaded object.
+async fn handle_kprobes<F: crate::event::RawEvent>(
+    programs: &[(&str, &str)],
+    globals: &[(&str, u32)],
+    event_map: &str,
+    func: impl AsyncFn(F) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut loader = aya::EbpfLoader::new();
+    for (name, value) in globals {
+        loader.set_global(name, value, true);
+    }
+    let names: Vec<_> = programs.iter().map(|(name, _)| *name).collect();
+    let mut ebpf = load_ebpf(&mut loader, &names)?;
+    for (program, function) in programs {
+        let program: &mut KProbe = ebpf.program_mut(program).unwrap().try_into()?;
+        program.load()?;
+        program.attach(function, 0)?;
+    }
+    poll_events(&mut ebpf, event_map, func).await
+}
+
+/// Attaches uprobes or uretprobes on `(program, symbol or offset)` to every binary in `targets`, after
+/// setting `globals` like [`handle_kprobes`]. A target failing to attach is skipped, but a
+/// program attached to none of them is an error.
+async fn handle_uprobes<'a, F: crate::event::RawEvent, L>(
+    programs: &[(&str, L)],
+    targets: &[std::path::PathBuf],
+    globals: &[(&str, u32)],
+    event_map: &str,
+    func: impl AsyncFn(F) -> anyhow::Result<()>,
+) -> anyhow::Result<()>
+where
+    L: Copy + Into<UProbeAttachLocation<'a>>,
+{
+    let mut loader = aya::EbpfLoader::new();
+    for (name, value) in globals {
+        loader.set_global(name, value, true);
+    }
+    let names: Vec<_> = programs.iter().map(|(name, _)| *name).collect();
+    let mut ebpf = load_ebpf(&mut loader, &names)?;
+    let mut loaded = Vec::new();
+    for (name, location) in programs {
+        let program: &mut UProbe = ebpf.program_mut(name).unwrap().try_into()?;
+        if !loaded.contains(name) {
+            program.load()?;
+            loaded.push(*name);
+        }
+        let mut attached = false;
+        for target in targets {
+            match program.attach(*location, target, None, None) {
+                Ok(_) => attached = true,
+                Err(e) => warn!("failed to attach {name} to {}: {e}", target.display()),
+            }
+        }
+        if !attached {
+            anyhow::bail!("{name} could not be attached to any target");
+        }
+    }
+    poll_events(&mut ebpf, event_map, func).await
+}
+
 async fn handle_xdp<F: crate::event::RawEvent>(
     program: &str,
     attach_point: (&str, XdpFlags),
     event_map: &str,
     func: impl AsyncFn(F) -> anyhow::Result<()>,
 ) -> anyhow::Result<()> {
-    let mut ebpf = aya::Ebpf::load(aya::include_bytes_aligned!(concat!(
-        env!("OUT_DIR"),
-        "/stalk"
-    )))?;
-    init_ebpf(&mut ebpf)?;
+    let mut ebpf = load_ebpf(&mut aya::EbpfLoader::new(), &[program])?;
     let program: &mut Xdp = ebpf.program_mut(program).unwrap().try_into()?;
     program.load()?;
     program.attach(attach_point.0, attach_point.1)?;
+    poll_events(&mut ebpf, event_map, func).await
+}
+
+async fn poll_events<F: crate::event::RawEvent>(
+    ebpf: &mut aya::Ebpf,
+    event_map: &str,
+    func: impl AsyncFn(F) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
     let ring_buf = RingBuf::try_from(
         ebpf.map_mut(event_map)
             .ok_or(anyhow::anyhow!("Failed to find map {}", event_map))?,
@@ -217,6 +1700,66 @@ async fn handle_xdp<F: crate::event::RawEvent>(
     }
 }
 
+/// Takes `name` out of `ebpf`, for maps read alongside others.
+fn take_map(ebpf: &mut aya::Ebpf, name: &str) -> anyhow::Result<aya::maps::Map> {
+    ebpf.take_map(name)
+        .ok_or(anyhow::anyhow!("Failed to find map {name}"))
+}
+
+/// Reads and deletes every entry of `map`.
+fn drain<K: aya::Pod, V: aya::Pod>(
+    map: &mut aya::maps::HashMap<aya::maps::MapData, K, V>,
+) -> Vec<(K, V)> {
+    let keys: Vec<K> = map.keys().flatten().collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            let value = map.get(&key, 0).ok()?;
+            let _ = map.remove(&key);
+            Some((key, value))
+        })
+        .collect()
+}
+
+/// Maps too large to preallocate in every copy of the object, with the program that needs
+/// them at full size. Each item loads a copy of its own, and items without that program
+/// shrink them to a single entry.
+const LARGE_MAPS: [(&str, &str); 11] = [
+    ("PROFILE_STACKS", "stalk_profile"),
+    ("PROFILE_COUNTS", "stalk_profile"),
+    ("OFFCPU_STACKS", "stalk_sched_switch"),
+    ("OFFCPU_START", "stalk_sched_switch"),
+    ("OFFCPU_TIME", "stalk_sched_switch"),
+    ("SYSCALL_STATS_0", "stalk_sys_exit"),
+    ("SYSCALL_STATS_1", "stalk_sys_exit"),
+    ("SYSCALL_ERRORS_0", "stalk_sys_exit"),
+    ("SYSCALL_ERRORS_1", "stalk_sys_exit"),
+    ("SOCK_OWNERS", "stalk_tcp_retransmit"),
+    ("TCP_RTT_LAST", "stalk_tcp_probe"),
+];
+
+/// Ring buffers shrunk like [`LARGE_MAPS`], to one page as their size must be a multiple of
+/// it.
+const LARGE_RING_BUFS: [(&str, &str); 1] = [("TLS_EVENTS", "stalk_ssl_ret")];
+
+/// Loads the object for an item running `programs`, with the [`LARGE_MAPS`] and
+/// [`LARGE_RING_BUFS`] none of them uses shrunk.
+fn load_ebpf(loader: &mut aya::EbpfLoader<'_>, programs: &[&str]) -> anyhow::Result<aya::Ebpf> {
+    let page_size = aya::util::page_size() as u32;
+    for (maps, size) in [(&LARGE_MAPS[..], 1), (&LARGE_RING_BUFS[..], page_size)] {
+        for (map, program) in maps {
+            if !programs.contains(program) {
+                loader.set_max_entries(map, size);
+            }
+        }
+    }
+    let mut ebpf = loader.load(aya::include_bytes_aligned!(concat!(
+        env!("OUT_DIR"),
+        "/stalk"
+    )))?;
+    init_ebpf(&mut ebpf)?;
+    Ok(ebpf)
+}
+
 fn init_ebpf(ebpf: &mut aya::Ebpf) -> anyhow::Result<()> {
     match aya_log::EbpfLogger::init(ebpf) {
         Err(e) => {